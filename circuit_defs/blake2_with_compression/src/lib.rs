@@ -15,6 +15,16 @@ pub const NUM_DELEGATION_CYCLES: usize = DOMAIN_SIZE - 1;
 pub const LDE_FACTOR: usize = 2;
 pub const LDE_SOURCE_COSETS: &[usize] = &[0, 1];
 pub const TREE_CAP_SIZE: usize = 32;
+// read from the generated layout, so they follow it when it is regenerated
+pub const WITNESS_LAYOUT_WIDTH: usize = compiled_layout::VERIFIER_COMPILED_LAYOUT
+    .witness_layout
+    .total_width;
+pub const MEMORY_LAYOUT_WIDTH: usize = compiled_layout::VERIFIER_COMPILED_LAYOUT
+    .memory_layout
+    .total_width;
+pub const SETUP_LAYOUT_WIDTH: usize = compiled_layout::VERIFIER_COMPILED_LAYOUT
+    .setup_layout
+    .total_width;
 
 fn serialize_to_file<T: serde::Serialize>(el: &T, filename: &str) {
     let mut dst = std::fs::File::create(filename).unwrap();
@@ -69,6 +79,13 @@ pub fn get_table_driver() -> prover::cs::tables::TableDriver<Mersenne31Field> {
     blake2_with_extended_control_delegation_circuit_create_table_driver()
 }
 
+mod compiled_layout {
+    use crate::Mersenne31Field;
+    use prover::cs::definitions::*;
+
+    include!("../generated/circuit_layout.rs");
+}
+
 mod sealed {
     use crate::Mersenne31Field;
     use prover::cs::cs::witness_placer::*;
@@ -14,6 +14,16 @@ pub const NUM_CYCLES: usize = DOMAIN_SIZE - 1;
 pub const LDE_FACTOR: usize = 2;
 pub const LDE_SOURCE_COSETS: &[usize] = &[0, 1];
 pub const TREE_CAP_SIZE: usize = 32;
+// read from the generated layout, so they follow it when it is regenerated
+pub const WITNESS_LAYOUT_WIDTH: usize = compiled_layout::VERIFIER_COMPILED_LAYOUT
+    .witness_layout
+    .total_width;
+pub const MEMORY_LAYOUT_WIDTH: usize = compiled_layout::VERIFIER_COMPILED_LAYOUT
+    .memory_layout
+    .total_width;
+pub const SETUP_LAYOUT_WIDTH: usize = compiled_layout::VERIFIER_COMPILED_LAYOUT
+    .setup_layout
+    .total_width;
 pub const MAX_ROM_SIZE: usize = 1 << 21; // bytes
 pub const ROM_ADDRESS_SPACE_SECOND_WORD_BITS: usize = (MAX_ROM_SIZE.trailing_zeros() - 16) as usize;
 
@@ -115,6 +125,13 @@ pub fn get_table_driver_for_rom_bound<const ROM_ADDRESS_SPACE_SECOND_WORD_BITS:
     table_driver
 }
 
+mod compiled_layout {
+    use crate::Mersenne31Field;
+    use prover::cs::definitions::*;
+
+    include!("../generated/circuit_layout.rs");
+}
+
 mod sealed {
     use crate::Mersenne31Field;
     use prover::cs::cs::placeholder::Placeholder;
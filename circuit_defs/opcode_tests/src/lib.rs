@@ -27,7 +27,7 @@ mod opcodes {
     mod bne;
     mod div;
     mod divu;
-    // mod fence;
+    mod fence;
     mod lbu;
     mod lh;
     mod lhu;
@@ -42,6 +42,7 @@ mod opcodes {
     mod rem;
     mod remu;
     mod sh;
+    mod shift_amounts;
     mod sll;
     mod slli;
     mod slt;
@@ -63,6 +64,8 @@ mod opcodes {
 use cs::cs::cs_reference::BasicAssembly;
 use cs::machine::machine_configurations::create_csr_table_for_delegation;
 use cs::machine::machine_configurations::full_isa_with_delegation_no_exceptions::FullIsaMachineWithDelegationNoExceptionHandling;
+use cs::machine::machine_configurations::minimal_no_exceptions_with_delegation::MinimalMachineNoExceptionHandlingWithDelegation;
+use cs::machine::BaseMachineState;
 use cs::machine::Machine;
 use cs::machine::UNIMP_OPCODE;
 use cs::tables::LookupWrapper;
@@ -78,6 +81,7 @@ use risc_v_simulator::abstractions::tracer::Tracer;
 use risc_v_simulator::cycle::state::RiscV32State;
 use risc_v_simulator::cycle::state::NUM_REGISTERS;
 use risc_v_simulator::cycle::IMStandardIsaConfig;
+use risc_v_simulator::cycle::IWithoutByteAccessIsaConfigWithDelegation;
 use risc_v_simulator::cycle::MachineConfig;
 use risc_v_simulator::delegations::DelegationsCSRProcessor;
 use risc_v_simulator::mmu::NoMMU;
@@ -119,20 +123,17 @@ fn broken_tests() {
         final_register: Option<(usize, u32)>,
     ) {
         assert!(alternative_instruction_bytecode.is_some() && final_register.is_none());
-        assert!(std::panic::catch_unwind(|| test_single_opcode(
-            instruction,
-            alternative_instruction_bytecode,
-            initial_registers,
-            final_register
-        ))
-        .unwrap_err()
-        .downcast_ref::<String>()
-        .unwrap()
-        .starts_with("unsatisfied"));
+        assert_unsatisfied(|| {
+            test_single_opcode(
+                instruction,
+                alternative_instruction_bytecode,
+                initial_registers,
+                final_register,
+            )
+        });
     }
 
     // opcodes that we don't support
-    const FENCE_OPCODE: u32 = 0x140000f;
     const ECALL_OPCODE: u32 = 0x73;
     const EBREAK_OPCODE: u32 = 0x100073;
     const CSRRW_UNSUPPORTED_OPCODE: u32 = UNIMP_OPCODE;
@@ -142,8 +143,6 @@ fn broken_tests() {
     const CSRRSI_OPCODE: u32 = 0x116073;
     const CSRRCI_OPCODE: u32 = 0x117073;
     const FADDS_OPCODE: u32 = 0x2081d3;
-    broken_test_impl("fence w, r", Some(FENCE_OPCODE), [0; 32], None);
-    broken_test_impl("fence w, r", Some(FENCE_OPCODE), [0; 32], None);
     broken_test_impl("ecall", Some(ECALL_OPCODE), [0; 32], None);
     broken_test_impl("ebreak", Some(EBREAK_OPCODE), [0; 32], None);
     broken_test_impl(
@@ -227,6 +226,48 @@ fn broken_tests() {
     );
 }
 
+#[test]
+fn reduced_machine_word_access_alignment() {
+    type C = IWithoutByteAccessIsaConfigWithDelegation;
+    type M = MinimalMachineNoExceptionHandlingWithDelegation;
+
+    // base address is in RAM, above the ROM bound
+    let registers = {
+        let mut xs = [0; 32];
+        xs[1] = 1 << 21;
+        xs[2] = 0x12345678;
+        xs
+    };
+    test_single_opcode_for_machine::<C, M>("lw, x3, 0(x1)", None, registers, Some((3, 0)));
+    test_single_opcode_for_machine::<C, M>("sw, x2, 4(x1)", None, registers, None);
+    for offset in 1..4 {
+        assert_unsatisfied(|| {
+            test_single_opcode_for_machine::<C, M>(
+                &format!("lw, x3, {offset}(x1)"),
+                None,
+                registers,
+                None,
+            )
+        });
+        assert_unsatisfied(|| {
+            test_single_opcode_for_machine::<C, M>(
+                &format!("sw, x2, {offset}(x1)"),
+                None,
+                registers,
+                None,
+            )
+        });
+    }
+}
+
+fn assert_unsatisfied(test: impl FnOnce() + std::panic::UnwindSafe) {
+    assert!(std::panic::catch_unwind(test)
+        .unwrap_err()
+        .downcast_ref::<String>()
+        .unwrap()
+        .starts_with("unsatisfied"));
+}
+
 /// 1. simulate
 /// 2. resolve circuit
 /// 3. check they're same
@@ -236,6 +277,27 @@ pub fn test_single_opcode(
     initial_registers: [u32; NUM_REGISTERS],
     final_register: Option<(usize, u32)>,
 ) {
+    test_single_opcode_for_machine::<
+        IMStandardIsaConfig,
+        FullIsaMachineWithDelegationNoExceptionHandling,
+    >(
+        instruction,
+        alternative_instruction_bytecode,
+        initial_registers,
+        final_register,
+    )
+}
+
+/// Same as [test_single_opcode], but for a given circuit machine and the simulator config it proves
+pub fn test_single_opcode_for_machine<C: MachineConfig, M: Machine<Mersenne31Field>>(
+    instruction: &str,
+    alternative_instruction_bytecode: Option<u32>,
+    initial_registers: [u32; NUM_REGISTERS],
+    final_register: Option<(usize, u32)>,
+) where
+    [(); { <M as Machine<Mersenne31Field>>::ASSUME_TRUSTED_CODE } as usize]:,
+    [(); { <M as Machine<Mersenne31Field>>::OUTPUT_EXACT_EXCEPTIONS } as usize]:,
+{
     dbg!(instruction);
     let bytecode = if let Some(b) = alternative_instruction_bytecode {
         vec![b]
@@ -248,8 +310,8 @@ pub fn test_single_opcode(
     const MAX_RAM: usize = 1 << 24;
     const START_ROM: u32 = 0;
     const ROM_BYTECODE_PADDING: &[u32] = &[UNIMP_OPCODE; (MAX_ROM / 4) - 4]; // necessary for ROM lookup table
-    let mut state: RiscV32State<IMStandardIsaConfig> = {
-        let mut state = RiscV32State::<IMStandardIsaConfig>::initial(ENTRY_POINT);
+    let mut state: RiscV32State<C> = {
+        let mut state = RiscV32State::<C>::initial(ENTRY_POINT);
         state.registers = initial_registers;
         state
     };
@@ -260,9 +322,8 @@ pub fn test_single_opcode(
         }
         memory_source
     };
-    let mut tracer: GPUFriendlyTracer<IMStandardIsaConfig> = {
-        let delegation_factories =
-            setups::delegation_factories_for_machine::<IMStandardIsaConfig, Global>();
+    let mut tracer: GPUFriendlyTracer<C> = {
+        let delegation_factories = setups::delegation_factories_for_machine::<C, Global>();
 
         let ram_tracer = RamTracingData::new_for_ram_size_and_rom_bound(1 << 30, MAX_ROM); // use 1 GB RAM
         let delegation_tracer = DelegationTracingData {
@@ -332,8 +393,6 @@ pub fn test_single_opcode(
             // CSR ops - since we crashed it's not non-determinism and it's not supported delegation. we're forced to use dummy reg write but this is wrong
             // WARNING: currently simulator also crashes for supported csr if we don't use abi correctly, and we are patching those too!
             0b1110011 if f3 != 0b000 => set_dummy_witness(),
-            // FENCE
-            0b0001111 => (),
             // FADD.S
             0b1010011 if f7 == 0b0000000 => {
                 state.set_register(rd_index, (rs1 as f32 + rs2 as f32) as u32, &mut tracer)
@@ -345,11 +404,11 @@ pub fn test_single_opcode(
     assert!(cs::cs::cs_reference::RESOLVE_WITNESS); // don't wanna deal with this problem again
     let csr_table = create_csr_table_for_delegation::<Mersenne31Field>(
         true,
-        IMStandardIsaConfig::ALLOWED_DELEGATION_CSRS,
+        C::ALLOWED_DELEGATION_CSRS,
         TableType::SpecialCSRProperties.to_table_id(),
     );
     let mut cs = {
-        let oracle: MainRiscVOracle<'static, IMStandardIsaConfig> = unsafe {
+        let oracle: MainRiscVOracle<'static, C> = unsafe {
             std::mem::transmute(MainRiscVOracle {
                 cycle_data: &tracer.trace_chunk,
             })
@@ -357,14 +416,13 @@ pub fn test_single_opcode(
         BasicAssembly::<Mersenne31Field>::new_with_oracle(oracle)
     };
     let (circuit_pc_prev, circuit_pc) = {
-        let (state_prev, state_next) =
-            FullIsaMachineWithDelegationNoExceptionHandling::run_single_cycle::<SECOND_WORD_BITS>(
-                &bytecode,
-                &mut cs,
-                Some(LookupWrapper::Dimensional3(csr_table)),
-            );
-        let pc_prev = state_prev.pc.get_value_unsigned(&cs).unwrap();
-        let pc_next = state_next.pc.get_value_unsigned(&cs).unwrap();
+        let (state_prev, state_next) = M::run_single_cycle::<SECOND_WORD_BITS>(
+            &bytecode,
+            &mut cs,
+            Some(LookupWrapper::Dimensional3(csr_table)),
+        );
+        let pc_prev = state_prev.get_pc().get_value_unsigned(&cs).unwrap();
+        let pc_next = state_next.get_pc().get_value_unsigned(&cs).unwrap();
         (pc_prev, pc_next)
     };
     let (circuit_registers, circuit_mem_accesses) = {
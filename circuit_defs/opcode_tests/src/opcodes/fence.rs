@@ -0,0 +1,16 @@
+#[test]
+fn test() {
+    const FENCE_W_R_OPCODE: u32 = 0x0140000f;
+    const FENCE_IORW_IORW_OPCODE: u32 = 0x0ff0000f;
+    const FENCE_TSO_OPCODE: u32 = 0x8330000f;
+    const FENCE_I_OPCODE: u32 = 0x0000100f;
+    crate::test_single_opcode("fence w, r", Some(FENCE_W_R_OPCODE), [0; 32], None);
+    crate::test_single_opcode(
+        "fence iorw, iorw",
+        Some(FENCE_IORW_IORW_OPCODE),
+        [0; 32],
+        None,
+    );
+    crate::test_single_opcode("fence.tso", Some(FENCE_TSO_OPCODE), [0; 32], None);
+    crate::test_single_opcode("fence.i", Some(FENCE_I_OPCODE), [0; 32], None);
+}
@@ -13,4 +13,24 @@ impl crate::TestCase for Test {
 #[test]
 fn test() {
     <Test as crate::TestCase>::test();
+    // only the first operand is signed
+    for (rs1, rs2, expected) in [
+        (i32::MIN as u32, u32::MAX, 0x80000000),
+        (i32::MIN as u32, 0x80000000, 0xc0000000),
+        (-1_i32 as u32, u32::MAX, u32::MAX),
+        (i32::MIN as u32, 0, 0),
+        (0, u32::MAX, 0),
+    ] {
+        crate::test_single_opcode(
+            "mulhsu, x3, x1, x2",
+            None,
+            {
+                let mut xs = [0; 32];
+                xs[1] = rs1;
+                xs[2] = rs2;
+                xs
+            },
+            Some((3, expected)),
+        );
+    }
 }
@@ -0,0 +1,32 @@
+#[test]
+fn test() {
+    let operands = [0x0000_0001, 0x1234_5678, 0x8765_4321, 0xffff_ffff];
+    // amounts above 31 are masked to the low 5 bits
+    let shift_amounts = (0..32).chain([32, 33, 63, 0x100, 0xffff_ffe1]);
+    for shift_amount in shift_amounts {
+        for input in operands {
+            let mut registers = [0; 32];
+            registers[1] = input;
+            registers[2] = shift_amount;
+            let masked = shift_amount & 0b1_1111;
+            crate::test_single_opcode(
+                "sll, x3, x1, x2",
+                None,
+                registers,
+                Some((3, input << masked)),
+            );
+            crate::test_single_opcode(
+                "srl, x3, x1, x2",
+                None,
+                registers,
+                Some((3, input >> masked)),
+            );
+            crate::test_single_opcode(
+                "sra, x3, x1, x2",
+                None,
+                registers,
+                Some((3, ((input as i32) >> masked) as u32)),
+            );
+        }
+    }
+}
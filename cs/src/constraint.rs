@@ -18,9 +18,24 @@ use crate::cs::circuit::Circuit;
 use crate::definitions::*;
 use crate::types::{Boolean, Num};
 use field::PrimeField;
+use std::collections::HashMap;
 
 pub const TERM_INNER_CAPACITY: usize = 4;
 
+/// Invariant violated during [Term::try_normalize] or [Constraint::try_normalize].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NormalizeError<F: PrimeField> {
+    /// Some slot of the term past its degree holds a variable instead of a placeholder
+    NonPlaceholderInUnusedSlot { term: Term<F> },
+    /// After combining like terms the constraint is still of degree larger than 2
+    DegreeTooHigh { term: Term<F> },
+    /// Combining like terms must never increase the degree
+    DegreeIncreased {
+        initial_degree: usize,
+        final_degree: usize,
+    },
+}
+
 // #[derive(Clone, Debug, Copy, PartialEq, Eq)]
 #[derive(Clone, Copy, PartialEq, Eq)]
 
@@ -40,8 +55,17 @@ impl<F: PrimeField> PartialOrd for Term<F> {
     }
 }
 
+#[cfg(test)]
+thread_local! {
+    /// Number of [Term] comparisons made on this thread, so tests can check how much sorting work is done
+    static TERM_COMPARISONS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
 impl<F: PrimeField> Ord for Term<F> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        #[cfg(test)]
+        TERM_COMPARISONS.with(|count| count.set(count.get() + 1));
+
         let t = other.degree().cmp(&self.degree());
         if t != std::cmp::Ordering::Equal {
             return t;
@@ -125,21 +149,50 @@ impl<F: PrimeField> Term<F> {
     /// For expressions, asserts unused slots are placeholders and sorts inner[..degree].
     /// Multiplication is commutative, x*y and y*x must be represented identically. Sorting inner[..degree] makes the representation unique.
     /// `combine` and `same_multiple` rely on simple slice equality. Sorting guarantees that equal monomials compare equal, so coefficients can be merged.
+    #[track_caller]
     pub fn normalize(&mut self) {
+        self.try_normalize().unwrap();
+    }
+
+    /// Same as [Term::normalize], but returns an error instead of panicking if
+    /// some slot past `degree` is not a placeholder.
+    pub fn try_normalize(&mut self) -> Result<(), NormalizeError<F>> {
         if let Self::Expression { coeff, .. } = &*self {
             if coeff.is_zero() {
                 *self = Self::Constant(F::ZERO);
             }
         }
+        let term = *self;
         match self {
             Term::Constant(_) => {}
             Term::Expression { degree, inner, .. } => {
                 for el in inner[*degree..].iter() {
-                    assert!(el.is_placeholder());
+                    if el.is_placeholder() == false {
+                        return Err(NormalizeError::NonPlaceholderInUnusedSlot { term });
+                    }
                 }
                 inner[..*degree].sort();
             }
         }
+
+        Ok(())
+    }
+
+    /// Returns `true` if [Term::try_normalize] would leave the term unchanged
+    /// and it is not zero.
+    pub fn is_normalized(&self) -> bool {
+        match self {
+            Term::Constant(constant) => constant.is_zero() == false,
+            Term::Expression {
+                coeff,
+                inner,
+                degree,
+            } => {
+                coeff.is_zero() == false
+                    && inner[..*degree].is_sorted()
+                    && inner[*degree..].iter().all(|el| el.is_placeholder())
+            }
+        }
     }
 
     /// Returns `true` if both terms are the same monomial up to a scalar
@@ -371,6 +424,31 @@ impl<F: PrimeField> From<bool> for Constraint<F> {
     }
 }
 
+/// Equality and hashing compare terms one by one, using canonical coefficients and variable
+/// indices. Both constraints must be normalized first, so that equal polynomials have
+/// their like terms combined and appear in the same order.
+impl<F: PrimeField> PartialEq for Constraint<F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.terms.len() == other.terms.len()
+            && self.terms.iter().zip(other.terms.iter()).all(|(a, b)| {
+                a.get_coef().as_u64_reduced() == b.get_coef().as_u64_reduced()
+                    && a.as_slice() == b.as_slice()
+            })
+    }
+}
+
+impl<F: PrimeField> Eq for Constraint<F> {}
+
+impl<F: PrimeField> std::hash::Hash for Constraint<F> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.terms.len().hash(state);
+        for term in self.terms.iter() {
+            term.get_coef().as_u64_reduced().hash(state);
+            term.as_slice().hash(state);
+        }
+    }
+}
+
 impl<F: PrimeField> Constraint<F> {
     pub fn empty() -> Self {
         Self {
@@ -387,6 +465,14 @@ impl<F: PrimeField> Constraint<F> {
         Self { terms: vec![term] }
     }
 
+    /// Returns the canonical boolean constraint x - x^2, that is zero iff variable is 0 or 1.
+    pub fn boolean_constraint(variable: Variable) -> Self {
+        let mut constraint = Self::from(variable) - Term::from(variable) * Term::from(variable);
+        constraint.normalize();
+
+        constraint
+    }
+
     /// Splits the constraint into quadratic terms, linear terms and a constant.
     /// Returns a triple (quadratic, linear, constant) where
     /// quadratic: Vec<(coeff, a, b)>
@@ -439,6 +525,24 @@ impl<F: PrimeField> Constraint<F> {
         (quadratic_terms, linear_terms, constant_term)
     }
 
+    /// Normalizes the constraint and splits it into the non-constant part and the constant term,
+    /// so that the original constraint is equal to `rest + constant`.
+    /// Purely constant constraint gives `(Constraint::empty(), constant)`.
+    pub fn split_affine(mut self) -> (Self, F) {
+        self.normalize();
+        let mut constant_term = F::ZERO;
+        self.terms.retain(|term| {
+            if term.is_constant() {
+                constant_term = term.get_coef();
+                false
+            } else {
+                true
+            }
+        });
+
+        (self, constant_term)
+    }
+
     /// Scales all coefficients and the constant by scaling_factor.
     pub fn scale(&mut self, scaling_factor: F) {
         for term in self.terms.iter_mut() {
@@ -482,7 +586,21 @@ impl<F: PrimeField> Constraint<F> {
     #[track_caller]
     /// Normalizes every term, sorts terms by the total order defined on Term, combines like terms and removes zeros, asserts the final degree is <= 2, converts a single zero term into an empty constraint.
     pub fn normalize(&mut self) {
-        self.terms.iter_mut().for_each(|el| el.normalize());
+        self.try_normalize().unwrap();
+    }
+
+    /// Same as [Constraint::normalize], but reports which invariant was violated instead of panicking.
+    /// On error the constraint is left in a partially normalized state and should not be used further.
+    pub fn try_normalize(&mut self) -> Result<(), NormalizeError<F>> {
+        // full normalization below is quadratic in the number of terms, and would not change
+        // an already normalized constraint anyway
+        if self.is_normalized() {
+            return Ok(());
+        }
+
+        for el in self.terms.iter_mut() {
+            el.try_normalize()?;
+        }
         self.terms.sort();
 
         let initial_degree = self.degree();
@@ -492,7 +610,7 @@ impl<F: PrimeField> Constraint<F> {
             let mut did_combine = false;
             for existing in combined.iter_mut() {
                 if existing.combine(&el) {
-                    existing.normalize();
+                    existing.try_normalize()?;
                     did_combine = true;
                     break;
                 }
@@ -510,18 +628,49 @@ impl<F: PrimeField> Constraint<F> {
             .filter(|el| el.is_zero() == false)
             .collect();
         let final_degree = self.degree();
-        assert!(final_degree <= 2);
+        if final_degree > 2 {
+            let term = *self
+                .terms
+                .iter()
+                .find(|el| el.degree() == final_degree)
+                .unwrap();
+            return Err(NormalizeError::DegreeTooHigh { term });
+        }
 
         if final_degree == 0 && self.terms == vec![Term::Constant(F::ZERO)] {
             *self = Constraint::empty();
-            return;
+            return Ok(());
         }
 
-        self.terms.iter_mut().for_each(|el| el.normalize());
+        for el in self.terms.iter_mut() {
+            el.try_normalize()?;
+        }
         self.terms.sort();
 
         // it's possible that terms will cancel each other
-        assert!(final_degree <= initial_degree);
+        if final_degree > initial_degree {
+            return Err(NormalizeError::DegreeIncreased {
+                initial_degree,
+                final_degree,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Returns `true` if the constraint is already in the form produced by [Constraint::normalize]:
+    /// all terms are normalized and non-zero, sorted, no two of them can be combined, and degree
+    /// is at most 2. Only takes a linear pass over the terms.
+    pub fn is_normalized(&self) -> bool {
+        // terms must be checked first, as comparison asserts sorted variables
+        self.terms.iter().all(|el| el.is_normalized())
+            && self.terms.is_sorted()
+            // sorting puts like terms next to each other
+            && self
+                .terms
+                .windows(2)
+                .all(|pair| pair[0].same_multiple(&pair[1]) == false)
+            && self.degree() <= 2
     }
 
     /// Returns true if any term contains variable.
@@ -628,28 +777,173 @@ impl<F: PrimeField> Constraint<F> {
         new
     }
 
+    /// Fixes variable to a constant value and returns the normalized result.
+    /// Every occurrence of the variable, including ones in quadratic terms, is replaced by the value,
+    /// so such terms lose a degree per occurrence.
+    pub fn substitute_constant(&self, variable: Variable, value: F) -> Self {
+        let mut new_terms = Vec::with_capacity(self.terms.len());
+        for term in self.terms.iter() {
+            match term {
+                Term::Constant(_) => new_terms.push(*term),
+                Term::Expression {
+                    coeff,
+                    inner,
+                    degree,
+                } => {
+                    let mut coeff = *coeff;
+                    let mut other_vars = Vec::with_capacity(*degree);
+                    for var in inner[..*degree].iter() {
+                        if *var == variable {
+                            coeff.mul_assign(&value);
+                        } else {
+                            other_vars.push(*var);
+                        }
+                    }
+                    new_terms.push(Term::expression(coeff, &other_vars));
+                }
+            }
+        }
+        let mut new = Self { terms: new_terms };
+        new.normalize();
+
+        new
+    }
+
+    /// Recognizes constraints of the form `out - rest`, where `out` appears only once, as a degree-1
+    /// term with coefficient one, and returns `(out, rest)`. Such a constraint defines the value of
+    /// `out` from `rest` rather than constraining already assigned values.
+    /// If several variables qualify, the first one in the normalized order is taken.
+    pub fn as_single_variable_difference(&self) -> Option<(Variable, Constraint<F>)> {
+        let mut normalized = self.clone();
+        normalized.normalize();
+        let terms_with_var = |variable: &Variable| {
+            normalized
+                .terms
+                .iter()
+                .filter(|el| el.contains_var(variable))
+                .count()
+        };
+        let position = normalized.terms.iter().position(|el| match el {
+            Term::Expression {
+                coeff,
+                inner,
+                degree: 1,
+            } => coeff.as_u64_reduced() == 1 && terms_with_var(&inner[0]) == 1,
+            _ => false,
+        })?;
+        let out = normalized.terms.remove(position).as_slice()[0];
+        let mut rest = normalized;
+        rest.scale(F::MINUS_ONE);
+        rest.normalize();
+
+        Some((out, rest))
+    }
+
+    /// Returns the sum of coefficients of all degree-1 terms in variable, or zero if there are none.
+    pub fn linear_coefficient(&self, variable: Variable) -> F {
+        let mut result = F::ZERO;
+        for term in self.terms.iter() {
+            if let Term::Expression {
+                coeff,
+                inner,
+                degree: 1,
+            } = term
+            {
+                if inner[0] == variable {
+                    result.add_assign(coeff);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Lists (coeff, other) for every degree-2 term coeff * variable * other.
+    /// For variable * variable the partner is the variable itself.
+    pub fn quadratic_partners(&self, variable: Variable) -> Vec<(F, Variable)> {
+        let mut result = vec![];
+        for term in self.terms.iter() {
+            if let Term::Expression {
+                coeff,
+                inner,
+                degree: 2,
+            } = term
+            {
+                if inner[0] == variable {
+                    result.push((*coeff, inner[1]));
+                } else if inner[1] == variable {
+                    result.push((*coeff, inner[0]));
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Renames every variable through mapping and normalizes the result. Placeholder slots are kept as is.
+    /// Distinct monomials can become the same one after renaming (e.g. x*y and x*z if y and z map
+    /// to the same variable), so normalization is needed to combine them.
+    pub fn map_variables<M: Fn(Variable) -> Variable>(&self, mapping: M) -> Self {
+        let mut terms = self.terms.clone();
+        for term in terms.iter_mut() {
+            if let Term::Expression { inner, degree, .. } = term {
+                for var in inner[..*degree].iter_mut() {
+                    *var = mapping(*var);
+                }
+            }
+            term.normalize();
+        }
+        let mut new = Self { terms };
+        new.normalize();
+
+        new
+    }
+
     /// Evaluates the constraint using witness values from a circuit,
     /// returning the concrete field value if all variables are assigned.
     pub fn get_value<CS: Circuit<F>>(&self, cs: &CS) -> Option<F> {
+        self.get_value_verbose(cs).ok()
+    }
+
+    /// Same as `get_value`, but returns the first variable without an assigned value on failure.
+    pub fn get_value_verbose<CS: Circuit<F>>(&self, cs: &CS) -> Result<F, Variable> {
         let (quad, linear, constant_term) = self.clone().split_max_quadratic();
+        let get_value = |var: Variable| cs.get_value(var).ok_or(var);
         let mut result = constant_term;
         for (coeff, a, b) in quad.into_iter() {
-            let mut t = cs.get_value(a)?;
-            t.mul_assign(&cs.get_value(b)?);
+            let mut t = get_value(a)?;
+            t.mul_assign(&get_value(b)?);
             t.mul_assign(&coeff);
             result.add_assign(&t);
         }
 
         for (coeff, a) in linear.into_iter() {
-            let mut t = cs.get_value(a)?;
+            let mut t = get_value(a)?;
             t.mul_assign(&coeff);
             result.add_assign(&t);
         }
 
-        Some(result)
+        Ok(result)
     }
 }
 
+/// Counts how many times every variable appears in the terms of constraints,
+/// a variable appearing with power k in some term is counted k times.
+pub fn constraint_variable_frequencies<F: PrimeField>(
+    constraints: &[Constraint<F>],
+) -> HashMap<Variable, usize> {
+    let mut frequencies = HashMap::new();
+    for constraint in constraints.iter() {
+        for term in constraint.terms.iter() {
+            for variable in term.as_slice().iter() {
+                *frequencies.entry(*variable).or_default() += 1;
+            }
+        }
+    }
+
+    frequencies
+}
+
 //CONSTRAINT -> CONSTRAINT OPS
 impl<F: PrimeField> std::ops::Add for Constraint<F> {
     type Output = Self;
@@ -773,6 +1067,37 @@ impl<F: PrimeField> std::ops::Mul<Term<F>> for Constraint<F> {
     }
 }
 
+//CONSTRAINT -> FIELD OPS
+impl<F: PrimeField> std::ops::Neg for Constraint<F> {
+    type Output = Self;
+
+    /// Negates all coefficients and the constant.
+    fn neg(self) -> Self::Output {
+        let mut ans = self;
+        ans.scale(F::MINUS_ONE);
+        ans
+    }
+}
+
+impl<F: PrimeField> std::ops::MulAssign<F> for Constraint<F> {
+    /// Scales the constraint by a field element (without immediate normalization).
+    fn mul_assign(&mut self, rhs: F) {
+        self.scale(rhs);
+    }
+}
+
+impl<F: PrimeField> std::ops::Mul<F> for Constraint<F> {
+    type Output = Self;
+
+    /// Scales the constraint by a field element and normalizes.
+    fn mul(self, rhs: F) -> Self::Output {
+        let mut ans = self;
+        ans *= rhs;
+        ans.normalize();
+        ans
+    }
+}
+
 //TERM -> CONSTRAINT OPS
 impl<F: PrimeField> std::ops::Mul<Constraint<F>> for Term<F> {
     type Output = Constraint<F>;
@@ -918,6 +1243,37 @@ impl<F: PrimeField> Term<F> {
     pub fn from_field(value: F) -> Self {
         Term::Constant(value)
     }
+
+    /// Creates coeff * prod(vars) in normalized form: variables are sorted, unused slots hold placeholders
+    /// and degree is the number of variables. Zero coefficient or no variables give a constant term.
+    /// Panics if there are more than TERM_INNER_CAPACITY variables or some of them is a placeholder.
+    #[track_caller]
+    pub fn expression(coeff: F, vars: &[Variable]) -> Self {
+        assert!(
+            vars.len() <= TERM_INNER_CAPACITY,
+            "term of degree {} exceeds capacity {}",
+            vars.len(),
+            TERM_INNER_CAPACITY
+        );
+        assert!(
+            vars.iter().all(|var| var.is_placeholder() == false),
+            "placeholder can not be a term variable"
+        );
+        if coeff.is_zero() {
+            return Term::Constant(F::ZERO);
+        }
+        if vars.is_empty() {
+            return Term::Constant(coeff);
+        }
+        let mut inner = [Variable::placeholder_variable(); TERM_INNER_CAPACITY];
+        inner[..vars.len()].copy_from_slice(vars);
+        inner[..vars.len()].sort();
+        Term::Expression {
+            coeff,
+            inner,
+            degree: vars.len(),
+        }
+    }
 }
 
 impl<F: PrimeField> From<u64> for Term<F> {
@@ -1006,3 +1362,314 @@ impl<F: PrimeField> Term<F> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cs::cs_reference::BasicAssembly;
+    use crate::cs::test_utils::{assign_variable, NoOracle};
+    use field::{Field, Mersenne31Field};
+
+    #[test]
+    fn test_try_normalize_reports_degree_overflow() {
+        let a = Term::<Mersenne31Field>::from(Variable(0));
+        let b = Term::<Mersenne31Field>::from(Variable(1));
+        let c = Term::<Mersenne31Field>::from(Variable(2));
+        let cubic = (a * b).terms[0] * c;
+
+        let mut constraint = cubic.clone();
+        let err = constraint.try_normalize().unwrap_err();
+        assert_eq!(
+            err,
+            NormalizeError::DegreeTooHigh {
+                term: cubic.terms[0]
+            }
+        );
+
+        let mut quadratic = a * b + Term::from(1u64);
+        assert!(quadratic.try_normalize().is_ok());
+    }
+
+    #[test]
+    fn test_normalize_fast_path() {
+        type F = Mersenne31Field;
+        let mut constraint = Constraint::<F>::empty();
+        for i in 0..49u64 {
+            let term = if i % 2 == 0 {
+                Term::from(Variable(i)) * Term::from(Variable(i + 1))
+            } else {
+                Constraint::from(Variable(i))
+            };
+            constraint = constraint + term;
+        }
+        constraint = constraint + Term::from(1u64);
+        constraint.normalize();
+        assert_eq!(constraint.terms.len(), 50);
+        assert!(constraint.is_normalized());
+        let expected = constraint.clone();
+        let count_comparisons = |constraint: &mut Constraint<F>| {
+            TERM_COMPARISONS.with(|count| count.set(0));
+            constraint.normalize();
+            TERM_COMPARISONS.with(|count| count.get())
+        };
+        // only the sortedness check of adjacent terms is done
+        let fast_path_comparisons = count_comparisons(&mut constraint);
+        assert_eq!(constraint, expected);
+        assert!(fast_path_comparisons < expected.terms.len());
+
+        // same terms out of order go through both sorts of the full path
+        let mut shuffled = Constraint {
+            terms: (0..50).map(|i| expected.terms[(i * 7) % 50]).collect(),
+        };
+        let full_path_comparisons = count_comparisons(&mut shuffled);
+        assert_eq!(shuffled, expected);
+        assert!(full_path_comparisons > 2 * fast_path_comparisons);
+
+        // anything that full normalization would change is not taken by the fast path
+        let x = Term::<F>::from(Variable(0));
+        let y = Term::<F>::from(Variable(1));
+        let not_normalized = [
+            Constraint::from(y) + x,
+            Constraint::from(x) + x,
+            Constraint::from(x) - x + y,
+            Constraint::from(x) + Term::from(0u64),
+            (x * x).terms[0] * x,
+        ];
+        for constraint in not_normalized {
+            assert!(constraint.is_normalized() == false);
+        }
+    }
+
+    #[test]
+    fn test_expression() {
+        type F = Mersenne31Field;
+        let coeff = F::from_u64_unchecked(7);
+        let mut inner = [Variable::placeholder_variable(); TERM_INNER_CAPACITY];
+        inner[0] = Variable(1);
+        inner[1] = Variable(3);
+        let hand_rolled = Term::Expression {
+            coeff,
+            inner,
+            degree: 2,
+        };
+        assert_eq!(
+            Term::expression(coeff, &[Variable(3), Variable(1)]),
+            hand_rolled
+        );
+        assert_eq!(
+            Term::expression(F::ZERO, &[Variable(3), Variable(1)]),
+            Term::Constant(F::ZERO)
+        );
+        assert_eq!(Term::expression(coeff, &[]), Term::Constant(coeff));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_expression_degree_overflow() {
+        let vars = [Variable(0); TERM_INNER_CAPACITY + 1];
+        Term::expression(Mersenne31Field::ONE, &vars);
+    }
+
+    #[test]
+    fn test_coefficients_for_var() {
+        type F = Mersenne31Field;
+        let (x, y, z) = (Variable(0), Variable(1), Variable(2));
+        let two = F::from_u64_unchecked(2);
+        let three = F::from_u64_unchecked(3);
+        let mut constraint = Constraint::from(Term::from((two, x)))
+            + Term::expression(three, &[x, y])
+            + Term::expression(F::ONE, &[x, z]);
+        constraint.normalize();
+
+        assert_eq!(constraint.linear_coefficient(x), two);
+        assert_eq!(constraint.linear_coefficient(y), F::ZERO);
+        let mut partners = constraint.quadratic_partners(x);
+        partners.sort_by_key(|(_, var)| *var);
+        assert_eq!(partners, vec![(three, y), (F::ONE, z)]);
+        assert_eq!(constraint.quadratic_partners(y), vec![(three, x)]);
+    }
+
+    #[test]
+    fn test_map_variables() {
+        type F = Mersenne31Field;
+        let (x, y, a) = (Variable(0), Variable(1), Variable(7));
+        let constraint = Constraint::<F>::from(Term::expression(F::ONE, &[x, y]));
+        let mapped = constraint.map_variables(|var| if var == x || var == y { a } else { var });
+        assert_eq!(mapped.terms, vec![Term::expression(F::ONE, &[a, a])]);
+        assert_eq!(mapped.degree_for_var(&a), 2);
+
+        // x*a and y*a become the same monomial and must be combined
+        let two = F::from_u64_unchecked(2);
+        let constraint = Constraint::<F>::from(Term::expression(F::ONE, &[x, a]))
+            + Term::expression(F::ONE, &[y, a]);
+        let mapped = constraint.map_variables(|var| if var == y { x } else { var });
+        assert_eq!(mapped.terms, vec![Term::expression(two, &[x, a])]);
+    }
+
+    #[test]
+    fn test_boolean_constraint() {
+        type F = Mersenne31Field;
+
+        let evaluate = |value: u64| {
+            let mut cs = BasicAssembly::<F>::new_with_oracle(NoOracle);
+            let var = assign_variable(&mut cs, value);
+            let constraint = Constraint::<F>::boolean_constraint(var);
+            assert_eq!(constraint.degree(), 2);
+
+            constraint.get_value(&cs).unwrap()
+        };
+
+        assert_eq!(evaluate(0), F::ZERO);
+        assert_eq!(evaluate(1), F::ZERO);
+        assert_ne!(evaluate(2), F::ZERO);
+    }
+
+    #[test]
+    fn test_get_value_verbose() {
+        type F = Mersenne31Field;
+
+        let mut cs = BasicAssembly::<F>::new_with_oracle(NoOracle);
+        let assigned = assign_variable(&mut cs, 3);
+        let unassigned = cs.add_variable();
+
+        let constraint = Constraint::<F>::from(assigned) + Term::from(1u64);
+        assert_eq!(
+            constraint.get_value_verbose(&cs),
+            Ok(F::from_u64_unchecked(4))
+        );
+
+        let constraint = Constraint::<F>::from(Term::expression(F::ONE, &[assigned, unassigned]))
+            + Term::from(assigned);
+        assert_eq!(constraint.get_value_verbose(&cs), Err(unassigned));
+        assert_eq!(constraint.get_value(&cs), None);
+    }
+
+    #[test]
+    fn test_dedup_normalized_constraints() {
+        use std::collections::HashSet;
+
+        type F = Mersenne31Field;
+        let (x, y) = (Variable(0), Variable(1));
+        let mut a = Constraint::<F>::from(x) + Term::from(y) - Term::from(1u64);
+        let mut b = Constraint::<F>::from(y) + Term::from(x) - Term::from(1u64);
+        a.normalize();
+        b.normalize();
+
+        let mut set = HashSet::new();
+        set.insert(a);
+        set.insert(b);
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn test_split_affine() {
+        type F = Mersenne31Field;
+        let (x, y) = (Variable(0), Variable(1));
+        let constraint = Constraint::<F>::from(Term::expression(F::from_u64_unchecked(3), &[x, y]))
+            + Term::from((F::from_u64_unchecked(2), x))
+            + Term::from(5u64);
+        let (rest, constant) = constraint.split_affine();
+        assert_eq!(constant, F::from_u64_unchecked(5));
+        let mut expected =
+            Constraint::<F>::from(Term::expression(F::from_u64_unchecked(3), &[x, y]))
+                + Term::from((F::from_u64_unchecked(2), x));
+        expected.normalize();
+        assert_eq!(rest, expected);
+
+        let (rest, constant) = Constraint::<F>::from(7u64).split_affine();
+        assert!(rest.is_empty());
+        assert_eq!(constant, F::from_u64_unchecked(7));
+    }
+
+    #[test]
+    fn test_substitute_constant() {
+        type F = Mersenne31Field;
+        let (x, y) = (Variable(0), Variable(1));
+        let constraint = Constraint::<F>::from(Term::expression(F::ONE, &[x, y]))
+            + Term::from((F::from_u64_unchecked(3), x));
+        let result = constraint.substitute_constant(x, F::from_u64_unchecked(2));
+        let mut expected =
+            Constraint::<F>::from(Term::from((F::from_u64_unchecked(2), y))) + Term::from(6u64);
+        expected.normalize();
+        assert_eq!(result, expected);
+        assert_eq!(result.degree(), 1);
+
+        // x^2 collapses to a constant, and pinning to zero removes the terms
+        let square = Constraint::<F>::from(Term::expression(F::ONE, &[x, x])) + Term::from(y);
+        let mut expected = Constraint::<F>::from(y) + Term::from(4u64);
+        expected.normalize();
+        assert_eq!(
+            square.substitute_constant(x, F::from_u64_unchecked(2)),
+            expected
+        );
+        let mut expected = Constraint::<F>::from(y);
+        expected.normalize();
+        assert_eq!(square.substitute_constant(x, F::ZERO), expected);
+    }
+
+    #[test]
+    fn test_as_single_variable_difference() {
+        type F = Mersenne31Field;
+        let (out, x, y) = (Variable(0), Variable(1), Variable(2));
+        let constraint =
+            Constraint::<F>::from(out) - Term::expression(F::ONE, &[x, y]) - Term::from(3u64);
+        let (variable, rest) = constraint.as_single_variable_difference().unwrap();
+        assert_eq!(variable, out);
+        let mut expected =
+            Constraint::<F>::from(Term::expression(F::ONE, &[x, y])) + Term::from(3u64);
+        expected.normalize();
+        assert_eq!(rest, expected);
+
+        // coefficient other than one, or the variable also appears in another term
+        let scaled = Constraint::<F>::from(Term::from((F::TWO, out))) - Term::from(x);
+        assert!(scaled.as_single_variable_difference().is_none());
+        let repeated = Constraint::<F>::from(out) - Term::expression(F::ONE, &[out, x]);
+        assert!(repeated.as_single_variable_difference().is_none());
+        let quadratic =
+            Constraint::<F>::from(Term::expression(F::ONE, &[out, out])) - Term::from(x);
+        assert!(quadratic.as_single_variable_difference().is_none());
+    }
+
+    #[test]
+    fn test_neg_and_scale() {
+        type F = Mersenne31Field;
+        let x = Variable(0);
+        let x_plus_one = Constraint::<F>::from(x) + Term::from(1u64);
+
+        let mut negated = -x_plus_one.clone();
+        negated.normalize();
+        let mut expected = Constraint::<F>::empty() - Term::from(x) - Term::from(1u64);
+        expected.normalize();
+        assert_eq!(negated, expected);
+
+        let scaled = x_plus_one.clone() * F::from_u64_unchecked(3);
+        let mut expected =
+            Constraint::<F>::from(Term::from((F::from_u64_unchecked(3), x))) + Term::from(3u64);
+        expected.normalize();
+        assert_eq!(scaled, expected);
+
+        let mut scaled_in_place = x_plus_one.clone();
+        scaled_in_place *= F::from_u64_unchecked(3);
+        scaled_in_place.normalize();
+        assert_eq!(scaled_in_place, expected);
+
+        assert!((x_plus_one * F::ZERO).is_empty());
+    }
+
+    #[test]
+    fn test_constraint_variable_frequencies() {
+        type F = Mersenne31Field;
+        let (x, y, z) = (Variable(0), Variable(1), Variable(2));
+        let constraints = [
+            Constraint::<F>::from(x) + Term::expression(F::ONE, &[x, y]),
+            Constraint::<F>::from(Term::expression(F::ONE, &[x, x])) + Term::from(z),
+            Constraint::<F>::from(y) + Term::from(1u64),
+        ];
+        let frequencies = constraint_variable_frequencies(&constraints);
+        // x appears in three terms, once squared
+        assert_eq!(frequencies[&x], 4);
+        assert_eq!(frequencies[&y], 2);
+        assert_eq!(frequencies[&z], 1);
+        assert_eq!(frequencies.len(), 3);
+    }
+}
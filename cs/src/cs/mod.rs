@@ -2,6 +2,8 @@ pub mod circuit;
 pub mod cs_reference;
 pub mod oracle;
 pub mod placeholder;
+#[cfg(test)]
+pub(crate) mod test_utils;
 pub mod utils;
 pub mod witness_placer;
 
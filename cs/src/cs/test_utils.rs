@@ -0,0 +1,61 @@
+use field::{Mersenne31Field, PrimeField};
+
+use super::circuit::Circuit;
+use super::cs_reference::BasicAssembly;
+use super::oracle::Oracle;
+use super::placeholder::Placeholder;
+use super::witness_placer::*;
+use crate::definitions::{TimestampScalar, Variable};
+use crate::types::{Boolean, Register};
+
+type F = Mersenne31Field;
+
+/// Oracle for test circuits that get all their witness values from `set_values`
+pub(crate) struct NoOracle;
+
+impl Oracle<F> for NoOracle {
+    fn get_witness_from_placeholder(&self, _: Placeholder, _: usize, _: usize) -> F {
+        unreachable!()
+    }
+
+    fn get_u32_witness_from_placeholder(&self, _: Placeholder, _: usize) -> u32 {
+        unreachable!()
+    }
+
+    fn get_timestamp_witness_from_placeholder(&self, _: Placeholder, _: usize) -> TimestampScalar {
+        unreachable!()
+    }
+}
+
+pub(crate) fn assign_variable(cs: &mut BasicAssembly<F>, value: u64) -> Variable {
+    let var = cs.add_variable();
+    let value_fn = move |placer: &mut <BasicAssembly<F> as Circuit<F>>::WitnessPlacer| {
+        let value = WitnessComputationalField::constant(F::from_u64_unchecked(value));
+        placer.assign_field(var, &value);
+    };
+    cs.set_values(value_fn);
+
+    var
+}
+
+pub(crate) fn assign_register(cs: &mut BasicAssembly<F>, value: u32) -> Register<F> {
+    let reg = Register::new(cs);
+    let vars = reg.0.map(|el| el.get_variable());
+    let value_fn = move |placer: &mut <BasicAssembly<F> as Circuit<F>>::WitnessPlacer| {
+        placer.assign_u32_from_u16_parts(vars, &WitnessComputationalU32::constant(value));
+    };
+    cs.set_values(value_fn);
+
+    reg
+}
+
+pub(crate) fn assign_boolean(cs: &mut BasicAssembly<F>, value: bool) -> Boolean {
+    let flag = cs.add_boolean_variable();
+    let var = flag.get_variable().unwrap();
+    let value_fn = move |placer: &mut <BasicAssembly<F> as Circuit<F>>::WitnessPlacer| {
+        placer.assign_mask(var, &WitnessMask::constant(value));
+    };
+    cs.set_values(value_fn);
+
+    flag
+}
@@ -95,6 +95,17 @@ pub fn split_timestamp(timestamp: TimestampScalar) -> (u32, u32) {
     (low, high)
 }
 
+/// Inverse of `split_timestamp`
+pub fn join_timestamp(low: u32, high: u32) -> TimestampScalar {
+    assert!(
+        low < (1 << TIMESTAMP_COLUMNS_NUM_BITS),
+        "low timestamp part 0x{:x} is too large",
+        low
+    );
+
+    ((high as TimestampScalar) << TIMESTAMP_COLUMNS_NUM_BITS) | (low as TimestampScalar)
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 #[repr(C)]
 pub struct TimestampData(pub [u16; NUM_TIMESTAMP_DATA_LIMBS]);
@@ -525,3 +536,30 @@ impl<'a, F: PrimeField> VerifierCompiledCircuitArtifact<'a, F> {
         num_quotient_terms
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_join_timestamp() {
+        const CHUNK_CAPACITY: usize = (1 << 22) - 1;
+        for circuit_sequence in [0, 1, 2, 1000, (1 << 14) - 1] {
+            for cycle in [0, 1, 12345, CHUNK_CAPACITY - 1] {
+                let timestamp = timestamp_from_chunk_cycle_and_sequence(
+                    cycle,
+                    CHUNK_CAPACITY,
+                    circuit_sequence,
+                );
+                let (low, high) = split_timestamp(timestamp);
+                assert_eq!(join_timestamp(low, high), timestamp);
+            }
+        }
+        for timestamp in
+            (0..MAX_NUMBER_OF_CYCLES << NUM_EMPTY_BITS_FOR_RAM_TIMESTAMP).step_by(123_456_789)
+        {
+            let (low, high) = split_timestamp(timestamp);
+            assert_eq!(join_timestamp(low, high), timestamp);
+        }
+    }
+}
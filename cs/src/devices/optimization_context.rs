@@ -16,6 +16,7 @@ use crate::one_row_compiler::LookupInput;
 use crate::types::*;
 use ::field::PrimeField;
 use core::array::from_fn;
+use std::collections::HashMap;
 
 use super::risc_v_types::ExecutorOperation;
 
@@ -67,6 +68,36 @@ struct LookupRelation<F: PrimeField> {
 // to avoid unsatisfiable constraint if the branch is not taken
 // Overall, it reduced a number of the variable in the circuit
 
+/// Key to deduplicate lookup requests. Two requests are only considered identical if they
+/// use the same table under the same execution flag and literally the same input variables.
+/// Inputs are sorted only for tables that are symmetric over their inputs.
+/// Execution flag can not be dropped from the key: relations at the same index are selected by
+/// their flags, so outputs of a request made under another flag are not constrained when only
+/// the new flag is set
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LookupDedupKey {
+    pub table_id: u32,
+    pub exec_flag: Variable,
+    pub inputs: Vec<Variable>,
+}
+
+impl LookupDedupKey {
+    fn is_symmetric_table(table_id: u32) -> bool {
+        [
+            TableType::And,
+            TableType::Or,
+            TableType::Xor,
+            TableType::Xor3,
+            TableType::Xor4,
+            TableType::Xor7,
+            TableType::Xor9,
+            TableType::Xor12,
+        ]
+        .iter()
+        .any(|el| el.to_table_id() == table_id)
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct OptCtxIndexers {
     pub register_allocation_indexer: usize,
@@ -112,6 +143,7 @@ pub struct OptimizationContext<F: PrimeField, C: Circuit<F>> {
     add_sub_ofs: Vec<Boolean>,
     is_zero_flags: Vec<Boolean>,
     lookup_outputs: Vec<Variable>,
+    lookup_dedup_cache: HashMap<LookupDedupKey, Vec<Variable>>,
 
     _marker: std::marker::PhantomData<(F, C)>,
 }
@@ -122,7 +154,9 @@ impl<F: PrimeField, CS: Circuit<F>> OptimizationContext<F, CS> {
     }
 
     pub fn restore_indexers(&mut self, indexers: OptCtxIndexers) {
-        self.indexers = indexers
+        self.indexers = indexers;
+        // lookup output variables will be reused by other requests from now on
+        self.lookup_dedup_cache.clear();
     }
 
     pub fn new() -> Self {
@@ -140,13 +174,16 @@ impl<F: PrimeField, CS: Circuit<F>> OptimizationContext<F, CS> {
             add_sub_ofs: vec![],
             is_zero_flags: vec![],
             lookup_outputs: vec![],
+            lookup_dedup_cache: HashMap::new(),
 
             _marker: std::marker::PhantomData,
         }
     }
 
     pub fn reset_indexers(&mut self) {
-        self.indexers.reset()
+        self.indexers.reset();
+        // lookup output variables will be reused by other requests from now on
+        self.lookup_dedup_cache.clear();
     }
 
     #[track_caller]
@@ -699,6 +736,32 @@ impl<F: PrimeField, CS: Circuit<F>> OptimizationContext<F, CS> {
         self.indexers.lookup_outputs_indexer += N;
     }
 
+    /// Returns a key under which the lookup request is deduplicated, or `None` if request
+    /// can not be deduplicated (table is not a constant, or execution flag is not a variable)
+    pub fn dedup_key(
+        vars: &[Variable],
+        table: Num<F>,
+        exec_flag: Boolean,
+    ) -> Option<LookupDedupKey> {
+        let Num::Constant(table_id) = table else {
+            return None;
+        };
+        let Boolean::Is(exec_flag) = exec_flag else {
+            return None;
+        };
+        let table_id = table_id.as_u64_reduced() as u32;
+        let mut inputs = vars.to_vec();
+        if LookupDedupKey::is_symmetric_table(table_id) {
+            inputs.sort_by_key(|el| el.0);
+        }
+
+        Some(LookupDedupKey {
+            table_id,
+            exec_flag,
+            inputs,
+        })
+    }
+
     #[track_caller]
     pub fn append_lookup_relation<const M: usize, const N: usize>(
         &mut self,
@@ -707,6 +770,16 @@ impl<F: PrimeField, CS: Circuit<F>> OptimizationContext<F, CS> {
         table: Num<F>,
         exec_flag: Boolean,
     ) -> [Variable; N] {
+        // if exactly the same request was already made, then its outputs are already constrained
+        // under the same execution flag, so we can just reuse them
+        let dedup_key = Self::dedup_key(vars, table, exec_flag);
+        if let Some(key) = dedup_key.as_ref() {
+            if let Some(cached) = self.lookup_dedup_cache.get(key) {
+                assert_eq!(cached.len(), N);
+                return from_fn(|i| cached[i]);
+            }
+        }
+
         // internally it'll perform witness resolution
         let inputs = vars.map(|el| LookupInput::Variable(el));
         let outputs = from_fn(|i| {
@@ -721,6 +794,10 @@ impl<F: PrimeField, CS: Circuit<F>> OptimizationContext<F, CS> {
         cs.peek_lookup_value_unconstrained_ext(&inputs, &outputs, table, exec_flag);
         self.append_lookup_relation_inner(&inputs, &outputs, table, exec_flag);
 
+        if let Some(key) = dedup_key {
+            self.lookup_dedup_cache.insert(key, outputs.to_vec());
+        }
+
         outputs
     }
 
@@ -1886,3 +1963,78 @@ impl<F: PrimeField, CS: Circuit<F>> OptimizationContext<F, CS> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cs::cs_reference::BasicAssembly;
+    use ::field::Mersenne31Field;
+
+    #[test]
+    fn test_identical_lookups_are_deduplicated() {
+        let mut cs = BasicAssembly::<Mersenne31Field>::new();
+        let mut opt_ctx = OptimizationContext::<Mersenne31Field, _>::new();
+        let exec_flag = cs.add_boolean_variable();
+        let a = cs.add_variable();
+        let b = cs.add_variable();
+        let c = cs.add_variable();
+
+        let [first] = opt_ctx.append_lookup_relation::<2, 1>(
+            &mut cs,
+            &[a, b],
+            TableType::And.to_num(),
+            exec_flag,
+        );
+        // AND is symmetric, so order of inputs doesn't matter
+        let [second] = opt_ctx.append_lookup_relation::<2, 1>(
+            &mut cs,
+            &[b, a],
+            TableType::And.to_num(),
+            exec_flag,
+        );
+        assert_eq!(first, second);
+        // structurally similar request over different variables must not be deduplicated
+        let [third] = opt_ctx.append_lookup_relation::<2, 1>(
+            &mut cs,
+            &[a, c],
+            TableType::And.to_num(),
+            exec_flag,
+        );
+        assert_ne!(first, third);
+
+        opt_ctx.enforce_all(&mut cs);
+        let (output, _) = cs.finalize();
+        let num_lookup_rows = output
+            .lookups
+            .iter()
+            .filter(|el| matches!(el.table, LookupQueryTableType::Variable(_)))
+            .count();
+        assert_eq!(num_lookup_rows, 2);
+    }
+
+    #[test]
+    fn test_lookup_dedup_cache_is_cleared_on_reset() {
+        let mut cs = BasicAssembly::<Mersenne31Field>::new();
+        let mut opt_ctx = OptimizationContext::<Mersenne31Field, _>::new();
+        let exec_flag = cs.add_boolean_variable();
+        let a = cs.add_variable();
+        let b = cs.add_variable();
+
+        opt_ctx.append_lookup_relation::<2, 1>(
+            &mut cs,
+            &[a, b],
+            TableType::And.to_num(),
+            exec_flag,
+        );
+        opt_ctx.reset_indexers();
+        // output variable of the first request now belongs to whatever request comes next,
+        // so the same request must be appended again
+        opt_ctx.append_lookup_relation::<2, 1>(
+            &mut cs,
+            &[a, b],
+            TableType::And.to_num(),
+            exec_flag,
+        );
+        assert_eq!(opt_ctx.lookup_relations.len(), 2);
+    }
+}
@@ -20,12 +20,21 @@ use one_row_compiler::LookupInput;
 
 use super::*;
 use crate::devices::risc_v_types::NUM_INSTRUCTION_TYPES;
+use crate::machine::ops::constants::OPERATION_MISC_MEM;
 
 // An optimization of basic decode for the case when CSR is explicitly matched later on. We try to drag values that are
 // not needed as explicit variables all the way to output
 
 pub const NUM_INSTRUCTION_TYPES_IN_DECODE_BITS: usize = NUM_INSTRUCTION_TYPES;
 
+/// FENCE (funct3 = 0b000) and FENCE.I (funct3 = 0b001) from MISC-MEM opcode family. Our machine is single-threaded
+/// and in-order, so those are semantically no-ops. They are encoded as I-type, but all the reserved fields
+/// (rd, rs1 and fm/pred/succ bits of the immediate) are ignored, so opcode bitmask table marks them as B-type
+/// to avoid RD write
+pub const fn is_fence_family(opcode: u8, funct3: u8) -> bool {
+    opcode == OPERATION_MISC_MEM && (funct3 == 0b000 || funct3 == 0b001)
+}
+
 pub struct OptimizedDecoder;
 
 pub struct OptimizedDecoderOutput<F: PrimeField> {
@@ -58,134 +67,18 @@ impl OptimizedDecoder {
         Vec<Boolean>,
     ) {
         // instruction set of variables: low: [15:0], high: [31:16]
-        // the most shredded instruction type is B-type (with additional splitting of rs_2, required for J-type):
-        // all other instruction types can be constructed from
-        // chunks of split instruction are:
-        // opcode [6:0], imm11: [7], imm[4-1]: [11:8], func3: [14:12], rs1: [19:15],
-        // rs2_low: [20], rs2_high: [24:21], imm[10-5]: [30:25], imm12: [31]
-        // rs1 crosses the border of register, so we need to additionally split it as
-        // rs1_low: [15], rs1_high: [16-19]
-
-        // NOTE: we DO range check opcode (7 bits) so we can later on use a single table lookup to get all our opcode properties
-
-        let opcode = Num::Var(circuit.add_variable());
-        // imm11 will be replaced as quadratic constraint over difference
-        let imm4_1 = Num::Var(circuit.add_variable());
-        let funct3 = Num::Var(circuit.add_variable());
-        let rs1_low = circuit.add_boolean_variable();
-        let rs1_high = Num::Var(circuit.add_variable());
-        // rs2_low will be replaced as quadratic constraint over difference
-        // let rs2_low = circuit.add_boolean_variable();
-        let rs2_high = Num::Var(circuit.add_variable());
-        let imm10_5 = Num::Var(circuit.add_variable());
-        let sign_bit = circuit.add_boolean_variable();
-
-        // here we will have to write value-fn manually
-
-        let input = inputs.instruction.0.map(|x| x.get_variable());
-
-        let opcode_var = opcode.get_variable();
-        let imm4_1_var = imm4_1.get_variable();
-        let funct3_var = funct3.get_variable();
-        let rs1_low_var = rs1_low.get_variable().unwrap();
-
-        let rs1_high_var = rs1_high.get_variable();
-        let rs2_high_var = rs2_high.get_variable();
-        let imm10_5_var = imm10_5.get_variable();
-        let sign_bit_var = sign_bit.get_variable().unwrap();
-
-        // Assign witnesses by slicing low/high 16-bit halves of the instruction.
-        // We take care to only materialize the small chunks we need, leaving
-        // imm11, rs2_low to be reconstructed as linear constraints.
-        let value_fn = move |placer: &mut CS::WitnessPlacer| {
-            use crate::cs::witness_placer::*;
-
-            let mut low_word = placer.get_u16(input[0]);
-            let mut high_word = placer.get_u16(input[1]);
-
-            let opcode = low_word.get_lowest_bits(7);
-            // skip imm11
-            low_word = low_word.shr(8);
-            let imm4_1 = low_word.get_lowest_bits(4);
-            low_word = low_word.shr(4);
-            let funct3 = low_word.get_lowest_bits(3);
-            low_word = low_word.shr(3);
-            let rs1_low = low_word.get_bit(0);
-
-            let rs1_high = high_word.get_lowest_bits(4);
-            // skip rs2_low
-            high_word = high_word.shr(5);
-            let rs2_high = high_word.get_lowest_bits(4);
-            high_word = high_word.shr(4);
-            let imm10_5 = high_word.get_lowest_bits(6);
-            high_word = high_word.shr(6);
-            let sign_bit = high_word.get_bit(0);
-
-            placer.assign_u16(opcode_var, &opcode);
-            placer.assign_u16(imm4_1_var, &imm4_1);
-            placer.assign_u16(funct3_var, &funct3);
-            placer.assign_mask(rs1_low_var, &rs1_low);
-
-            placer.assign_u16(rs1_high_var, &rs1_high);
-            placer.assign_u16(rs2_high_var, &rs2_high);
-            placer.assign_u16(imm10_5_var, &imm10_5);
-            placer.assign_mask(sign_bit_var, &sign_bit);
-        };
-
-        circuit.set_values(value_fn);
-
-        // range check decomposition pieces
-        circuit.enforce_lookup_tuple_for_fixed_table(
-            &[
-                imm4_1.get_variable(),
-                rs1_high.get_variable(),
-                rs2_high.get_variable(),
-            ]
-            .map(|el| LookupInput::from(el)),
-            TableType::QuickDecodeDecompositionCheck4x4x4,
-            false,
-        );
-
-        circuit.enforce_lookup_tuple_for_fixed_table(
-            &[
-                opcode.get_variable(),
-                funct3.get_variable(),
-                imm10_5.get_variable(),
-            ]
-            .map(|el| LookupInput::from(el)),
-            TableType::QuickDecodeDecompositionCheck7x3x6,
-            false,
-        );
-
-        // insn_low <=> opcode [6:0], imm11: [7], imm[4-1]: [11:8], func3: [14:12], rs1_low: [15],
-        let [low_insn, high_insn] = inputs.instruction.get_terms();
-        let mut imm11_constraint = {
-            low_insn
-                - Term::from(opcode)
-                - Term::from(1 << 8) * Term::from(imm4_1)
-                - Term::from(1 << 12) * Term::from(funct3)
-                - Term::from(rs1_low) * Term::from(1 << 15)
-        };
-        imm11_constraint.scale(F::from_u64_unchecked(1 << 7).inverse().unwrap());
-        circuit
-            .add_constraint(imm11_constraint.clone() * (imm11_constraint.clone() - Term::from(1)));
-
-        // insn_high <=> rs1_high: [19:16], rs2: [24:20], imm[10-5]: [30:25], imm12: [31]
-        let mut rs2_low_constraint = {
-            high_insn
-                - Term::from(rs1_high)
-                - Term::from(rs2_high) * Term::from(1 << 5)
-                - Term::from(imm10_5) * Term::from(1 << 9)
-                - Term::from(sign_bit) * Term::from(1 << 15)
-        };
-        rs2_low_constraint.scale(F::from_u64_unchecked(1 << 4).inverse().unwrap());
-        circuit.add_constraint(
-            rs2_low_constraint.clone() * (rs2_low_constraint.clone() - Term::from(1)),
-        );
-
-        // imm11 and rs2_low constraint are linear
-        assert_eq!(imm11_constraint.degree(), 1);
-        assert_eq!(rs2_low_constraint.degree(), 1);
+        let chunks = decompose_instruction(circuit, &inputs.instruction);
+        let InstructionChunks {
+            opcode,
+            imm4_1,
+            funct3,
+            rs1_low,
+            rs1_high,
+            rs2_high,
+            imm10_5,
+            sign_bit,
+            ..
+        } = chunks;
 
         // We do NOT need rd as variable, because it'll be merged in the write into explicit variable,
         // so we can drag it along as linear constraint
@@ -195,8 +88,8 @@ impl OptimizedDecoder {
         let rs1 = circuit.add_variable_from_constraint_allow_explicit_linear(
             Term::from(rs1_high) * Term::from(1 << 1) + Term::from(rs1_low),
         );
-        let rs2_constraint = Term::from(rs2_high) * Term::from(1 << 1) + rs2_low_constraint.clone();
-        let rd_constraint = Term::from(imm4_1) * Term::from(1 << 1) + imm11_constraint.clone();
+        let rs2_constraint = Term::from(rs2_high) * Term::from(1 << 1) + chunks.rs2_low.clone();
+        let rd_constraint = Term::from(imm4_1) * Term::from(1 << 1) + chunks.imm11.clone();
 
         // funct_7 = sign_bit[1] | imm_10-5[6]
         let funct7_constraint = Term::from(sign_bit) * Term::from(1 << 6) + Term::from(imm10_5);
@@ -217,74 +110,12 @@ impl OptimizedDecoder {
         );
 
         // now we need to construct the right constant from different constant chunks
-        // the actual constant is dependent on the opcode type:
-        // -------------------------------------------------------------------------------------------------------|
-        // |       chunk5[31-16]    |   chunk4[15-12]   | chunk3[11] | chunk2[10-5] | chunk1[4-1] | chunk0[0] |   |
-        // |========================|===================|============|==============|=============|===========|===|
-        // |         sign_bit       |    sign_bit       |  sign_bit  |   imm[10-5]  |   rs2_high  |  rs2_low  | I |
-        // |------------------------|-------------------|------------|--------------|-------------|-----------|---|
-        // |         sign_bit       |    sign_bit       |  sign_bit  |   imm[10-5]  |   imm4_1    |   imm11   | S |
-        // |------------------------|-------------------|------------|--------------|-------------|-----------|---|
-        // |         sign_bit       |    sign_bit       |   imm11    |   imm[10-5]  |   imm4_1    |     0     | B |
-        // |------------------------|-------------------|------------|--------------|-------------|-----------|---|
-        // |         insn_high      | rs1_low || funct3 |      0     |      0       |      0      |     0     | U |
-        // |------------------------|-------------------|------------|--------------|-------------|-----------|---|
-        // |  sign_bit || rs1_high  | rs1_low || funct3 |  rs2_low   |   imm[10-5]  |   rs2_high  |     0     | J |
-        // |========================|===================|============|==============|=============|===========|===|
-        // hence:
-        // chunk0 = i_insn * rs2_low +  s_insn * imm11
-        // chunk1 = (i_insn + j_insn) * rs2_high + (s_insn + b_insn) * imm4_1
-        // chunk2 = (1 - u_insn) * imm10_5
-        // chunk3 = (i_insn + s_insn) * sign_bit + b_insn * imm11 + j_insn * rs2_low
-        // chunk4 = (i_insn + s_insn + b_insn) * sign_bit * 0b1111 + (u_insn + j_insn) * (rs1_low << 3 + funct3)
-        // chunk5 = {
-        //      j_insn * (sign_bit * 0xfff0 + rs1_high) + u_insn * insn_high +
-        //      (1 - j_insn - b_insn) * sign_bit * 0xffff
-        // }
-
-        // chunks 0..4 are used for linear constraint later on to form imm_low
-        let chunks_defining_constraints: [Constraint<F>; 5] = [
-            // 0
-            Term::from(i_insn) * rs2_low_constraint.clone()
-                + Term::from(s_insn) * imm11_constraint.clone(),
-            // 1
-            (Term::from(i_insn) + Term::from(j_insn)) * Term::from(rs2_high)
-                + (Term::from(s_insn) + Term::from(b_insn)) * Term::from(imm4_1),
-            // 2
-            (Term::from(1) - Term::from(u_insn)) * Term::from(imm10_5),
-            // 3
-            (Term::from(i_insn) + Term::from(s_insn)) * Term::from(sign_bit)
-                + Term::from(b_insn) * imm11_constraint
-                + Term::from(j_insn) * rs2_low_constraint.clone(),
-            // 4
-            (Term::from(i_insn) + Term::from(s_insn) + Term::from(b_insn))
-                * Term::from(sign_bit)
-                * Term::from(0b1111u64)
-                + (Term::from(u_insn) + Term::from(j_insn))
-                    * (Term::from(rs1_low) * Term::from(1 << 3) + (Term::from(funct3))),
-        ];
-
-        let [chunk0, chunk1, chunk2, chunk3, chunk4] = chunks_defining_constraints;
-
-        let imm_low = Num::Var(circuit.add_variable_from_constraint(
-            chunk0
-                + chunk1 * Term::from(1 << 1)
-                + chunk2 * Term::from(1 << 5)
-                + chunk3 * Term::from(1 << 11)
-                + chunk4 * Term::from(1 << 12),
-        ));
-
-        // chunk 5 is just higher part of the immediate
-        // This encodes sign-extension for all formats. For U format we take insn_high entirely.
-        let imm_high = Num::Var(circuit.add_variable_from_constraint(
-            Term::from(j_insn) * (Term::from(sign_bit) * Term::from(0xfff0) + Term::from(rs1_high))
-                + Term::from(u_insn) * Term::from(inputs.instruction.0[1])
-                + (Term::from(1) - Term::from(j_insn) - Term::from(u_insn))
-                    * Term::from(sign_bit)
-                    * Term::from(0xffff),
-        ));
-
-        let imm = Register([imm_low, imm_high]);
+        let imm = assemble_immediate_from_chunks(
+            circuit,
+            &inputs.instruction,
+            &chunks,
+            &[r_insn, i_insn, s_insn, b_insn, u_insn, j_insn],
+        );
 
         // funct_12 is used only by:
         // SYSTEM CSR - there we can use single table lookup to validate if 12-bit index is valid and trap (along with R/W info if we want)
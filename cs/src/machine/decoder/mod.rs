@@ -1,5 +1,7 @@
 pub mod decode_optimized_must_handle_csr;
 
+use one_row_compiler::LookupInput;
+
 use super::*;
 use crate::devices::risc_v_types::NUM_INSTRUCTION_TYPES;
 
@@ -21,3 +23,363 @@ pub const NUM_INSTRUCTION_TYPES_IN_DECODE_BITS: usize = NUM_INSTRUCTION_TYPES;
 pub struct DecoderInput<F: PrimeField> {
     pub instruction: Register<F>,
 }
+
+/// Chunks of the instruction that all the formats are assembled from. Bits 7 and 20 (imm11 and rs2_low)
+/// are not materialized and are carried as linear constraints instead
+pub struct InstructionChunks<F: PrimeField> {
+    pub opcode: Num<F>,
+    pub imm11: Constraint<F>,
+    pub imm4_1: Num<F>,
+    pub funct3: Num<F>,
+    pub rs1_low: Boolean,
+    pub rs1_high: Num<F>,
+    pub rs2_low: Constraint<F>,
+    pub rs2_high: Num<F>,
+    pub imm10_5: Num<F>,
+    pub sign_bit: Boolean,
+}
+
+/// Splits the instruction into chunks and range checks them. The most shredded instruction type is B-type
+/// (with additional splitting of rs_2, required for J-type), and all other instruction types can be constructed from
+/// opcode [6:0], imm11: [7], imm[4-1]: [11:8], func3: [14:12], rs1: [19:15],
+/// rs2_low: [20], rs2_high: [24:21], imm[10-5]: [30:25], imm12: [31].
+/// rs1 crosses the border of register, so we additionally split it as rs1_low: [15], rs1_high: [16-19]
+pub fn decompose_instruction<F: PrimeField, CS: Circuit<F>>(
+    circuit: &mut CS,
+    instruction: &Register<F>,
+) -> InstructionChunks<F> {
+    // NOTE: we DO range check opcode (7 bits) so we can later on use a single table lookup to get all our opcode properties
+
+    let opcode = Num::Var(circuit.add_variable());
+    // imm11 will be replaced as quadratic constraint over difference
+    let imm4_1 = Num::Var(circuit.add_variable());
+    let funct3 = Num::Var(circuit.add_variable());
+    let rs1_low = circuit.add_boolean_variable();
+    let rs1_high = Num::Var(circuit.add_variable());
+    // rs2_low will be replaced as quadratic constraint over difference
+    let rs2_high = Num::Var(circuit.add_variable());
+    let imm10_5 = Num::Var(circuit.add_variable());
+    let sign_bit = circuit.add_boolean_variable();
+
+    // here we will have to write value-fn manually
+
+    let input = instruction.0.map(|x| x.get_variable());
+
+    let opcode_var = opcode.get_variable();
+    let imm4_1_var = imm4_1.get_variable();
+    let funct3_var = funct3.get_variable();
+    let rs1_low_var = rs1_low.get_variable().unwrap();
+
+    let rs1_high_var = rs1_high.get_variable();
+    let rs2_high_var = rs2_high.get_variable();
+    let imm10_5_var = imm10_5.get_variable();
+    let sign_bit_var = sign_bit.get_variable().unwrap();
+
+    // Assign witnesses by slicing low/high 16-bit halves of the instruction.
+    // We take care to only materialize the small chunks we need, leaving
+    // imm11, rs2_low to be reconstructed as linear constraints.
+    let value_fn = move |placer: &mut CS::WitnessPlacer| {
+        use crate::cs::witness_placer::*;
+
+        let mut low_word = placer.get_u16(input[0]);
+        let mut high_word = placer.get_u16(input[1]);
+
+        let opcode = low_word.get_lowest_bits(7);
+        // skip imm11
+        low_word = low_word.shr(8);
+        let imm4_1 = low_word.get_lowest_bits(4);
+        low_word = low_word.shr(4);
+        let funct3 = low_word.get_lowest_bits(3);
+        low_word = low_word.shr(3);
+        let rs1_low = low_word.get_bit(0);
+
+        let rs1_high = high_word.get_lowest_bits(4);
+        // skip rs2_low
+        high_word = high_word.shr(5);
+        let rs2_high = high_word.get_lowest_bits(4);
+        high_word = high_word.shr(4);
+        let imm10_5 = high_word.get_lowest_bits(6);
+        high_word = high_word.shr(6);
+        let sign_bit = high_word.get_bit(0);
+
+        placer.assign_u16(opcode_var, &opcode);
+        placer.assign_u16(imm4_1_var, &imm4_1);
+        placer.assign_u16(funct3_var, &funct3);
+        placer.assign_mask(rs1_low_var, &rs1_low);
+
+        placer.assign_u16(rs1_high_var, &rs1_high);
+        placer.assign_u16(rs2_high_var, &rs2_high);
+        placer.assign_u16(imm10_5_var, &imm10_5);
+        placer.assign_mask(sign_bit_var, &sign_bit);
+    };
+
+    circuit.set_values(value_fn);
+
+    // range check decomposition pieces
+    circuit.enforce_lookup_tuple_for_fixed_table(
+        &[
+            imm4_1.get_variable(),
+            rs1_high.get_variable(),
+            rs2_high.get_variable(),
+        ]
+        .map(|el| LookupInput::from(el)),
+        TableType::QuickDecodeDecompositionCheck4x4x4,
+        false,
+    );
+
+    circuit.enforce_lookup_tuple_for_fixed_table(
+        &[
+            opcode.get_variable(),
+            funct3.get_variable(),
+            imm10_5.get_variable(),
+        ]
+        .map(|el| LookupInput::from(el)),
+        TableType::QuickDecodeDecompositionCheck7x3x6,
+        false,
+    );
+
+    // insn_low <=> opcode [6:0], imm11: [7], imm[4-1]: [11:8], func3: [14:12], rs1_low: [15],
+    let [low_insn, high_insn] = instruction.get_terms();
+    let mut imm11_constraint = {
+        low_insn
+            - Term::from(opcode)
+            - Term::from(1 << 8) * Term::from(imm4_1)
+            - Term::from(1 << 12) * Term::from(funct3)
+            - Term::from(rs1_low) * Term::from(1 << 15)
+    };
+    imm11_constraint.scale(F::from_u64_unchecked(1 << 7).inverse().unwrap());
+    circuit.add_constraint(imm11_constraint.clone() * (imm11_constraint.clone() - Term::from(1)));
+
+    // insn_high <=> rs1_high: [19:16], rs2: [24:20], imm[10-5]: [30:25], imm12: [31]
+    let mut rs2_low_constraint = {
+        high_insn
+            - Term::from(rs1_high)
+            - Term::from(rs2_high) * Term::from(1 << 5)
+            - Term::from(imm10_5) * Term::from(1 << 9)
+            - Term::from(sign_bit) * Term::from(1 << 15)
+    };
+    rs2_low_constraint.scale(F::from_u64_unchecked(1 << 4).inverse().unwrap());
+    circuit
+        .add_constraint(rs2_low_constraint.clone() * (rs2_low_constraint.clone() - Term::from(1)));
+
+    // imm11 and rs2_low constraint are linear
+    assert_eq!(imm11_constraint.degree(), 1);
+    assert_eq!(rs2_low_constraint.degree(), 1);
+
+    InstructionChunks {
+        opcode,
+        imm11: imm11_constraint,
+        imm4_1,
+        funct3,
+        rs1_low,
+        rs1_high,
+        rs2_low: rs2_low_constraint,
+        rs2_high,
+        imm10_5,
+        sign_bit,
+    }
+}
+
+/// Builds sign-extended immediate from already decomposed instruction. `instruction_type_bits` are orthogonal
+/// format flags in order [R,I,S,B,U,J], so at most one of them is set
+pub fn assemble_immediate_from_chunks<F: PrimeField, CS: Circuit<F>>(
+    circuit: &mut CS,
+    instruction: &Register<F>,
+    chunks: &InstructionChunks<F>,
+    instruction_type_bits: &[Boolean],
+) -> Register<F> {
+    assert_eq!(
+        instruction_type_bits.len(),
+        NUM_INSTRUCTION_TYPES_IN_DECODE_BITS
+    );
+    let [_r_insn, i_insn, s_insn, b_insn, u_insn, j_insn] =
+        std::array::from_fn(|idx| instruction_type_bits[idx]);
+
+    let InstructionChunks {
+        imm11,
+        imm4_1,
+        funct3,
+        rs1_low,
+        rs1_high,
+        rs2_low,
+        rs2_high,
+        imm10_5,
+        sign_bit,
+        ..
+    } = chunks;
+    let (imm4_1, funct3, rs1_low, rs1_high, rs2_high, imm10_5, sign_bit) = (
+        *imm4_1, *funct3, *rs1_low, *rs1_high, *rs2_high, *imm10_5, *sign_bit,
+    );
+
+    // the actual constant is dependent on the opcode type:
+    // -------------------------------------------------------------------------------------------------------|
+    // |       chunk5[31-16]    |   chunk4[15-12]   | chunk3[11] | chunk2[10-5] | chunk1[4-1] | chunk0[0] |   |
+    // |========================|===================|============|==============|=============|===========|===|
+    // |         sign_bit       |    sign_bit       |  sign_bit  |   imm[10-5]  |   rs2_high  |  rs2_low  | I |
+    // |------------------------|-------------------|------------|--------------|-------------|-----------|---|
+    // |         sign_bit       |    sign_bit       |  sign_bit  |   imm[10-5]  |   imm4_1    |   imm11   | S |
+    // |------------------------|-------------------|------------|--------------|-------------|-----------|---|
+    // |         sign_bit       |    sign_bit       |   imm11    |   imm[10-5]  |   imm4_1    |     0     | B |
+    // |------------------------|-------------------|------------|--------------|-------------|-----------|---|
+    // |         insn_high      | rs1_low || funct3 |      0     |      0       |      0      |     0     | U |
+    // |------------------------|-------------------|------------|--------------|-------------|-----------|---|
+    // |  sign_bit || rs1_high  | rs1_low || funct3 |  rs2_low   |   imm[10-5]  |   rs2_high  |     0     | J |
+    // |========================|===================|============|==============|=============|===========|===|
+    // hence:
+    // chunk0 = i_insn * rs2_low +  s_insn * imm11
+    // chunk1 = (i_insn + j_insn) * rs2_high + (s_insn + b_insn) * imm4_1
+    // chunk2 = (1 - u_insn) * imm10_5
+    // chunk3 = (i_insn + s_insn) * sign_bit + b_insn * imm11 + j_insn * rs2_low
+    // chunk4 = (i_insn + s_insn + b_insn) * sign_bit * 0b1111 + (u_insn + j_insn) * (rs1_low << 3 + funct3)
+    // chunk5 = {
+    //      j_insn * (sign_bit * 0xfff0 + rs1_high) + u_insn * insn_high +
+    //      (1 - j_insn - b_insn) * sign_bit * 0xffff
+    // }
+
+    // chunks 0..4 are used for linear constraint later on to form imm_low
+    let chunks_defining_constraints: [Constraint<F>; 5] = [
+        // 0
+        Term::from(i_insn) * rs2_low.clone() + Term::from(s_insn) * imm11.clone(),
+        // 1
+        (Term::from(i_insn) + Term::from(j_insn)) * Term::from(rs2_high)
+            + (Term::from(s_insn) + Term::from(b_insn)) * Term::from(imm4_1),
+        // 2
+        (Term::from(1) - Term::from(u_insn)) * Term::from(imm10_5),
+        // 3
+        (Term::from(i_insn) + Term::from(s_insn)) * Term::from(sign_bit)
+            + Term::from(b_insn) * imm11.clone()
+            + Term::from(j_insn) * rs2_low.clone(),
+        // 4
+        (Term::from(i_insn) + Term::from(s_insn) + Term::from(b_insn))
+            * Term::from(sign_bit)
+            * Term::from(0b1111u64)
+            + (Term::from(u_insn) + Term::from(j_insn))
+                * (Term::from(rs1_low) * Term::from(1 << 3) + (Term::from(funct3))),
+    ];
+
+    let [chunk0, chunk1, chunk2, chunk3, chunk4] = chunks_defining_constraints;
+
+    let imm_low = Num::Var(circuit.add_variable_from_constraint(
+        chunk0
+            + chunk1 * Term::from(1 << 1)
+            + chunk2 * Term::from(1 << 5)
+            + chunk3 * Term::from(1 << 11)
+            + chunk4 * Term::from(1 << 12),
+    ));
+
+    // chunk 5 is just higher part of the immediate
+    // This encodes sign-extension for all formats. For U format we take insn_high entirely.
+    let imm_high = Num::Var(circuit.add_variable_from_constraint(
+        Term::from(j_insn) * (Term::from(sign_bit) * Term::from(0xfff0) + Term::from(rs1_high))
+            + Term::from(u_insn) * Term::from(instruction.0[1])
+            + (Term::from(1) - Term::from(j_insn) - Term::from(u_insn))
+                * Term::from(sign_bit)
+                * Term::from(0xffff),
+    ));
+
+    Register([imm_low, imm_high])
+}
+
+/// Decomposes the instruction and builds sign-extended immediate for the format given by
+/// `instruction_type_bits` ([R,I,S,B,U,J] flags). R-type has no immediate, so the result is meaningless for it
+pub fn assemble_immediate<F: PrimeField, CS: Circuit<F>>(
+    circuit: &mut CS,
+    instruction: &Register<F>,
+    instruction_type_bits: &[Boolean],
+) -> Register<F> {
+    let chunks = decompose_instruction(circuit, instruction);
+    assemble_immediate_from_chunks(circuit, instruction, &chunks, instruction_type_bits)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cs::cs_reference::BasicAssembly;
+    use crate::cs::test_utils::NoOracle;
+    use field::Mersenne31Field;
+
+    type F = Mersenne31Field;
+
+    const R_TYPE: usize = 0;
+    const I_TYPE: usize = 1;
+    const S_TYPE: usize = 2;
+    const B_TYPE: usize = 3;
+    const U_TYPE: usize = 4;
+    const J_TYPE: usize = 5;
+
+    fn assemble(instruction: u32, instruction_type: usize) -> u32 {
+        let mut cs = BasicAssembly::<F>::new_with_oracle(NoOracle);
+        cs.materialize_table(TableType::QuickDecodeDecompositionCheck4x4x4);
+        cs.materialize_table(TableType::QuickDecodeDecompositionCheck7x3x6);
+
+        let input = [cs.add_variable(), cs.add_variable()];
+        let type_bits: [Boolean; NUM_INSTRUCTION_TYPES_IN_DECODE_BITS] =
+            std::array::from_fn(|_| cs.add_boolean_variable());
+        let type_bits_vars = type_bits.map(|el| el.get_variable().unwrap());
+        let value_fn = move |placer: &mut <BasicAssembly<F> as Circuit<F>>::WitnessPlacer| {
+            use crate::cs::witness_placer::*;
+
+            for (var, value) in input.iter().zip([instruction & 0xffff, instruction >> 16]) {
+                let value =
+                    WitnessComputationalField::constant(F::from_u64_unchecked(value as u64));
+                placer.assign_field(*var, &value);
+            }
+            for (idx, var) in type_bits_vars.iter().enumerate() {
+                placer.assign_mask(*var, &WitnessMask::constant(idx == instruction_type));
+            }
+        };
+        cs.set_values(value_fn);
+
+        let instruction = Register(input.map(|el| Num::Var(el)));
+        let imm = assemble_immediate(&mut cs, &instruction, &type_bits);
+        assert!(cs.is_satisfied());
+
+        imm.get_value_unsigned(&cs).unwrap()
+    }
+
+    #[test]
+    fn test_assemble_i_type_immediate() {
+        // addi x1, x0, -1
+        assert_eq!(assemble(0xfff00093, I_TYPE), -1i32 as u32);
+        // lw x5, 2047(x2)
+        assert_eq!(assemble(0x7ff12283, I_TYPE), 2047);
+    }
+
+    #[test]
+    fn test_assemble_s_type_immediate() {
+        // sw x2, -4(x1)
+        assert_eq!(assemble(0xfe20ae23, S_TYPE), -4i32 as u32);
+        // sb x7, 2020(x3)
+        assert_eq!(assemble(0x7e718223, S_TYPE), 2020);
+    }
+
+    #[test]
+    fn test_assemble_b_type_immediate() {
+        // beq x1, x2, -8
+        assert_eq!(assemble(0xfe208ce3, B_TYPE), -8i32 as u32);
+        // bne x6, x5, 2048
+        assert_eq!(assemble(0x005310e3, B_TYPE), 2048);
+    }
+
+    #[test]
+    fn test_assemble_u_type_immediate() {
+        // lui x1, 0x12345
+        assert_eq!(assemble(0x123450b7, U_TYPE), 0x12345000);
+        // auipc x3, 0xfffff
+        assert_eq!(assemble(0xfffff197, U_TYPE), 0xfffff000);
+    }
+
+    #[test]
+    fn test_assemble_j_type_immediate() {
+        // jal x1, -4
+        assert_eq!(assemble(0xffdff0ef, J_TYPE), -4i32 as u32);
+        // jal x0, 32766
+        assert_eq!(assemble(0x7ff0706f, J_TYPE), 32766);
+    }
+
+    #[test]
+    fn test_assemble_r_type_immediate_is_satisfiable() {
+        // add x3, x1, x2 has no immediate, but circuit must still be satisfied
+        let _ = assemble(0x002081b3, R_TYPE);
+    }
+}
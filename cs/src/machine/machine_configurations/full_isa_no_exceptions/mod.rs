@@ -36,6 +36,7 @@ impl<F: PrimeField> Machine<F> for FullIsaMachineNoExceptionHandling {
             Box::new(ConditionalOp::<true>),
             Box::new(ShiftOp::<true, false>),
             Box::new(JumpOp),
+            Box::new(FenceOp),
             Box::new(LoadOp::<true, true>),
             Box::new(StoreOp::<true>),
             Box::new(CsrOp::<false, false, false>),
@@ -66,6 +67,7 @@ impl<F: PrimeField> Machine<F> for FullIsaMachineNoExceptionHandling {
             BS,
         >>::define_used_tables());
         set.extend(<JumpOp as MachineOp<F, ST<F>, RS<F>, DE<F>, BS>>::define_used_tables());
+        set.extend(<FenceOp as MachineOp<F, ST<F>, RS<F>, DE<F>, BS>>::define_used_tables());
         set.extend(<LoadOp<true, true> as MachineOp<
             F,
             ST<F>,
@@ -176,6 +176,16 @@ pub(crate) fn optimized_base_isa_state_transition<
     application_results.push(application_result);
     cs.set_log(&opt_ctx, "JUMP");
 
+    let application_result = FenceOp::apply::<_, ASSUME_TRUSTED_CODE, OUTPUT_EXACT_EXCEPTIONS>(
+        cs,
+        &initial_state,
+        &decoder_output,
+        &flags_source,
+        &mut opt_ctx,
+    );
+    application_results.push(application_result);
+    cs.set_log(&opt_ctx, "FENCE");
+
     let [rs1_query, mut rs2_or_mem_load_query, mut rd_or_mem_store_query] = memory_queries;
 
     let application_result = LoadOp::<true, true>::spec_apply::<
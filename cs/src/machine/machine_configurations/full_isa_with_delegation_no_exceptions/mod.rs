@@ -32,6 +32,7 @@ impl<F: PrimeField> Machine<F> for FullIsaMachineWithDelegationNoExceptionHandli
             Box::new(ConditionalOp::<true>),
             Box::new(ShiftOp::<true, false>),
             Box::new(JumpOp),
+            Box::new(FenceOp),
             Box::new(LoadOp::<true, true>),
             Box::new(StoreOp::<true>),
             Box::new(CsrOp::<false, false, false>),
@@ -62,6 +63,7 @@ impl<F: PrimeField> Machine<F> for FullIsaMachineWithDelegationNoExceptionHandli
             BS,
         >>::define_used_tables());
         set.extend(<JumpOp as MachineOp<F, ST<F>, RS<F>, DE<F>, BS>>::define_used_tables());
+        set.extend(<FenceOp as MachineOp<F, ST<F>, RS<F>, DE<F>, BS>>::define_used_tables());
         set.extend(<LoadOp<true, true> as MachineOp<
             F,
             ST<F>,
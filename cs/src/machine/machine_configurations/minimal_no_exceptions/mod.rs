@@ -36,6 +36,7 @@ impl<F: PrimeField> Machine<F> for MinimalMachineNoExceptionHandling {
             Box::new(ConditionalOp::<true>),
             Box::new(ShiftOp::<true, false>),
             Box::new(JumpOp),
+            Box::new(FenceOp),
             Box::new(LoadOp::<false, false>),
             Box::new(StoreOp::<false>),
             Box::new(MopOp),
@@ -69,6 +70,7 @@ impl<F: PrimeField> Machine<F> for MinimalMachineNoExceptionHandling {
             BS,
         >>::define_used_tables());
         set.extend(<JumpOp as MachineOp<F, ST<F>, RS<F>, DE<F>, BS>>::define_used_tables());
+        set.extend(<FenceOp as MachineOp<F, ST<F>, RS<F>, DE<F>, BS>>::define_used_tables());
         set.extend(<LoadOp<false, false> as MachineOp<
             F,
             ST<F>,
@@ -150,6 +150,16 @@ pub(crate) fn optimized_reduced_isa_state_transition<
     application_results.push(application_result);
     cs.set_log(&opt_ctx, "JUMP");
 
+    let application_result = FenceOp::apply::<_, ASSUME_TRUSTED_CODE, OUTPUT_EXACT_EXCEPTIONS>(
+        cs,
+        &initial_state,
+        &decoder_output,
+        &flags_source,
+        &mut opt_ctx,
+    );
+    application_results.push(application_result);
+    cs.set_log(&opt_ctx, "FENCE");
+
     let application_result = MopOp::apply::<_, ASSUME_TRUSTED_CODE, OUTPUT_EXACT_EXCEPTIONS>(
         cs,
         &initial_state,
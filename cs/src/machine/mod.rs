@@ -183,6 +183,69 @@ pub trait MachineOp<
     ) -> CommonDiffs<F>;
 }
 
+/// Instruction in the bytecode that is rejected by some machine's decoder
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnsupportedInstruction {
+    pub address: u32,
+    pub opcode: u32,
+    /// Family and variants in the full ISA, or `None` if instruction is not known at all
+    pub family: Option<(
+        DecoderMajorInstructionFamilyKey,
+        &'static [DecoderInstructionVariantsKey],
+    )>,
+}
+
+impl std::fmt::Display for UnsupportedInstruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.family {
+            Some((major, minors)) => {
+                write!(f, "{}", major.0)?;
+                if minors.is_empty() == false {
+                    let minors: Vec<_> = minors.iter().map(|el| el.0).collect();
+                    write!(f, " ({})", minors.join(", "))?;
+                }
+            }
+            None => {
+                write!(f, "unknown instruction")?;
+            }
+        }
+        write!(f, " 0x{:08x} at 0x{:08x}", self.opcode, self.address)
+    }
+}
+
+/// Classification of a raw instruction word the same way as the decoder table does it, but without
+/// building any constraints, so it can be used to analyze the bytecode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InstructionClass {
+    /// Instruction is accepted by the machine's decoder
+    Valid(DecoderMajorInstructionFamilyKey),
+    /// Instruction is not known even in the full ISA
+    InvalidOpcode,
+    /// Instruction belongs to the given family of the full ISA, but machine's decoder rejects it
+    UnsupportedByMachine(DecoderMajorInstructionFamilyKey),
+    /// UNIMP (`csrrw x0, cycle, x0`), that is rejected by the main circuit before decoding
+    Unimp,
+}
+
+/// Finds which of the `opcodes` accepts the instruction, and returns its decoder keys
+pub fn decode_instruction_family(
+    opcodes: &[Box<dyn DecodableMachineOp>],
+    instruction: u32,
+) -> Option<(
+    DecoderMajorInstructionFamilyKey,
+    &'static [DecoderInstructionVariantsKey],
+)> {
+    let op = instruction & 0b111_1111;
+    let funct3 = (instruction >> 12) & 0b111;
+    let funct7 = (instruction >> 25) & 0b111_1111;
+    opcodes.iter().find_map(|supported_op| {
+        supported_op
+            .define_decoder_subspace(op as u8, funct3 as u8, funct7 as u8)
+            .ok()
+            .map(|(_, major, minors)| (major, minors))
+    })
+}
+
 pub trait Machine<F: PrimeField>: 'static + Clone + Default {
     const ASSUME_TRUSTED_CODE: bool;
     const OUTPUT_EXACT_EXCEPTIONS: bool;
@@ -372,6 +435,53 @@ pub trait Machine<F: PrimeField>: 'static + Clone + Default {
         unsupported_opcodes
     }
 
+    /// Major instruction families that this machine's decoder accepts
+    fn supported_instruction_families() -> Vec<DecoderMajorInstructionFamilyKey> {
+        Self::all_decoder_keys().all_major_keys()
+    }
+
+    /// Same as `verify_bytecode_base`, but also resolves every rejected instruction into
+    /// the family it belongs to in the full ISA, so the caller can report what exactly is
+    /// not supported and where, instead of getting an unsatisfiable circuit later on
+    fn find_unsupported_instructions(
+        bytecode: &[u32],
+        base_address: u32,
+    ) -> Vec<UnsupportedInstruction> {
+        use crate::machine::machine_configurations::full_isa_no_exceptions::FullIsaMachineNoExceptionHandling;
+
+        let reference_opcodes =
+            <FullIsaMachineNoExceptionHandling as Machine<F>>::all_supported_opcodes();
+        Self::verify_bytecode_base(bytecode)
+            .into_iter()
+            .map(|(pos, opcode)| {
+                let family = decode_instruction_family(&reference_opcodes, opcode);
+                UnsupportedInstruction {
+                    address: base_address + (pos as u32) * 4,
+                    opcode,
+                    family,
+                }
+            })
+            .collect()
+    }
+
+    /// Classifies a single instruction as this machine's decoder would see it
+    fn classify_instruction(raw: u32) -> InstructionClass {
+        use crate::machine::machine_configurations::full_isa_no_exceptions::FullIsaMachineNoExceptionHandling;
+
+        if raw == UNIMP_OPCODE {
+            return InstructionClass::Unimp;
+        }
+        if let Some((major, _)) = decode_instruction_family(&Self::all_supported_opcodes(), raw) {
+            return InstructionClass::Valid(major);
+        }
+        let reference_opcodes =
+            <FullIsaMachineNoExceptionHandling as Machine<F>>::all_supported_opcodes();
+        match decode_instruction_family(&reference_opcodes, raw) {
+            Some((major, _)) => InstructionClass::UnsupportedByMachine(major),
+            None => InstructionClass::InvalidOpcode,
+        }
+    }
+
     fn describe_state_transition<CS: Circuit<F>, const ROM_ADDRESS_SPACE_SECOND_WORD_BITS: usize>(
         circuit: &mut CS,
     ) -> (Self::State, Self::State)
@@ -422,6 +532,59 @@ mod test {
         dbg!(splitting);
     }
 
+    #[test]
+    fn test_find_unsupported_instructions() {
+        use crate::machine::ops::MUL_COMMON_OP_KEY;
+
+        // addi x1, x0, 1; mulh x3, x1, x2; mul x3, x1, x2
+        let binary = [0x00100093, 0x022091b3, 0x022081b3];
+        let families =
+            <MinimalMachineNoExceptionHandling as Machine<F>>::supported_instruction_families();
+        assert!(families.contains(&MUL_COMMON_OP_KEY) == false);
+
+        let unsupported =
+            <MinimalMachineNoExceptionHandling as Machine<F>>::find_unsupported_instructions(
+                &binary, 0x8000,
+            );
+        assert_eq!(unsupported.len(), 2);
+        assert_eq!(unsupported[0].address, 0x8004);
+        assert_eq!(unsupported[0].family.unwrap().0, MUL_COMMON_OP_KEY);
+        assert_eq!(unsupported[1].address, 0x8008);
+        assert_eq!(
+            unsupported[0].to_string(),
+            "MUL_COMMON_KEY (MULH) 0x022091b3 at 0x00008004"
+        );
+        assert_eq!(
+            unsupported[1].to_string(),
+            "MUL_COMMON_KEY (MUL) 0x022081b3 at 0x00008008"
+        );
+    }
+
+    #[test]
+    fn test_classify_instruction() {
+        use crate::machine::ops::{ADD_OP_KEY, JUMP_COMMON_OP_KEY, MUL_COMMON_OP_KEY};
+
+        let classify = <MinimalMachineNoExceptionHandling as Machine<F>>::classify_instruction;
+        // addi x1, x0, 1
+        assert_eq!(classify(0x00100093), InstructionClass::Valid(ADD_OP_KEY));
+        // jal x1, -4
+        assert_eq!(
+            classify(0xffdff0ef),
+            InstructionClass::Valid(JUMP_COMMON_OP_KEY)
+        );
+        // mul x3, x1, x2
+        assert_eq!(
+            classify(0x022081b3),
+            InstructionClass::UnsupportedByMachine(MUL_COMMON_OP_KEY)
+        );
+        assert_eq!(classify(UNIMP_OPCODE), InstructionClass::Unimp);
+        // all zeroes and all ones are both defined as illegal instructions
+        assert_eq!(classify(0), InstructionClass::InvalidOpcode);
+        assert_eq!(classify(0xffffffff), InstructionClass::InvalidOpcode);
+        // 64-bit only ld x1, 0(x2)
+        assert_eq!(classify(0x00013083), InstructionClass::InvalidOpcode);
+    }
+
     #[ignore = "depends on ZKsync OS"]
     #[test]
     fn check_binary() {
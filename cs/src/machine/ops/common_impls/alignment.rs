@@ -0,0 +1,100 @@
+use super::*;
+
+/// Makes misaligned memory access unsatisfiable instead of silently producing a wrong witness.
+/// Width of the access comes from funct3 via decoder flags: LW/SW require both lowest address
+/// bits to be zero, LH/LHU/SH require the lowest bit to be zero, and byte access has no
+/// requirement. `address_bits` are the lowest two bits of the address from the
+/// `MemoryOffsetGetBits` table
+pub fn constrain_access_alignment<F: PrimeField, CS: Circuit<F>>(
+    cs: &mut CS,
+    address_bits: [Variable; 2],
+    exec_word: Boolean,
+    exec_half_word: Boolean,
+) {
+    let [bit_0, bit_1] = address_bits;
+    // bits come from the table, so the sum is zero only if both are zero
+    cs.add_constraint((Term::from(bit_0) + Term::from(bit_1)) * exec_word.get_terms());
+    cs.add_constraint(Term::from(bit_0) * exec_half_word.get_terms());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cs::cs_reference::BasicAssembly;
+    use crate::cs::test_utils::NoOracle;
+    use field::Mersenne31Field;
+
+    type F = Mersenne31Field;
+
+    const LW: u32 = 0b010;
+    const LH: u32 = 0b001;
+    const LHU: u32 = 0b101;
+    const LB: u32 = 0b000;
+    const LBU: u32 = 0b100;
+    const SW: u32 = 0b010;
+    const SH: u32 = 0b001;
+    const SB: u32 = 0b000;
+
+    fn is_satisfied(address: u32, funct3: u32) -> bool {
+        let mut cs = BasicAssembly::<F>::new_with_oracle(NoOracle);
+        cs.materialize_table(TableType::MemoryOffsetGetBits);
+
+        let address_low = cs.add_variable();
+        let exec_word = cs.add_boolean_variable();
+        let exec_half_word = cs.add_boolean_variable();
+        let flags = [exec_word, exec_half_word].map(|el| el.get_variable().unwrap());
+        // width is encoded in the lowest two bits of funct3 for both loads and stores
+        let width = funct3 & 0b11;
+        let flag_values = [width == 0b10, width == 0b01];
+        let value_fn = move |placer: &mut <BasicAssembly<F> as Circuit<F>>::WitnessPlacer| {
+            use crate::cs::witness_placer::*;
+
+            let value = F::from_u64_unchecked((address & 0xffff) as u64);
+            placer.assign_field(address_low, &WitnessComputationalField::constant(value));
+            for (var, value) in flags.iter().zip(flag_values) {
+                placer.assign_mask(*var, &WitnessMask::constant(value));
+            }
+        };
+        cs.set_values(value_fn);
+
+        let address_bits = cs.get_variables_from_lookup_constrained(
+            &[LookupInput::from(address_low)],
+            TableType::MemoryOffsetGetBits,
+        );
+        constrain_access_alignment(&mut cs, address_bits, exec_word, exec_half_word);
+
+        cs.is_satisfied()
+    }
+
+    #[test]
+    fn test_load_alignment() {
+        assert!(is_satisfied(0x1000, LW));
+        assert!(is_satisfied(0xfffc, LW));
+        for address in [0x1001, 0x1002, 0x1003] {
+            assert!(!is_satisfied(address, LW));
+        }
+        for funct3 in [LH, LHU] {
+            assert!(is_satisfied(0x1002, funct3));
+            assert!(!is_satisfied(0x1001, funct3));
+            assert!(!is_satisfied(0x1003, funct3));
+        }
+        for funct3 in [LB, LBU] {
+            for address in 0x1000..0x1004 {
+                assert!(is_satisfied(address, funct3));
+            }
+        }
+    }
+
+    #[test]
+    fn test_store_alignment() {
+        assert!(is_satisfied(0x2000, SW));
+        assert!(!is_satisfied(0x2002, SW));
+        assert!(is_satisfied(0x2002, SH));
+        assert!(!is_satisfied(0x2001, SH));
+        assert!(!is_satisfied(0x2003, SH));
+        // byte stores are never constrained
+        for address in 0x2000..0x2004 {
+            assert!(is_satisfied(address, SB));
+        }
+    }
+}
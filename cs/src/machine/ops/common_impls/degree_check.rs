@@ -0,0 +1,51 @@
+use super::*;
+
+/// Checks that every constraint emitted by the op named `op_name` is at most quadratic,
+/// and returns a message naming the op and the first offending constraint otherwise
+pub fn check_all_quadratic<F: PrimeField>(
+    op_name: &str,
+    constraints: &[Constraint<F>],
+) -> Result<(), String> {
+    for (idx, constraint) in constraints.iter().enumerate() {
+        let degree = constraint.degree();
+        if degree > 2 {
+            return Err(format!(
+                "op {} emitted constraint {} of degree {}: {:?}",
+                op_name, idx, degree, constraint
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Batch version of the degree check that is done by `Constraint::normalize`, but that can be
+/// run after the op finishes emitting constraints, so failure is attributed to the op.
+/// Does nothing in release builds
+#[track_caller]
+pub fn debug_assert_all_quadratic<F: PrimeField>(op_name: &str, constraints: &[Constraint<F>]) {
+    if cfg!(debug_assertions) {
+        if let Err(message) = check_all_quadratic(op_name, constraints) {
+            panic!("{}", message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use field::Mersenne31Field;
+
+    #[test]
+    fn test_cubic_constraint_is_attributed_to_op() {
+        let a = Term::<Mersenne31Field>::from(Variable(0));
+        let b = Term::<Mersenne31Field>::from(Variable(1));
+        let c = Term::<Mersenne31Field>::from(Variable(2));
+        let quadratic = a * b;
+        let cubic = quadratic.terms[0] * c;
+
+        assert!(check_all_quadratic("MUL", &[quadratic.clone()]).is_ok());
+        let message = check_all_quadratic("MUL", &[quadratic, cubic]).unwrap_err();
+        assert!(message.starts_with("op MUL emitted constraint 1 of degree 3"));
+    }
+}
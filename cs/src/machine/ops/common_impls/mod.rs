@@ -1,7 +1,13 @@
 use super::*;
 
+pub mod alignment;
 pub mod csr;
 pub mod csr_with_delegation;
+pub mod degree_check;
+pub mod range_check;
 
+pub use self::alignment::*;
 pub use self::csr::*;
 pub use self::csr_with_delegation::*;
+pub use self::degree_check::*;
+pub use self::range_check::*;
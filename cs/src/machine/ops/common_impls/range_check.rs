@@ -0,0 +1,125 @@
+use super::*;
+
+/// Enforces that `value` fits into `bits` bits. Value is decomposed into 16-bit limbs that go
+/// into the large range check table, and if the highest limb is partial then it is additionally
+/// checked by range checking its shifted copy, so `limb * 2^(16 - partial_bits)` is also 16 bits.
+/// All the checks are plain range check invariants, so they are counted by the same multiplicities
+/// as any other range check in the circuit
+pub fn range_check<F: PrimeField, CS: Circuit<F>>(cs: &mut CS, value: Num<F>, bits: usize) {
+    assert!(bits > 0);
+    // otherwise decomposition is not unique
+    assert!(
+        bits < F::CHAR_BITS,
+        "can not range check {} bits in the field of {} bits",
+        bits,
+        F::CHAR_BITS
+    );
+
+    let value = match value {
+        Num::Constant(constant) => {
+            assert!(
+                constant.as_u64_reduced() < (1u64 << bits),
+                "constant {:?} does not fit into {} bits",
+                constant,
+                bits
+            );
+            return;
+        }
+        Num::Var(var) => var,
+    };
+
+    const LIMB_WIDTH: usize = LARGE_RANGE_CHECK_TABLE_WIDTH;
+
+    let num_limbs = bits.div_ceil(LIMB_WIDTH);
+    let last_limb_bits = bits - (num_limbs - 1) * LIMB_WIDTH;
+    let limbs: Vec<Variable> = (0..num_limbs).map(|_| cs.add_variable()).collect();
+
+    {
+        let limbs = limbs.clone();
+        let value_fn = move |placer: &mut CS::WitnessPlacer| {
+            use crate::cs::witness_placer::*;
+
+            let value = placer.get_field(value).as_integer();
+            for (idx, limb) in limbs.iter().enumerate() {
+                let mut limb_value = value.shr((idx * LIMB_WIDTH) as u32);
+                if idx == num_limbs - 1 {
+                    limb_value = limb_value.get_lowest_bits(last_limb_bits as u32);
+                }
+                placer.assign_u16(*limb, &limb_value.truncate());
+            }
+        };
+        cs.set_values(value_fn);
+    }
+
+    for (idx, limb) in limbs.iter().enumerate() {
+        let width = if idx == num_limbs - 1 && last_limb_bits == SMALL_RANGE_CHECK_TABLE_WIDTH {
+            SMALL_RANGE_CHECK_TABLE_WIDTH
+        } else {
+            LARGE_RANGE_CHECK_TABLE_WIDTH
+        };
+        cs.require_invariant(
+            *limb,
+            Invariant::RangeChecked {
+                width: width as u32,
+            },
+        );
+    }
+
+    if last_limb_bits != LIMB_WIDTH && last_limb_bits != SMALL_RANGE_CHECK_TABLE_WIDTH {
+        // limb is already 16 bits, so shifted value is below 2^31 and can not wrap around the modulus
+        let last_limb = *limbs.last().unwrap();
+        let shift = F::from_u64_unchecked(1u64 << (LIMB_WIDTH - last_limb_bits));
+        let shifted = cs.add_variable_from_constraint_allow_explicit_linear(Constraint::from(
+            Term::from((shift, last_limb)),
+        ));
+        cs.require_invariant(
+            shifted,
+            Invariant::RangeChecked {
+                width: LIMB_WIDTH as u32,
+            },
+        );
+    }
+
+    let mut constraint = Constraint::<F>::empty();
+    for (idx, limb) in limbs.iter().enumerate() {
+        constraint += Term::from((F::from_u64_unchecked(1u64 << (idx * LIMB_WIDTH)), *limb));
+    }
+    constraint -= Term::from(value);
+    cs.add_constraint_allow_explicit_linear(constraint);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cs::cs_reference::BasicAssembly;
+    use crate::cs::test_utils::{assign_variable, NoOracle};
+    use field::Mersenne31Field;
+
+    type F = Mersenne31Field;
+
+    fn check(value: u32, bits: usize) -> bool {
+        let mut cs = BasicAssembly::<F>::new_with_oracle(NoOracle);
+        let var = assign_variable(&mut cs, value as u64);
+        range_check(&mut cs, Num::Var(var), bits);
+
+        cs.is_satisfied()
+    }
+
+    #[test]
+    fn test_range_check_value_that_fits() {
+        assert!(check(0, 1));
+        assert!(check(0xff, 8));
+        assert!(check(0xffff, 16));
+        assert!(check((1 << 18) - 1, 18));
+        assert!(check((1 << 24) - 1, 24));
+        assert!(check((1 << 30) - 1, 30));
+    }
+
+    #[test]
+    fn test_range_check_value_that_does_not_fit() {
+        assert!(check(2, 1) == false);
+        assert!(check(0x100, 8) == false);
+        assert!(check(1 << 18, 18) == false);
+        assert!(check(1 << 24, 24) == false);
+    }
+}
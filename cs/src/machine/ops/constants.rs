@@ -8,5 +8,6 @@ pub const OPERATION_JAL: u8 = 0b110_1111;
 pub const OPERATION_JALR: u8 = 0b110_0111;
 pub const OPERATION_LOAD: u8 = 0b000_0011;
 pub const OPERATION_STORE: u8 = 0b010_0011;
+pub const OPERATION_MISC_MEM: u8 = 0b000_1111;
 pub const OPERATION_SYSTEM: u8 = 0b111_0011;
 pub const MACHINE_PRIV: u8 = 0b000;
@@ -0,0 +1,128 @@
+use super::*;
+use crate::machine::decoder::decode_optimized_must_handle_csr::is_fence_family;
+
+pub const FENCE_OP_KEY: DecoderMajorInstructionFamilyKey =
+    DecoderMajorInstructionFamilyKey("FENCE/FENCE.I");
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FenceOp;
+
+impl DecodableMachineOp for FenceOp {
+    fn define_decoder_subspace(
+        &self,
+        opcode: u8,
+        func3: u8,
+        _func7: u8,
+    ) -> Result<
+        (
+            InstructionType,
+            DecoderMajorInstructionFamilyKey,
+            &'static [DecoderInstructionVariantsKey],
+        ),
+        (),
+    > {
+        if is_fence_family(opcode, func3) {
+            // FENCE/FENCE.I. Even though it's encoded as I-type, we decode it as B-type,
+            // so that RD field is ignored and the write is modeled as 0 into x0, same as for BRANCH
+            Ok((InstructionType::BType, FENCE_OP_KEY, &[][..]))
+        } else {
+            Err(())
+        }
+    }
+}
+
+impl<
+        F: PrimeField,
+        ST: BaseMachineState<F>,
+        RS: RegisterValueSource<F>,
+        DE: DecoderOutputSource<F, RS>,
+        BS: IndexableBooleanSet,
+    > MachineOp<F, ST, RS, DE, BS> for FenceOp
+{
+    fn apply<
+        CS: Circuit<F>,
+        const ASSUME_TRUSTED_CODE: bool,
+        const OUTPUT_EXACT_EXCEPTIONS: bool,
+    >(
+        cs: &mut CS,
+        _machine_state: &ST,
+        _inputs: &DE,
+        boolean_set: &BS,
+        opt_ctx: &mut OptimizationContext<F, CS>,
+    ) -> CommonDiffs<F> {
+        opt_ctx.reset_indexers();
+        let exec_flag = boolean_set.get_major_flag(FENCE_OP_KEY);
+
+        // Our memory is linear and execution is in-order, so there is nothing to do:
+        // no register or memory effects, and PC just advances
+        CommonDiffs {
+            exec_flag,
+            trapped: None,
+            trap_reason: None,
+            rd_value: vec![],
+            new_pc_value: NextPcValue::Default,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::machine::machine_configurations::full_isa_with_delegation_no_exceptions::FullIsaMachineWithDelegationNoExceptionHandling;
+    use crate::machine::machine_configurations::minimal_no_exceptions::MinimalMachineNoExceptionHandling;
+    use field::Mersenne31Field;
+
+    fn decode(instruction: u32) -> Result<DecoderMajorInstructionFamilyKey, ()> {
+        let opcode = (instruction & 0b111_1111) as u8;
+        let funct3 = ((instruction >> 12) & 0b111) as u8;
+        let funct7 = ((instruction >> 25) & 0b111_1111) as u8;
+        FenceOp
+            .define_decoder_subspace(opcode, funct3, funct7)
+            .map(|(_, key, _)| key)
+    }
+
+    fn decoder_table_entry<M: Machine<Mersenne31Field>>(instruction: u32) -> u64 {
+        let opcode = instruction & 0b111_1111;
+        let funct3 = (instruction >> 12) & 0b111;
+        let funct7 = (instruction >> 25) & 0b111_1111;
+        let (_, table) = M::produce_decoder_table_stub();
+        table[(opcode + (funct3 << 7) + (funct7 << 10)) as usize]
+    }
+
+    #[test]
+    fn test_fence_decoding() {
+        // fence iorw, iorw
+        assert_eq!(decode(0x0ff0000f), Ok(FENCE_OP_KEY));
+        // fence rw, rw
+        assert_eq!(decode(0x0330000f), Ok(FENCE_OP_KEY));
+        // fence.tso
+        assert_eq!(decode(0x8330000f), Ok(FENCE_OP_KEY));
+        // fence.i
+        assert_eq!(decode(0x0000100f), Ok(FENCE_OP_KEY));
+        // other funct3 values in MISC-MEM are not FENCE
+        assert_eq!(decode(0x0000200f), Err(()));
+        // addi x0, x0, 0
+        assert_eq!(decode(0x00000013), Err(()));
+    }
+
+    #[test]
+    fn test_fence_is_valid_in_decoder_table() {
+        const INVALID_BIT: u64 = 1;
+        const B_TYPE_BIT: u64 = (1 << InstructionType::BType as u64) << 1;
+
+        for instruction in [0x0ff0000f, 0x0140000f, 0x0000100f] {
+            let full_isa =
+                decoder_table_entry::<FullIsaMachineWithDelegationNoExceptionHandling>(instruction);
+            let minimal = decoder_table_entry::<MinimalMachineNoExceptionHandling>(instruction);
+            for entry in [full_isa, minimal] {
+                assert_eq!(entry & INVALID_BIT, 0);
+                assert_ne!(entry & B_TYPE_BIT, 0);
+            }
+        }
+        // funct3 outside of FENCE/FENCE.I stays invalid
+        assert_eq!(
+            decoder_table_entry::<FullIsaMachineWithDelegationNoExceptionHandling>(0x0000200f),
+            basic_invalid_bitmask()
+        );
+    }
+}
@@ -7,6 +7,40 @@ pub const JAL_OP_KEY: DecoderInstructionVariantsKey = DecoderInstructionVariants
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct JumpOp;
 
+/// Computes jump target as `pc + imm` for JAL and `rs1 + imm` for JALR. Lowest limb of the target
+/// is the output of the `JumpCleanupOffset` lookup, that clears two lowest bits, so JALR's
+/// `target & !1` is enforced by the lookup and not assumed. Also returns the 2nd lowest bit of the
+/// unmasked target, that must be zero for aligned jump. The lowest bit is ignored:
+/// - if it's JAL, then we add PC (0 mod 4) with immediate, that encodes 2-byte offset, so it's 0 mod 2 anyway
+/// - if it's JALR then we should clean the lowest bit anyway, so we only need to check 2nd bit
+pub fn compute_jump_target<F: PrimeField, CS: Circuit<F>>(
+    cs: &mut CS,
+    is_jal: Boolean,
+    pc: Register<F>,
+    rs1: Register<F>,
+    imm: Register<F>,
+    exec_flag: Boolean,
+    opt_ctx: &mut OptimizationContext<F, CS>,
+) -> (Register<F>, Variable) {
+    let base = Register::choose::<CS>(cs, &is_jal, &pc, &rs1);
+    let (x, _of_flag) = opt_ctx.append_add_relation(base, imm, exec_flag, cs);
+    let [bit_1, dst_low] = opt_ctx.append_lookup_relation(
+        cs,
+        &[x.0[0].get_variable()],
+        TableType::JumpCleanupOffset.to_num(),
+        exec_flag,
+    );
+    let dst = Register([Num::Var(dst_low), x.0[1]]);
+
+    (dst, bit_1)
+}
+
+/// Return address written into rd, that is `pc + 4` as computed by the decoder. Write into x0 is
+/// discarded at writeback, same as for any other opcode
+pub fn jump_return_address<F: PrimeField>(pc_next: Register<F>) -> [Constraint<F>; 2] {
+    pc_next.0.map(|el| Constraint::<F>::from(el.get_variable()))
+}
+
 impl DecodableMachineOp for JumpOp {
     fn define_decoder_subspace(
         &self,
@@ -78,27 +112,15 @@ impl<
         let is_jal = boolean_set.get_minor_flag(JUMP_COMMON_OP_KEY, JAL_OP_KEY);
         let pc = *machine_state.get_pc();
 
-        let src1 = Register::choose::<CS>(cs, &is_jal, &pc, &src1);
-        let (x, _of_flag) = opt_ctx.append_add_relation(src1, imm, exec_flag, cs);
+        let (dst, bit_1) = compute_jump_target(cs, is_jal, pc, src1, imm, exec_flag, opt_ctx);
+        let returned_value = jump_return_address(pc_next);
 
         if ASSUME_TRUSTED_CODE {
-            // - if it's JALR then we should clean the lowest bit anyway, so we only need to check 2nd bit
-
-            let [bit_1, dst_low] = opt_ctx.append_lookup_relation(
-                cs,
-                &[x.0[0].get_variable()],
-                TableType::JumpCleanupOffset.to_num(),
-                exec_flag,
-            );
             let is_misaligned_addr = bit_1;
 
             // if we have misasigned jump then we should make it unprovable circuit
             cs.add_constraint(Term::from(is_misaligned_addr) * exec_flag.get_terms());
 
-            let dst_low = Num::Var(dst_low);
-            let dst_high = x.0[1];
-            let dst = Register([dst_low, dst_high]);
-
             if exec_flag.get_value(cs).unwrap_or(false) {
                 println!("JUMP");
                 if is_jal.get_value(cs).unwrap() {
@@ -111,11 +133,6 @@ impl<
                 dbg!(pc_next.get_value_unsigned(cs));
             }
 
-            let returned_value = [
-                Constraint::<F>::from(pc_next.0[0].get_variable()),
-                Constraint::<F>::from(pc_next.0[1].get_variable()),
-            ];
-
             CommonDiffs {
                 exec_flag,
                 trapped: None,
@@ -124,22 +141,7 @@ impl<
                 new_pc_value: NextPcValue::Custom(dst),
             }
         } else {
-            // NOTE why we only check 2nd bit and completely ignore lowest one:
-            // - if it's JAL, then we add PC (0 mod 4) with immediate, that encodes 2-byte offset, so it's 0 mod 2 anyway
-            // - if it's JALR then we should clean the lowest bit anyway, so we only need to check 2nd bit
-
-            let [bit_1, dst_low] = opt_ctx.append_lookup_relation(
-                cs,
-                &[x.0[0].get_variable()],
-                TableType::JumpCleanupOffset.to_num(),
-                exec_flag,
-            );
-            let bit_1 = Boolean::Is(bit_1);
-            let is_misaligned_addr = bit_1;
-
-            let dst_low = Num::Var(dst_low);
-            let dst_high = x.0[1];
-            let dst = Register([dst_low, dst_high]);
+            let is_misaligned_addr = Boolean::Is(bit_1);
             let trapped = is_misaligned_addr;
             let trap_reason = Num::Constant(F::from_u64_unchecked(
                 TrapReason::InstructionAddressMisaligned as u64,
@@ -160,11 +162,6 @@ impl<
                 }
             }
 
-            let returned_value = [
-                Constraint::<F>::from(pc_next.0[0].get_variable()),
-                Constraint::<F>::from(pc_next.0[1].get_variable()),
-            ];
-
             CommonDiffs {
                 exec_flag,
                 trapped: Some(trapped),
@@ -175,3 +172,107 @@ impl<
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cs::cs_reference::BasicAssembly;
+    use crate::cs::test_utils::{assign_boolean, assign_register, assign_variable, NoOracle};
+    use field::Mersenne31Field;
+
+    type F = Mersenne31Field;
+
+    // returns target and whether it's misaligned
+    fn jump_target(pc: u32, rs1: u32, imm: u32, is_jal: bool) -> (u32, bool) {
+        let mut cs = BasicAssembly::<F>::new_with_oracle(NoOracle);
+        cs.materialize_table(TableType::JumpCleanupOffset);
+        let mut opt_ctx = OptimizationContext::<F, _>::new();
+
+        let exec_flag = assign_boolean(&mut cs, true);
+        let is_jal_flag = assign_boolean(&mut cs, is_jal);
+        let pc_reg = assign_register(&mut cs, pc);
+        let rs1_reg = assign_register(&mut cs, rs1);
+        let imm_reg = assign_register(&mut cs, imm);
+        let (dst, bit_1) = compute_jump_target(
+            &mut cs,
+            is_jal_flag,
+            pc_reg,
+            rs1_reg,
+            imm_reg,
+            exec_flag,
+            &mut opt_ctx,
+        );
+        opt_ctx.enforce_all(&mut cs);
+        assert!(cs.is_satisfied());
+
+        let target = dst.get_value_unsigned(&cs).unwrap();
+        let is_misaligned = cs.get_value(bit_1).unwrap().as_u64_reduced() == 1;
+
+        // lowest limb must be the table output for the unmasked sum, and not the sum itself
+        assert!(matches!(dst.0[0], Num::Var(..)));
+        let unmasked = if is_jal { pc } else { rs1 }.wrapping_add(imm);
+        let table = TableType::JumpCleanupOffset.generate_table::<F>();
+        let [_, masked_low] =
+            table.lookup_value::<2>(&[F::from_u64_unchecked((unmasked & 0xffff) as u64)]);
+        assert_eq!(masked_low.as_u64_reduced(), (target & 0xffff) as u64);
+        assert_eq!(target >> 16, unmasked >> 16);
+
+        (target, is_misaligned)
+    }
+
+    // value that is written into rd, masked the same way as at writeback
+    fn written_return_address(pc: u32, rd: u64) -> u32 {
+        let mut cs = BasicAssembly::<F>::new_with_oracle(NoOracle);
+        let pc_next = assign_register(&mut cs, pc.wrapping_add(4));
+        let rd_var = assign_variable(&mut cs, rd);
+
+        let returned_value = jump_return_address(pc_next)
+            .map(|el| Num::Var(cs.add_variable_from_constraint_allow_explicit_linear(el)));
+        let rd_is_zero = cs.is_zero(Num::Var(rd_var));
+        let written = Register(returned_value).mask(&mut cs, rd_is_zero.toggle());
+        assert!(cs.is_satisfied());
+
+        written.get_value_unsigned(&cs).unwrap()
+    }
+
+    #[test]
+    fn test_jalr_clears_lowest_bit() {
+        // 0x8000_1000 + 5 = 0x8000_1005
+        assert_eq!(
+            jump_target(0x100, 0x8000_1000, 5, false),
+            (0x8000_1004, false)
+        );
+        // 0x1000 - 3 = 0x0ffd
+        assert_eq!(
+            jump_target(0x100, 0x1000, (-3i32) as u32, false),
+            (0x0ffc, false)
+        );
+        // odd target is fine, but 2nd bit is not
+        assert!(jump_target(0x100, 0x1000, 3, false).1);
+        // JAL doesn't use rs1
+        assert_eq!(jump_target(0x100, 0x1001, 8, true), (0x108, false));
+    }
+
+    #[test]
+    fn test_jal_backward() {
+        assert_eq!(
+            jump_target(0x2000, 0x1234_5678, (-16i32) as u32, true),
+            (0x1ff0, false)
+        );
+        // borrow from the high limb
+        assert_eq!(
+            jump_target(0x0001_0000, 0, (-4i32) as u32, true),
+            (0xfffc, false)
+        );
+        assert_eq!(jump_target(0x100, 0, (-0x100i32) as u32, true), (0, false));
+    }
+
+    #[test]
+    fn test_rd_x0() {
+        assert_eq!(written_return_address(0x1000, 1), 0x1004);
+        assert_eq!(written_return_address(0xffff_fffc, 31), 0);
+        assert_eq!(written_return_address(0x0000_fffc, 5), 0x0001_0000);
+        // return address is discarded
+        assert_eq!(written_return_address(0x1000, 0), 0);
+    }
+}
@@ -179,9 +179,7 @@ impl<const SUPPORT_SIGNED: bool, const SUPPORT_LESS_THAN_WORD: bool>
             // check alignment in case of subword accesses
             if ASSUME_TRUSTED_CODE {
                 // unprovable if we do not have proper alignment
-                cs.add_constraint((Term::from(bit_0) + Term::from(bit_1)) * exec_word.get_terms());
-
-                cs.add_constraint(Term::from(bit_0) * exec_half_word.get_terms());
+                constrain_access_alignment(cs, [bit_0, bit_1], exec_word, exec_half_word);
             } else {
                 todo!();
             }
@@ -399,7 +397,8 @@ impl<const SUPPORT_SIGNED: bool, const SUPPORT_LESS_THAN_WORD: bool>
                 execute_family,
             );
 
-            // This will also trap unaligned access
+            // This will also trap unaligned access into ROM, as the table only contains word aligned
+            // addresses. Alignment of RAM access is checked below
             let rom_address = Term::from(unaligned_address.0[0])
                 + Term::from((F::from_u64_unchecked(1 << 16), address_high_bits_for_rom));
 
@@ -420,6 +419,16 @@ impl<const SUPPORT_SIGNED: bool, const SUPPORT_LESS_THAN_WORD: bool>
                 Term::from(execute_family.get_variable().unwrap()) * Term::from(is_ram_range),
             );
 
+            // only LW is supported, so RAM access must be word aligned
+            let [bit_0, bit_1] = opt_ctx.append_lookup_relation(
+                cs,
+                &[unaligned_address.0[0].get_variable()],
+                TableType::MemoryOffsetGetBits.to_num(),
+                execute_family,
+            );
+            // unprovable if we do not have proper alignment
+            cs.add_constraint((Term::from(bit_0) + Term::from(bit_1)) * Term::from(is_ram_read));
+
             {
                 // ROM
 
@@ -16,6 +16,7 @@ pub mod binops;
 pub mod conditional;
 pub mod constants;
 pub mod csr;
+pub mod fence;
 pub mod jump;
 pub mod lui_auipc;
 // pub mod memory;
@@ -36,6 +37,7 @@ pub use self::binops::*;
 pub use self::conditional::*;
 pub use self::constants::*;
 pub use self::csr::*;
+pub use self::fence::*;
 pub use self::jump::*;
 pub use self::lui_auipc::*;
 // pub use self::memory::*;
@@ -161,7 +161,7 @@ impl<
                 } else if mulh_flag.get_value(cs).unwrap_or(false) {
                     println!("MULH");
                 } else if mulhsu_flag.get_value(cs).unwrap_or(false) {
-                    println!("MUL");
+                    println!("MULHSU");
                 } else {
                     println!("MULHU");
                 }
@@ -715,3 +715,26 @@ impl<
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // mulhsu x3, x1, x2
+    const MULHSU_INSTRUCTION: u32 = 0x0220a1b3;
+
+    #[test]
+    fn test_mulhsu_decoding() {
+        let signed: Vec<Box<dyn DecodableMachineOp>> = vec![Box::new(MulOp::<true>)];
+        assert_eq!(
+            decode_instruction_family(&signed, MULHSU_INSTRUCTION),
+            Some((MUL_COMMON_OP_KEY, &[MULHSU_OP_KEY][..]))
+        );
+        // machines without signed multiplication must reject it
+        let unsigned: Vec<Box<dyn DecodableMachineOp>> = vec![Box::new(MulOp::<false>)];
+        assert_eq!(
+            decode_instruction_family(&unsigned, MULHSU_INSTRUCTION),
+            None
+        );
+    }
+}
@@ -139,7 +139,10 @@ impl<
         let src1 = inputs.get_rs1_or_equivalent();
         let src2 = inputs.get_rs2_or_equivalent();
 
-        // truncate shift amount to 5 bits
+        // Shift amount is taken modulo 32 as required by RV32I, so amounts >= 32 only use their low 5 bits.
+        // Masking is done by the AND table lookup below, that also constrains the low byte of rs2
+        // (or immediate) to be 8 bits, so truncated amount is constrained and not just assumed to be in range.
+        // Higher bytes of the shift amount do not affect the result and are ignored
 
         let input = src1.get_register();
         let input_sign = src1
@@ -164,7 +167,8 @@ impl<
             exec_flag,
         );
 
-        // we will do a little of brute force and ask a table for contributions
+        // we will do a little of brute force and ask a table for contributions. Shift by 0 needs no special handling
+        // here: shift table returns the input in place with zero overflow, and SRA filler is zero for it
 
         if exec_flag.get_value(cs).unwrap_or(false) {
             println!("SHIFT OPCODE");
@@ -155,9 +155,7 @@ impl<const SUPPORT_LESS_THAN_WORD: bool> StoreOp<SUPPORT_LESS_THAN_WORD> {
             // check alignment in case of subword accesses
             if ASSUME_TRUSTED_CODE {
                 // unprovable if we do not have proper alignment
-                cs.add_constraint((Term::from(bit_0) + Term::from(bit_1)) * exec_word.get_terms());
-
-                cs.add_constraint(Term::from(bit_0) * exec_half_word.get_terms());
+                constrain_access_alignment(cs, [bit_0, bit_1], exec_word, exec_half_word);
             } else {
                 todo!();
             }
@@ -16,11 +16,13 @@ use std::collections::BTreeSet;
 
 pub mod delegation;
 pub mod layout_utils;
+pub mod public_inputs_diff;
 pub mod stage_2_layout;
 
 mod compile_layout;
 
 pub use self::layout_utils::*;
+pub use self::public_inputs_diff::*;
 pub use self::stage_2_layout::*;
 
 pub fn array_to_tokens<T: quote::ToTokens, const N: usize>(
@@ -0,0 +1,120 @@
+use super::*;
+
+/// Difference between public inputs of two compiled circuits. Public inputs are positional,
+/// as prover and verifier read them in the order of `CompiledCircuitArtifact::public_inputs`,
+/// so entries are compared index by index
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PublicInputDiff {
+    /// Public input is only present in the second artifact
+    Added {
+        index: usize,
+        input: (BoundaryConstraintLocation, ColumnAddress),
+    },
+    /// Public input is only present in the first artifact
+    Removed {
+        index: usize,
+        input: (BoundaryConstraintLocation, ColumnAddress),
+    },
+    /// Public input at the same index is read from a different row or column
+    Moved {
+        index: usize,
+        from: (BoundaryConstraintLocation, ColumnAddress),
+        to: (BoundaryConstraintLocation, ColumnAddress),
+    },
+}
+
+/// Compares public input layouts of two compiled circuits, empty result means that public inputs
+/// are read from exactly the same places
+pub fn diff_public_inputs<F: PrimeField>(
+    a: &CompiledCircuitArtifact<F>,
+    b: &CompiledCircuitArtifact<F>,
+) -> Vec<PublicInputDiff> {
+    let mut result = vec![];
+    let len = core::cmp::max(a.public_inputs.len(), b.public_inputs.len());
+    for index in 0..len {
+        match (a.public_inputs.get(index), b.public_inputs.get(index)) {
+            (Some(from), Some(to)) => {
+                if from != to {
+                    result.push(PublicInputDiff::Moved {
+                        index,
+                        from: *from,
+                        to: *to,
+                    });
+                }
+            }
+            (Some(input), None) => result.push(PublicInputDiff::Removed {
+                index,
+                input: *input,
+            }),
+            (None, Some(input)) => result.push(PublicInputDiff::Added {
+                index,
+                input: *input,
+            }),
+            (None, None) => unreachable!(),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::default_compile_machine;
+    use crate::machine::machine_configurations::create_csr_table_for_delegation;
+    use crate::machine::machine_configurations::create_table_for_rom_image;
+    use crate::machine::machine_configurations::minimal_no_exceptions_with_delegation::MinimalMachineNoExceptionHandlingWithDelegation;
+    use crate::tables::TableType;
+
+    const SECOND_WORD_BITS: usize = 4;
+
+    #[test]
+    fn test_diff_public_inputs() {
+        let rom_table = create_table_for_rom_image::<_, SECOND_WORD_BITS>(
+            &[],
+            TableType::RomRead.to_table_id(),
+        );
+        let csr_table = create_csr_table_for_delegation(
+            true,
+            &[1991],
+            TableType::SpecialCSRProperties.to_table_id(),
+        );
+        let a = default_compile_machine::<_, SECOND_WORD_BITS>(
+            MinimalMachineNoExceptionHandlingWithDelegation,
+            rom_table,
+            Some(csr_table),
+            20,
+        );
+        assert!(diff_public_inputs(&a, &a).is_empty());
+
+        let mut b = a.clone();
+        let (location, column) = a.public_inputs[0];
+        assert_eq!(location, BoundaryConstraintLocation::FirstRow);
+        b.public_inputs[0] = (BoundaryConstraintLocation::OneBeforeLastRow, column);
+        assert_eq!(
+            diff_public_inputs(&a, &b),
+            vec![PublicInputDiff::Moved {
+                index: 0,
+                from: (BoundaryConstraintLocation::FirstRow, column),
+                to: (BoundaryConstraintLocation::OneBeforeLastRow, column),
+            }]
+        );
+
+        let removed = b.public_inputs.pop().unwrap();
+        let index = b.public_inputs.len();
+        assert_eq!(
+            diff_public_inputs(&b, &a),
+            vec![
+                PublicInputDiff::Moved {
+                    index: 0,
+                    from: (BoundaryConstraintLocation::OneBeforeLastRow, column),
+                    to: (BoundaryConstraintLocation::FirstRow, column),
+                },
+                PublicInputDiff::Added {
+                    index,
+                    input: removed,
+                },
+            ]
+        );
+    }
+}
@@ -2195,4 +2195,24 @@ mod test {
         }
         dbg!(vec_time.elapsed());
     }
+
+    #[test]
+    fn test_byte_binop_tables() {
+        // BinaryOp uses funct3 directly as the table id, so every byte pair is a single lookup
+        for (table_type, funct3, op) in [
+            (TableType::Xor, 0b100, (|a, b| a ^ b) as fn(u32, u32) -> u32),
+            (TableType::Or, 0b110, |a, b| a | b),
+            (TableType::And, 0b111, |a, b| a & b),
+        ] {
+            assert_eq!(table_type.to_table_id(), funct3);
+            let table = table_type.generate_table::<Mersenne31Field>();
+            for a in 0..256u32 {
+                for b in 0..256u32 {
+                    let [value] =
+                        table.lookup_value::<1>(&[Mersenne31Field(a), Mersenne31Field(b)]);
+                    assert_eq!(value, Mersenne31Field(op(a, b)));
+                }
+            }
+        }
+    }
 }
@@ -39,7 +39,7 @@ pub fn u64_from_lsb_bits(bits: &[bool]) -> u64 {
     result
 }
 
-pub use crate::definitions::split_timestamp;
+pub use crate::definitions::{join_timestamp, split_timestamp};
 
 pub fn split_u32_into_pair_u16(num: u32) -> (u16, u16) {
     let high_word = (num >> 16) as u16;
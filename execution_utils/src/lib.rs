@@ -14,18 +14,27 @@ use verifier_common::transcript::Blake2sBufferingTranscript;
 mod constants;
 mod proofs;
 mod recursion;
+mod recursion_plan;
 mod verifiers;
 
 use self::constants::*;
 pub use self::proofs::{ProgramProof, ProofList, ProofMetadata};
 
 pub use self::verifiers::{
-    generate_oracle_data_for_universal_verifier, generate_oracle_data_from_metadata_and_proof_list,
-    VerifierCircuitsIdentifiers,
+    allowed_delegation_types, generate_oracle_data_for_combined_pair,
+    generate_oracle_data_for_combined_recursion, generate_oracle_data_for_universal_verifier,
+    generate_oracle_data_from_metadata_and_proof_list, generate_oracle_data_genesis,
+    oracle_identifier_for, oracle_sections, validate_metadata_and_proof_list, write_oracle_data,
+    OracleError, OracleSection, OracleSectionKind, VerifierCircuitsIdentifiers,
 };
 
 pub use self::recursion::{
-    generate_constants_for_binary, generate_params_for_binary, RecursionStrategy,
+    chain_public_inputs, generate_constants_for_binary, generate_params_for_binary, RecursionError,
+    RecursionStrategy, RecursionStrategyParser,
+};
+pub use self::recursion_plan::{
+    RecursionLayer, RecursionPlan, RecursionPlanError, RecursionPlanStep, VerifierCostModel,
+    MAX_SIMULATED_RECURSION_LEVELS,
 };
 
 // pub const RUN_VERIFIERS_WITH_OUTPUT: bool = false;
@@ -194,7 +203,7 @@ pub struct VerificationKey {
 pub fn run_verifier_binary(binary: &[u8], reads: Vec<u32>) -> Option<[u32; 16]> {
     use risc_v_simulator::cycle::IMIsaConfigWithAllDelegations;
 
-    let final_pc = find_binary_exit_point(binary);
+    let final_pc = find_binary_exit_point(binary).expect("binary must have a single exit point");
     println!("Expected final PC = 0x{:08x}", final_pc);
 
     let source = QuasiUARTSource::new_with_reads(reads);
@@ -219,8 +228,14 @@ pub fn run_verifier_binary(binary: &[u8], reads: Vec<u32>) -> Option<[u32; 16]>
     Some(regs)
 }
 
-pub fn find_binary_exit_point(binary: &[u8]) -> u32 {
-    assert!(binary.len() % 4 == 0);
+/// Finds the PC at which the program is expected to finish. By convention programs end with
+/// `EXIT_SEQUENCE`, that loads the output into registers x10-x25 and then loops forever on the
+/// last instruction, so the final PC is the address of the last word of the sequence.
+/// Returns `None` if binary is not word aligned, or doesn't contain exactly one exit sequence.
+pub fn find_binary_exit_point(binary: &[u8]) -> Option<u32> {
+    if binary.len() % 4 != 0 {
+        return None;
+    }
 
     let binary: Vec<u32> = binary
         .as_chunks::<4>()
@@ -237,11 +252,13 @@ pub fn find_binary_exit_point(binary: &[u8]) -> u32 {
         }
     }
 
-    assert_eq!(candidates.len(), 1, "too many candidates for exit sequence");
+    if candidates.len() != 1 {
+        return None;
+    }
     let start = candidates[0];
     let final_pc = (start + EXIT_SEQUENCE.len() - 1) * core::mem::size_of::<u32>();
 
-    final_pc as u32
+    Some(final_pc as u32)
 }
 
 pub fn verify_base_layer(full_proof: &ProgramProof) -> bool {
@@ -373,6 +390,45 @@ pub fn compute_chain_encoding(data: Vec<[u32; 8]>) -> [u32; 8] {
     previous
 }
 
+/// Incremental version of `compute_chain_encoding`, absorbs params one by one without
+/// keeping the whole sequence. Starts from the leading `[0u32; 8]`, so absorbing `a, b, c`
+/// gives the same result as `compute_chain_encoding(vec![[0u32; 8], a, b, c])`
+#[derive(Clone, Copy, Debug)]
+pub struct ChainEncoder {
+    chain: [u32; 8],
+    last: [u32; 8],
+}
+
+impl ChainEncoder {
+    pub fn new() -> Self {
+        Self {
+            chain: [0u32; 8],
+            last: [0u32; 8],
+        }
+    }
+
+    pub fn absorb(&mut self, params: [u32; 8]) {
+        // continue the chain, only if the data is different
+        if params != self.last {
+            let mut hasher = Blake2sBufferingTranscript::new();
+            hasher.absorb(&self.chain);
+            hasher.absorb(&params);
+            self.chain = hasher.finalize_reset().0;
+            self.last = params;
+        }
+    }
+
+    pub fn finalize(self) -> [u32; 8] {
+        self.chain
+    }
+}
+
+impl Default for ChainEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::alloc::Global;
@@ -389,7 +445,40 @@ mod test {
         let mut file = std::fs::File::open(path).unwrap();
         file.read_to_end(&mut data).unwrap();
 
-        find_binary_exit_point(&data)
+        find_binary_exit_point(&data).unwrap()
+    }
+
+    #[test]
+    fn test_find_binary_exit_point() {
+        let mut binary: Vec<u8> = vec![0x13, 0x00, 0x00, 0x00]; // nop
+        assert_eq!(find_binary_exit_point(&binary), None);
+
+        for word in EXIT_SEQUENCE.iter() {
+            binary.extend_from_slice(&word.to_le_bytes());
+        }
+        let expected = (EXIT_SEQUENCE.len() * core::mem::size_of::<u32>()) as u32;
+        assert_eq!(find_binary_exit_point(&binary), Some(expected));
+
+        // exit sequence is ambiguous
+        binary.extend_from_slice(&binary.clone());
+        assert_eq!(find_binary_exit_point(&binary), None);
+    }
+
+    #[test]
+    fn test_chain_encoder() {
+        let params: Vec<[u32; 8]> = [1, 1, 2, 3, 3, 3, 1].into_iter().map(|x| [x; 8]).collect();
+        let mut encoder = ChainEncoder::new();
+        let mut sequence = vec![[0u32; 8]];
+        assert_eq!(encoder.finalize(), compute_chain_encoding(sequence.clone()));
+        for el in params {
+            encoder.absorb(el);
+            sequence.push(el);
+            assert_eq!(encoder.finalize(), compute_chain_encoding(sequence.clone()));
+        }
+        // zero params right after the start do not continue the chain
+        let mut encoder = ChainEncoder::new();
+        encoder.absorb([0u32; 8]);
+        assert_eq!(encoder.finalize(), [0u32; 8]);
     }
 
     #[test]
@@ -414,13 +503,10 @@ mod test {
             .collect();
 
         // let unsupported_opcodes = <prover::cs::machine::machine_configurations::full_isa_with_delegation_no_exceptions::FullIsaMachineWithDelegationNoExceptionHandling as Machine<Mersenne31Field>>::verify_bytecode_base(&text_section);
-        let unsupported_opcodes = <prover::cs::machine::machine_configurations::full_isa_with_delegation_no_exceptions_no_signed_mul_div::FullIsaMachineWithDelegationNoExceptionHandlingNoSignedMulDiv as Machine<Mersenne31Field>>::verify_bytecode_base(&text_section);
-        // let unsupported_opcodes = <prover::cs::machine::machine_configurations::minimal_no_exceptions_with_delegation::MinimalMachineNoExceptionHandlingWithDelegation as Machine<Mersenne31Field>>::verify_bytecode_base(&text_section);
-        for (pc, opcode) in unsupported_opcodes {
-            println!(
-                "Potentially unsupported opcode 0x{:08x} at PC = 0x{:08x}",
-                opcode, pc
-            );
+        let unsupported_opcodes = <prover::cs::machine::machine_configurations::full_isa_with_delegation_no_exceptions_no_signed_mul_div::FullIsaMachineWithDelegationNoExceptionHandlingNoSignedMulDiv as Machine<Mersenne31Field>>::find_unsupported_instructions(&text_section, 0);
+        // let unsupported_opcodes = <prover::cs::machine::machine_configurations::minimal_no_exceptions_with_delegation::MinimalMachineNoExceptionHandlingWithDelegation as Machine<Mersenne31Field>>::find_unsupported_instructions(&text_section, 0);
+        for unsupported in unsupported_opcodes {
+            println!("Potentially unsupported {}", unsupported);
         }
 
         let worker = prover::worker::Worker::new();
@@ -430,7 +516,7 @@ mod test {
                 &worker,
             );
 
-        let expected_final_pc = find_binary_exit_point(&binary);
+        let expected_final_pc = find_binary_exit_point(&binary).unwrap();
         println!(
             "Expected final PC for base program is 0x{:08x}",
             expected_final_pc
@@ -479,6 +565,7 @@ mod test {
             end_params,
             recursion_chain_preimage: None,
             recursion_chain_hash: None,
+            recursion_depth: 0,
         };
 
         let is_valid = verify_base_layer(&program_proof);
@@ -500,7 +587,7 @@ mod test {
 
         let binary = BASE_LAYER_VERIFIER;
 
-        let expected_final_pc = find_binary_exit_point(&binary);
+        let expected_final_pc = find_binary_exit_point(&binary).unwrap();
         println!(
             "Expected final PC for recursion program is 0x{:08x}",
             expected_final_pc
@@ -566,6 +653,7 @@ mod test {
             end_params: base_layer_verifier_end_params,
             recursion_chain_preimage: Some(preimage),
             recursion_chain_hash: Some(chain_hash),
+            recursion_depth: proofs.recursion_depth + 1,
         };
 
         let mut dst = std::fs::File::create("recursion_layer.json").unwrap();
@@ -586,7 +674,7 @@ mod test {
 
         let binary = RECURSION_LAYER_VERIFIER;
 
-        let expected_final_pc = find_binary_exit_point(&binary);
+        let expected_final_pc = find_binary_exit_point(&binary).unwrap();
         println!(
             "Expected final PC for recursion program is 0x{:08x}",
             expected_final_pc
@@ -656,6 +744,7 @@ mod test {
             end_params: new_end_params,
             recursion_chain_preimage: Some(preimage),
             recursion_chain_hash: Some(chain_hash),
+            recursion_depth: proofs.recursion_depth + 1,
         };
 
         let is_valid = verify_recursion_layer(&program_proof);
@@ -676,7 +765,7 @@ mod test {
 
         let binary = RECURSION_LAYER_VERIFIER;
 
-        let expected_final_pc = find_binary_exit_point(&binary);
+        let expected_final_pc = find_binary_exit_point(&binary).unwrap();
         println!(
             "Expected final PC for recursion program is 0x{:08x}",
             expected_final_pc
@@ -746,6 +835,7 @@ mod test {
             end_params: new_end_params,
             recursion_chain_preimage: Some(preimage),
             recursion_chain_hash: Some(chain_hash),
+            recursion_depth: proofs.recursion_depth + 1,
         };
 
         let is_valid = verify_recursion_log_23_layer(&program_proof);
@@ -761,7 +851,7 @@ mod test {
 
         let binary = RECURSION_LAYER_NO_DELEGATION_VERIFIER;
 
-        let expected_final_pc = find_binary_exit_point(&binary);
+        let expected_final_pc = find_binary_exit_point(&binary).unwrap();
         println!(
             "Expected final PC for recursion program is 0x{:08x}",
             expected_final_pc
@@ -831,6 +921,7 @@ mod test {
             end_params: new_end_params,
             recursion_chain_preimage: Some(preimage),
             recursion_chain_hash: Some(chain_hash),
+            recursion_depth: proofs.recursion_depth + 1,
         };
 
         let is_valid = verify_final_recursion_layer(&program_proof);
@@ -846,7 +937,7 @@ mod test {
 
         let binary = FINAL_RECURSION_LAYER_VERIFIER;
 
-        let expected_final_pc = find_binary_exit_point(&binary);
+        let expected_final_pc = find_binary_exit_point(&binary).unwrap();
         println!(
             "Expected final PC for recursion program is 0x{:08x}",
             expected_final_pc
@@ -918,6 +1009,7 @@ mod test {
             end_params: new_end_params,
             recursion_chain_preimage: Some(preimage),
             recursion_chain_hash: Some(chain_hash),
+            recursion_depth: proofs.recursion_depth + 1,
         };
 
         let is_valid = verify_final_recursion_layer(&program_proof);
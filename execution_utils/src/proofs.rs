@@ -22,6 +22,9 @@ pub struct ProgramProof {
     pub end_params: [u32; 8],
     pub recursion_chain_preimage: Option<[u32; 16]>,
     pub recursion_chain_hash: Option<[u32; 8]>,
+    // number of recursion levels applied on top of the base layer proofs.
+    #[serde(default)]
+    pub recursion_depth: usize,
 }
 
 /// This structs covers only the metadata of given set of proofs.
@@ -43,6 +46,9 @@ pub struct ProofMetadata {
     pub prev_end_params_output_hash: Option<[u32; BLAKE2S_DIGEST_SIZE_U32_WORDS]>,
     // parameters from the previous recursion level.
     pub prev_end_params_output: Option<[u32; 16]>,
+    // number of recursion levels applied on top of the proofs the recursion started from.
+    #[serde(default)]
+    pub recursion_depth: usize,
 }
 
 /// This struct contains just the proofs.
@@ -130,6 +136,7 @@ impl ProgramProof {
             end_params: proof_metadata.end_params,
             recursion_chain_preimage: proof_metadata.prev_end_params_output,
             recursion_chain_hash: proof_metadata.prev_end_params_output_hash,
+            recursion_depth: proof_metadata.recursion_depth,
         }
     }
     pub fn to_metadata_and_proof_list(self) -> (ProofMetadata, ProofList) {
@@ -152,6 +159,7 @@ impl ProgramProof {
             end_params: self.end_params,
             prev_end_params_output_hash: self.recursion_chain_hash,
             prev_end_params_output: self.recursion_chain_preimage,
+            recursion_depth: self.recursion_depth,
         };
         (proof_metadata, proof_list)
     }
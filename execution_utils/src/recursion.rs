@@ -1,12 +1,19 @@
+use crate::recursion_plan::{is_collapsed, RecursionPlanError};
 use crate::{get_padded_binary, Machine, ProofMetadata, UNIVERSAL_CIRCUIT_VERIFIER};
+use clap::builder::{PossibleValue, TypedValueParser, ValueParserFactory};
 use clap::ValueEnum;
+use rayon::prelude::*;
 use std::alloc::Global;
+use std::str::FromStr;
 
 use crate::{
     compute_chain_encoding, recursion_layer_verifier_vk, recursion_log_23_layer_verifier_vk,
     universal_circuit_log_23_verifier_vk, universal_circuit_verifier_vk,
 };
 use verifier_common::blake2s_u32::BLAKE2S_DIGEST_SIZE_U32_WORDS;
+use verifier_common::field::Mersenne31Field;
+
+type BF = Mersenne31Field;
 
 /// We have two layers of recursion:
 /// 1. Reduced machine (2^22 cycles) + blake delegation
@@ -16,30 +23,199 @@ use verifier_common::blake2s_u32::BLAKE2S_DIGEST_SIZE_U32_WORDS;
 /// Note: end_params constant differs if we do 1 or multiple repetitions of the 2nd layer.
 /// So we need to run the 2nd layer exactly one time or at least twice.
 /// Then we can define four recursion strategies:
-#[derive(Clone, Copy, Debug, ValueEnum, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum RecursionStrategy {
     /// UseFinalMachine is no longer supported.
     // UseFinalMachine,
     /// Does 1st layer until 2 reduced + 1 delegation then 1 reduced 2^23 + 1 delegation (one repetition)
     UseReducedLog23Machine,
-    /// Does 1st layer until N reduced + M delegation then reduced 2^23 + delegation (at least two repetitions)
-    UseReducedLog23MachineMultiple,
+    /// Does 1st layer until N reduced + M delegation then reduced 2^23 + delegation (at least two repetitions).
+    /// N and M are `reduced_threshold` and `delegation_threshold`, sane defaults are 5 and 2.
+    UseReducedLog23MachineMultiple {
+        reduced_threshold: usize,
+        delegation_threshold: usize,
+    },
     /// Skips 1st layer and does reduced 2^23 + delegation (at least two repetitions)
     UseReducedLog23MachineOnly,
+    /// Skips 1st layer and does reduced 2^23 + delegation exactly D times, regardless of proof counts.
+    /// Levels that have nothing left to reduce just re-prove a single proof.
+    FixedDepth(usize),
+}
+
+impl FromStr for RecursionStrategy {
+    type Err = String;
+
+    /// Accepts kebab-case variant names, fixed depth is given as `fixed-depth-<D>` and custom
+    /// thresholds for multiple repetitions as `use-reduced-log23-machine-multiple-<N>-<M>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "use-reduced-log23-machine" => Ok(RecursionStrategy::UseReducedLog23Machine),
+            "use-reduced-log23-machine-multiple" => Ok(Self::use_reduced_log23_machine_multiple()),
+            "use-reduced-log23-machine-only" => Ok(RecursionStrategy::UseReducedLog23MachineOnly),
+            _ if s.starts_with("use-reduced-log23-machine-multiple-") => {
+                let thresholds = s
+                    .strip_prefix("use-reduced-log23-machine-multiple-")
+                    .unwrap()
+                    .split('-')
+                    .map(|x| x.parse::<usize>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| format!("invalid threshold in recursion strategy {s}: {e}"))?;
+                let [reduced_threshold, delegation_threshold] = thresholds[..] else {
+                    return Err(format!("expected two thresholds in recursion strategy {s}"));
+                };
+                // with 0 threshold the first layer never switches to the second one
+                if reduced_threshold == 0 || delegation_threshold == 0 {
+                    return Err("recursion thresholds must be at least 1".to_string());
+                }
+                Ok(RecursionStrategy::UseReducedLog23MachineMultiple {
+                    reduced_threshold,
+                    delegation_threshold,
+                })
+            }
+            _ => {
+                let depth = s
+                    .strip_prefix("fixed-depth-")
+                    .ok_or_else(|| format!("unknown recursion strategy: {s}"))?
+                    .parse::<usize>()
+                    .map_err(|e| format!("invalid depth in recursion strategy {s}: {e}"))?;
+                if depth == 0 {
+                    return Err("fixed recursion depth must be at least 1".to_string());
+                }
+                Ok(RecursionStrategy::FixedDepth(depth))
+            }
+        }
+    }
+}
+
+/// Only the strategies without parameters are listed as variants, parametrized ones are accepted
+/// by `from_str` in the same form as for [FromStr].
+impl ValueEnum for RecursionStrategy {
+    fn value_variants<'a>() -> &'a [Self] {
+        const VARIANTS: &[RecursionStrategy] = &[
+            RecursionStrategy::UseReducedLog23Machine,
+            RecursionStrategy::use_reduced_log23_machine_multiple(),
+            RecursionStrategy::UseReducedLog23MachineOnly,
+        ];
+
+        VARIANTS
+    }
+
+    fn from_str(input: &str, _ignore_case: bool) -> Result<Self, String> {
+        input.parse()
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        match self {
+            RecursionStrategy::UseReducedLog23Machine => {
+                Some(PossibleValue::new("use-reduced-log23-machine"))
+            }
+            _ if *self == Self::use_reduced_log23_machine_multiple() => {
+                Some(PossibleValue::new("use-reduced-log23-machine-multiple"))
+            }
+            RecursionStrategy::UseReducedLog23MachineOnly => {
+                Some(PossibleValue::new("use-reduced-log23-machine-only"))
+            }
+            RecursionStrategy::UseReducedLog23MachineMultiple { .. }
+            | RecursionStrategy::FixedDepth(_) => None,
+        }
+    }
 }
 
+/// CLI parser for [RecursionStrategy]: lists [ValueEnum] variants and parametrized forms in `--help`,
+/// and accepts everything that [FromStr] does.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RecursionStrategyParser;
+
+impl TypedValueParser for RecursionStrategyParser {
+    type Value = RecursionStrategy;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        _arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let value = value
+            .to_str()
+            .ok_or_else(|| clap::Error::new(clap::error::ErrorKind::InvalidUtf8).with_cmd(cmd))?;
+
+        <RecursionStrategy as ValueEnum>::from_str(value, false)
+            .map_err(|e| clap::Error::raw(clap::error::ErrorKind::InvalidValue, e).with_cmd(cmd))
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        let parametrized = [
+            PossibleValue::new("use-reduced-log23-machine-multiple-<N>-<M>"),
+            PossibleValue::new("fixed-depth-<D>"),
+        ];
+
+        Some(Box::new(
+            RecursionStrategy::value_variants()
+                .iter()
+                .filter_map(ValueEnum::to_possible_value)
+                .chain(parametrized),
+        ))
+    }
+}
+
+impl ValueParserFactory for RecursionStrategy {
+    type Parser = RecursionStrategyParser;
+
+    fn value_parser() -> Self::Parser {
+        RecursionStrategyParser
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecursionError {
+    /// Strategy relies on the universal verifier to verify the 2nd layer by itself.
+    RequiresUniversalVerifier(RecursionStrategy),
+}
+
+impl std::fmt::Display for RecursionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecursionError::RequiresUniversalVerifier(strategy) => write!(
+                f,
+                "Recursion strategy {:?} is not supported for non-universal verifier. Use the universal verifier or UseReducedLog23Machine strategy instead.",
+                strategy
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RecursionError {}
+
 impl RecursionStrategy {
+    pub const DEFAULT_REDUCED_THRESHOLD: usize = 5;
+    pub const DEFAULT_DELEGATION_THRESHOLD: usize = 2;
+
+    /// Multiple repetitions strategy with default thresholds
+    pub const fn use_reduced_log23_machine_multiple() -> Self {
+        RecursionStrategy::UseReducedLog23MachineMultiple {
+            reduced_threshold: Self::DEFAULT_REDUCED_THRESHOLD,
+            delegation_threshold: Self::DEFAULT_DELEGATION_THRESHOLD,
+        }
+    }
+
+    /// Checks that the strategy can be used with the given kind of verifiers.
+    pub fn validate_for(&self, universal_verifier: bool) -> Result<(), RecursionError> {
+        match self {
+            RecursionStrategy::UseReducedLog23Machine => Ok(()),
+            _ if universal_verifier => Ok(()),
+            _ => Err(RecursionError::RequiresUniversalVerifier(*self)),
+        }
+    }
+
     pub fn skip_first_layer(&self) -> bool {
         match self {
             RecursionStrategy::UseReducedLog23MachineOnly => true,
+            RecursionStrategy::FixedDepth(_) => true,
             _ => false,
         }
     }
 
     pub fn switch_to_second_recursion_layer(&self, proof_metadata: &ProofMetadata) -> bool {
-        const N: usize = 5;
-        const M: usize = 2;
-
         let continue_first_layer = match self {
             RecursionStrategy::UseReducedLog23Machine => {
                 proof_metadata.reduced_proof_count > 2
@@ -48,33 +224,41 @@ impl RecursionStrategy {
                         .iter()
                         .any(|(_, x)| *x > 1)
             }
-            RecursionStrategy::UseReducedLog23MachineMultiple => {
-                proof_metadata.reduced_proof_count > N
+            RecursionStrategy::UseReducedLog23MachineMultiple {
+                reduced_threshold,
+                delegation_threshold,
+            } => {
+                proof_metadata.reduced_proof_count > *reduced_threshold
                     || proof_metadata
                         .delegation_proof_count
                         .iter()
-                        .any(|(_, x)| *x > M)
+                        .any(|(_, x)| *x > *delegation_threshold)
+            }
+            RecursionStrategy::UseReducedLog23MachineOnly | RecursionStrategy::FixedDepth(_) => {
+                false
             }
-            RecursionStrategy::UseReducedLog23MachineOnly => false,
         };
 
         !continue_first_layer
     }
 
+    /// Returns an error if the strategy expects a single proof at this level, but there are more.
     pub fn finish_second_recursion_layer(
         &self,
         proof_metadata: &ProofMetadata,
         proof_level: usize,
-    ) -> bool {
+    ) -> Result<bool, RecursionPlanError> {
         let continue_second_layer = match self {
             RecursionStrategy::UseReducedLog23Machine => {
                 // In this strategy we should run only one repetition of 2nd layer
                 assert!(proof_level == 0);
-                assert!(proof_metadata.reduced_log_23_proof_count == 1);
+                if proof_metadata.reduced_log_23_proof_count != 1 {
+                    return Err(RecursionPlanError::NotCollapsed { proof_level });
+                }
 
                 false
             }
-            RecursionStrategy::UseReducedLog23MachineMultiple
+            RecursionStrategy::UseReducedLog23MachineMultiple { .. }
             | RecursionStrategy::UseReducedLog23MachineOnly => {
                 proof_metadata.reduced_log_23_proof_count > 1
                     || proof_metadata
@@ -83,26 +267,36 @@ impl RecursionStrategy {
                         .any(|(_, x)| *x > 1)
                     || proof_level == 0
             }
+            RecursionStrategy::FixedDepth(depth) => {
+                // First layer is skipped, so every recursion level so far belongs to the 2nd layer
+                let current_depth = proof_metadata.recursion_depth;
+                assert!(current_depth > 0 && current_depth <= depth);
+                if current_depth == depth && !is_collapsed(proof_metadata) {
+                    return Err(RecursionPlanError::NotCollapsed { proof_level });
+                }
+
+                current_depth < depth
+            }
         };
 
-        !continue_second_layer
+        Ok(!continue_second_layer)
     }
 
     pub fn get_second_layer_machine(&self) -> Machine {
         match self {
             RecursionStrategy::UseReducedLog23Machine
-            | RecursionStrategy::UseReducedLog23MachineMultiple
-            | RecursionStrategy::UseReducedLog23MachineOnly => Machine::ReducedLog23,
+            | RecursionStrategy::UseReducedLog23MachineMultiple { .. }
+            | RecursionStrategy::UseReducedLog23MachineOnly
+            | RecursionStrategy::FixedDepth(_) => Machine::ReducedLog23,
         }
     }
 
     pub fn get_second_layer_binary(&self) -> Vec<u32> {
         match self {
             RecursionStrategy::UseReducedLog23Machine
-            | RecursionStrategy::UseReducedLog23MachineMultiple
-            | RecursionStrategy::UseReducedLog23MachineOnly => {
-                get_padded_binary(UNIVERSAL_CIRCUIT_VERIFIER)
-            }
+            | RecursionStrategy::UseReducedLog23MachineMultiple { .. }
+            | RecursionStrategy::UseReducedLog23MachineOnly
+            | RecursionStrategy::FixedDepth(_) => get_padded_binary(UNIVERSAL_CIRCUIT_VERIFIER),
         }
     }
 
@@ -120,6 +314,9 @@ pub fn generate_constants_for_binary(
     [u32; BLAKE2S_DIGEST_SIZE_U32_WORDS],
     [u32; BLAKE2S_DIGEST_SIZE_U32_WORDS],
 ) {
+    if let Err(error) = recursion_mode.validate_for(universal_verifier) {
+        panic!("{error}");
+    }
     let (end_params, aux_values) = if universal_verifier {
         if recompute {
             match recursion_mode {
@@ -130,7 +327,7 @@ pub fn generate_constants_for_binary(
                     ],
                     (&crate::UNIVERSAL_CIRCUIT_VERIFIER, Machine::ReducedLog23),
                 ),
-                RecursionStrategy::UseReducedLog23MachineMultiple => {
+                RecursionStrategy::UseReducedLog23MachineMultiple { .. } => {
                     generate_params_and_register_values(
                         &[
                             (&base_layer_bin, Machine::Standard),
@@ -149,6 +346,18 @@ pub fn generate_constants_for_binary(
                         (&crate::UNIVERSAL_CIRCUIT_VERIFIER, Machine::ReducedLog23),
                     )
                 }
+                // with a single repetition the last machine never verifies itself
+                RecursionStrategy::FixedDepth(1) => generate_params_and_register_values(
+                    &[(&base_layer_bin, Machine::Standard)],
+                    (&crate::UNIVERSAL_CIRCUIT_VERIFIER, Machine::ReducedLog23),
+                ),
+                RecursionStrategy::FixedDepth(_) => generate_params_and_register_values(
+                    &[
+                        (&base_layer_bin, Machine::Standard),
+                        (&crate::UNIVERSAL_CIRCUIT_VERIFIER, Machine::ReducedLog23),
+                    ],
+                    (&crate::UNIVERSAL_CIRCUIT_VERIFIER, Machine::ReducedLog23),
+                ),
             }
         } else {
             let base_params = generate_params_for_binary(&base_layer_bin, Machine::Standard);
@@ -163,7 +372,7 @@ pub fn generate_constants_for_binary(
 
                     (universal_circuit_log_23_verifier_vk().params, aux_values)
                 }
-                RecursionStrategy::UseReducedLog23MachineMultiple => {
+                RecursionStrategy::UseReducedLog23MachineMultiple { .. } => {
                     let aux_values = compute_chain_encoding(vec![
                         [0u32; 8],
                         base_params,
@@ -180,6 +389,21 @@ pub fn generate_constants_for_binary(
                         universal_circuit_log_23_verifier_vk().params,
                     ]);
 
+                    (universal_circuit_log_23_verifier_vk().params, aux_values)
+                }
+                // with a single repetition the last machine never verifies itself
+                RecursionStrategy::FixedDepth(1) => {
+                    let aux_values = compute_chain_encoding(vec![[0u32; 8], base_params]);
+
+                    (universal_circuit_log_23_verifier_vk().params, aux_values)
+                }
+                RecursionStrategy::FixedDepth(_) => {
+                    let aux_values = compute_chain_encoding(vec![
+                        [0u32; 8],
+                        base_params,
+                        universal_circuit_log_23_verifier_vk().params,
+                    ]);
+
                     (universal_circuit_log_23_verifier_vk().params, aux_values)
                 }
             }
@@ -195,7 +419,7 @@ pub fn generate_constants_for_binary(
                     ],
                     (&crate::RECURSION_LAYER_VERIFIER, Machine::ReducedLog23),
                 ),
-                _ => panic!("This recursion strategy is not supported for non-universal verifier."),
+                _ => unreachable!(),
             }
         } else {
             let base_params = generate_params_for_binary(&base_layer_bin, Machine::Standard);
@@ -211,7 +435,7 @@ pub fn generate_constants_for_binary(
 
                     (recursion_log_23_layer_verifier_vk().params, aux_values)
                 }
-                _ => panic!("This recursion strategy is not supported for non-universal verifier."),
+                _ => unreachable!(),
             }
         }
     };
@@ -235,8 +459,9 @@ pub fn generate_params_and_register_values(
 fn compute_commitment_for_chain_of_programs(
     binaries_and_machines: &[(&[u8], Machine)],
 ) -> [u32; BLAKE2S_DIGEST_SIZE_U32_WORDS] {
+    // params of every binary are independent, and collecting keeps the chain order
     let mut end_params = binaries_and_machines
-        .iter()
+        .par_iter()
         .map(|(bin, machine)| generate_params_for_binary(bin, machine.clone()))
         .collect::<Vec<_>>();
 
@@ -248,7 +473,8 @@ fn compute_commitment_for_chain_of_programs(
 pub fn generate_params_for_binary(bin: &[u8], machine: Machine) -> [u32; 8] {
     let worker = verifier_common::prover::worker::Worker::new();
 
-    let expected_final_pc = crate::find_binary_exit_point(&bin);
+    let expected_final_pc =
+        crate::find_binary_exit_point(&bin).expect("binary must have a single exit point");
     let binary: Vec<u32> = crate::get_padded_binary(&bin);
     match machine {
         Machine::Standard => crate::compute_end_parameters(
@@ -277,3 +503,274 @@ pub fn generate_params_for_binary(bin: &[u8], machine: Machine) -> [u32; 8] {
         ),
     }
 }
+
+/// Folds public inputs of consecutive proofs in a chain into the public inputs of the chain as a whole.
+/// Public inputs of every proof are input state variables (from the first row) followed by output state
+/// variables (from the one before last row), so output state of every proof must match the input state of
+/// the next one - same as the PC continuity check in the full statement verifier.
+/// `layer_params` are the end params of the program proven at every layer, that are folded with
+/// `compute_chain_encoding` the same way as the aux registers of the recursion verifier, so repeated
+/// layers of the same program are only committed once.
+/// Returns input state of the first proof followed by output state of the last one, and the chain encoding.
+pub fn chain_public_inputs(
+    layer_inputs: &[Vec<BF>],
+    layer_params: &[[u32; 8]],
+) -> (Vec<BF>, [u32; 8]) {
+    assert!(
+        !layer_inputs.is_empty(),
+        "chain must contain at least one proof"
+    );
+    assert_eq!(
+        layer_inputs.len(),
+        layer_params.len(),
+        "every proof in the chain must have its params"
+    );
+    let num_public_inputs = layer_inputs[0].len();
+    assert!(
+        num_public_inputs % 2 == 0,
+        "public inputs must consist of input and output state"
+    );
+    let state_size = num_public_inputs / 2;
+    for (index, pair) in layer_inputs.windows(2).enumerate() {
+        assert_eq!(
+            pair[1].len(),
+            num_public_inputs,
+            "proof {} has unexpected number of public inputs",
+            index + 1
+        );
+        assert_eq!(
+            &pair[0][state_size..],
+            &pair[1][..state_size],
+            "output state of proof {} doesn't match input state of proof {}",
+            index,
+            index + 1
+        );
+    }
+
+    let mut public_inputs = layer_inputs[0][..state_size].to_vec();
+    public_inputs.extend_from_slice(&layer_inputs.last().unwrap()[state_size..]);
+
+    let mut params = Vec::with_capacity(layer_params.len() + 1);
+    params.push([0u32; 8]);
+    params.extend_from_slice(layer_params);
+    let aux_values = compute_chain_encoding(params);
+
+    (public_inputs, aux_values)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{ProgramProof, ProofList};
+
+    fn pc_as_public_inputs(start_pc: u32, end_pc: u32) -> Vec<BF> {
+        [
+            start_pc & 0xffff,
+            start_pc >> 16,
+            end_pc & 0xffff,
+            end_pc >> 16,
+        ]
+        .map(|el| BF::new(el))
+        .to_vec()
+    }
+
+    #[test]
+    fn test_chain_public_inputs() {
+        let layers = vec![
+            pc_as_public_inputs(0, 0x1_0004),
+            pc_as_public_inputs(0x1_0004, 0x2_0008),
+            pc_as_public_inputs(0x2_0008, 0x2_0008),
+        ];
+        let (base, verifier) = ([1u32; 8], [2u32; 8]);
+        let (public_inputs, aux_values) = chain_public_inputs(&layers, &[base, verifier, verifier]);
+        assert_eq!(public_inputs, pc_as_public_inputs(0, 0x2_0008));
+        // repeated verifier layer is committed once
+        assert_eq!(
+            aux_values,
+            compute_chain_encoding(vec![[0u32; 8], base, verifier])
+        );
+    }
+
+    #[test]
+    fn test_parse_recursion_strategy() {
+        assert_eq!(
+            "use-reduced-log23-machine".parse(),
+            Ok(RecursionStrategy::UseReducedLog23Machine)
+        );
+        assert_eq!(
+            "fixed-depth-3".parse(),
+            Ok(RecursionStrategy::FixedDepth(3))
+        );
+        assert!("fixed-depth-0".parse::<RecursionStrategy>().is_err());
+        assert!("fixed-depth".parse::<RecursionStrategy>().is_err());
+        assert!("unknown".parse::<RecursionStrategy>().is_err());
+    }
+
+    #[test]
+    fn test_value_enum_lists_unit_strategies() {
+        let names: Vec<_> = RecursionStrategy::value_variants()
+            .iter()
+            .map(|v| v.to_possible_value().unwrap().get_name().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "use-reduced-log23-machine",
+                "use-reduced-log23-machine-multiple",
+                "use-reduced-log23-machine-only"
+            ]
+        );
+        for name in names {
+            assert_eq!(
+                <RecursionStrategy as ValueEnum>::from_str(&name, false),
+                name.parse()
+            );
+        }
+        assert_eq!(
+            <RecursionStrategy as ValueEnum>::from_str("fixed-depth-2", false),
+            Ok(RecursionStrategy::FixedDepth(2))
+        );
+    }
+
+    #[test]
+    fn test_parse_multiple_thresholds() {
+        assert_eq!(
+            "use-reduced-log23-machine-multiple".parse(),
+            Ok(RecursionStrategy::use_reduced_log23_machine_multiple())
+        );
+        assert_eq!(
+            "use-reduced-log23-machine-multiple-1-3".parse(),
+            Ok(RecursionStrategy::UseReducedLog23MachineMultiple {
+                reduced_threshold: 1,
+                delegation_threshold: 3,
+            })
+        );
+        assert!("use-reduced-log23-machine-multiple-1"
+            .parse::<RecursionStrategy>()
+            .is_err());
+        for s in [
+            "use-reduced-log23-machine-multiple-0-0",
+            "use-reduced-log23-machine-multiple-0-2",
+            "use-reduced-log23-machine-multiple-5-0",
+        ] {
+            assert!(s.parse::<RecursionStrategy>().is_err());
+        }
+    }
+
+    #[test]
+    fn test_validate_for() {
+        let strategy = RecursionStrategy::use_reduced_log23_machine_multiple();
+        assert_eq!(strategy.validate_for(true), Ok(()));
+        assert_eq!(
+            strategy.validate_for(false),
+            Err(RecursionError::RequiresUniversalVerifier(strategy))
+        );
+        assert_eq!(
+            RecursionStrategy::UseReducedLog23Machine.validate_for(false),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_custom_thresholds() {
+        let default = RecursionStrategy::use_reduced_log23_machine_multiple();
+        let custom = RecursionStrategy::UseReducedLog23MachineMultiple {
+            reduced_threshold: 1,
+            delegation_threshold: 1,
+        };
+        let metadata = ProofMetadata {
+            reduced_proof_count: 3,
+            delegation_proof_count: vec![(1991, 2)],
+            ..Default::default()
+        };
+        assert!(default.switch_to_second_recursion_layer(&metadata));
+        assert!(!custom.switch_to_second_recursion_layer(&metadata));
+        let metadata = ProofMetadata {
+            reduced_proof_count: 1,
+            delegation_proof_count: vec![(1991, 1)],
+            ..Default::default()
+        };
+        assert!(custom.switch_to_second_recursion_layer(&metadata));
+    }
+
+    #[test]
+    fn test_fixed_depth_finishes_at_depth() {
+        let strategy = RecursionStrategy::FixedDepth(3);
+        assert!(strategy.skip_first_layer());
+        let mut metadata = ProofMetadata {
+            reduced_log_23_proof_count: 1,
+            delegation_proof_count: vec![(1991, 1)],
+            ..Default::default()
+        };
+        for proof_level in 0..3 {
+            metadata.recursion_depth = proof_level + 1;
+            assert_eq!(
+                strategy.finish_second_recursion_layer(&metadata, proof_level),
+                Ok(proof_level == 2)
+            );
+        }
+    }
+
+    #[test]
+    fn test_fixed_depth_survives_program_proof_round_trip() {
+        let metadata = ProofMetadata {
+            reduced_log_23_proof_count: 1,
+            recursion_depth: 2,
+            ..Default::default()
+        };
+        let proof_list = ProofList {
+            basic_proofs: vec![],
+            reduced_proofs: vec![],
+            reduced_log_23_proofs: vec![],
+            delegation_proofs: vec![],
+        };
+        let (metadata, _) = ProgramProof::from_proof_list_and_metadata(&proof_list, &metadata)
+            .to_metadata_and_proof_list();
+        assert_eq!(metadata.recursion_depth, 2);
+        assert_eq!(
+            RecursionStrategy::FixedDepth(3).finish_second_recursion_layer(&metadata, 0),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn test_fixed_depth_not_collapsed() {
+        let metadata = ProofMetadata {
+            reduced_log_23_proof_count: 2,
+            recursion_depth: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            RecursionStrategy::FixedDepth(1).finish_second_recursion_layer(&metadata, 0),
+            Err(RecursionPlanError::NotCollapsed { proof_level: 0 })
+        );
+    }
+
+    #[test]
+    fn test_parallel_chain_commitment() {
+        let chain: &[(&[u8], Machine)] = &[
+            (crate::BASE_PROGRAM, Machine::Standard),
+            (UNIVERSAL_CIRCUIT_VERIFIER, Machine::Reduced),
+            (UNIVERSAL_CIRCUIT_VERIFIER, Machine::ReducedLog23),
+        ];
+        let mut sequential = chain
+            .iter()
+            .map(|(bin, machine)| generate_params_for_binary(bin, machine.clone()))
+            .collect::<Vec<_>>();
+        sequential.insert(0, [0u32; BLAKE2S_DIGEST_SIZE_U32_WORDS]);
+        assert_eq!(
+            compute_commitment_for_chain_of_programs(chain),
+            compute_chain_encoding(sequential)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_chain_public_inputs_broken_chain() {
+        let layers = vec![
+            pc_as_public_inputs(0, 0x1_0004),
+            pc_as_public_inputs(0x1_0008, 0x2_0008),
+        ];
+        chain_public_inputs(&layers, &[[0u32; 8]; 2]);
+    }
+}
@@ -0,0 +1,257 @@
+use crate::{Machine, ProofMetadata, RecursionStrategy};
+use risc_v_simulator::delegations::blake2_round_function_with_compression_mode::BLAKE2_ROUND_FUNCTION_WITH_EXTENDED_CONTROL_ACCESS_ID;
+use trace_and_split::setups::{
+    blake2_with_compression, reduced_risc_v_log_23_machine, reduced_risc_v_machine,
+};
+
+/// Simulation gives up after this many recursion levels in a single layer.
+pub const MAX_SIMULATED_RECURSION_LEVELS: usize = 64;
+
+/// Rough cost of verifying a single proof in the universal verifier.
+/// It is only used for dry runs, actual proving doesn't depend on it.
+///
+/// The defaults are pessimistic round numbers rather than measured values: a reduced machine
+/// chunk (2^22 cycles) fits about two verifications and a Blake2s delegation circuit (2^20 calls)
+/// about four, so the plan rather overestimates the number of levels than underestimates it.
+/// Use counts from running the universal verifier in the simulator when precise numbers matter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VerifierCostModel {
+    /// RISC-V cycles spent per verified proof.
+    pub cycles_per_proof: usize,
+    /// Blake2s delegation requests per verified proof.
+    pub blake_calls_per_proof: usize,
+}
+
+impl Default for VerifierCostModel {
+    fn default() -> Self {
+        Self {
+            cycles_per_proof: 1 << 21,
+            blake_calls_per_proof: 1 << 18,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecursionLayer {
+    First,
+    Second,
+}
+
+/// Single recursion level of the plan with estimated number of proofs it produces.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecursionPlanStep {
+    pub layer: RecursionLayer,
+    pub machine: Machine,
+    pub main_proof_count: usize,
+    pub delegation_proof_count: Vec<(u32, usize)>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RecursionPlan {
+    pub steps: Vec<RecursionPlanStep>,
+}
+
+impl RecursionPlan {
+    fn push_step(&mut self, layer: RecursionLayer, machine: &Machine, metadata: &ProofMetadata) {
+        self.steps.push(RecursionPlanStep {
+            layer,
+            machine: machine.clone(),
+            main_proof_count: metadata.reduced_proof_count + metadata.reduced_log_23_proof_count,
+            delegation_proof_count: metadata.delegation_proof_count.clone(),
+        });
+    }
+
+    pub fn total_proofs(&self) -> usize {
+        self.steps
+            .iter()
+            .map(|step| {
+                step.main_proof_count
+                    + step
+                        .delegation_proof_count
+                        .iter()
+                        .map(|(_, x)| *x)
+                        .sum::<usize>()
+            })
+            .sum()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecursionPlanError {
+    /// Layer didn't finish within `MAX_SIMULATED_RECURSION_LEVELS` levels.
+    NotConverged { layer: RecursionLayer },
+    /// Strategy requires a single proof at the given level of the 2nd layer, but there are more.
+    NotCollapsed { proof_level: usize },
+}
+
+impl std::fmt::Display for RecursionPlanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecursionPlanError::NotConverged { layer } => write!(
+                f,
+                "{layer:?} recursion layer didn't finish after {MAX_SIMULATED_RECURSION_LEVELS} levels"
+            ),
+            RecursionPlanError::NotCollapsed { proof_level } => write!(
+                f,
+                "proofs didn't collapse to a single one at 2nd layer level {proof_level}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RecursionPlanError {}
+
+pub(crate) fn is_collapsed(metadata: &ProofMetadata) -> bool {
+    metadata.reduced_log_23_proof_count == 1
+        && metadata.delegation_proof_count.iter().all(|(_, x)| *x <= 1)
+}
+
+/// Estimates the metadata of proofs produced by verifying all proofs from `metadata` on `machine`.
+fn estimate_next_level(
+    metadata: &ProofMetadata,
+    machine: &Machine,
+    cost_model: &VerifierCostModel,
+) -> ProofMetadata {
+    let proofs_count = metadata.total_proofs();
+    let cycles_per_chunk = match machine {
+        Machine::Reduced => reduced_risc_v_machine::NUM_CYCLES,
+        Machine::ReducedLog23 => reduced_risc_v_log_23_machine::NUM_CYCLES,
+        _ => panic!("{machine:?} is not used for recursion"),
+    };
+    let main_proof_count = (proofs_count * cost_model.cycles_per_proof).div_ceil(cycles_per_chunk);
+    let blake_proof_count = (proofs_count * cost_model.blake_calls_per_proof)
+        .div_ceil(blake2_with_compression::NUM_DELEGATION_CYCLES);
+    let mut next = metadata.clone();
+    next.basic_proof_count = 0;
+    next.reduced_proof_count = 0;
+    next.reduced_log_23_proof_count = 0;
+    match machine {
+        Machine::Reduced => next.reduced_proof_count = main_proof_count,
+        _ => next.reduced_log_23_proof_count = main_proof_count,
+    }
+    next.delegation_proof_count = if blake_proof_count == 0 {
+        vec![]
+    } else {
+        vec![(
+            BLAKE2_ROUND_FUNCTION_WITH_EXTENDED_CONTROL_ACCESS_ID,
+            blake_proof_count,
+        )]
+    };
+    next.recursion_depth = metadata.recursion_depth + 1;
+
+    next
+}
+
+impl RecursionStrategy {
+    /// Dry run of the recursion with the default verifier cost model.
+    pub fn simulate_plan(
+        &self,
+        initial_metadata: &ProofMetadata,
+    ) -> Result<RecursionPlan, RecursionPlanError> {
+        self.simulate_plan_with_cost_model(initial_metadata, &VerifierCostModel::default())
+    }
+
+    /// Walks through the recursion layers the same way as the prover does, but instead of proving
+    /// estimates the number of proofs at every level from `cost_model`.
+    pub fn simulate_plan_with_cost_model(
+        &self,
+        initial_metadata: &ProofMetadata,
+        cost_model: &VerifierCostModel,
+    ) -> Result<RecursionPlan, RecursionPlanError> {
+        let mut plan = RecursionPlan::default();
+        let mut metadata = initial_metadata.clone();
+        if !self.skip_first_layer() {
+            let mut finished = false;
+            for _ in 0..MAX_SIMULATED_RECURSION_LEVELS {
+                metadata = estimate_next_level(&metadata, &Machine::Reduced, cost_model);
+                plan.push_step(RecursionLayer::First, &Machine::Reduced, &metadata);
+                if self.switch_to_second_recursion_layer(&metadata) {
+                    finished = true;
+                    break;
+                }
+            }
+            if !finished {
+                return Err(RecursionPlanError::NotConverged {
+                    layer: RecursionLayer::First,
+                });
+            }
+        }
+
+        let machine = self.get_second_layer_machine();
+        for proof_level in 0..MAX_SIMULATED_RECURSION_LEVELS {
+            metadata = estimate_next_level(&metadata, &machine, cost_model);
+            plan.push_step(RecursionLayer::Second, &machine, &metadata);
+            if self.finish_second_recursion_layer(&metadata, proof_level)? {
+                return Ok(plan);
+            }
+        }
+
+        Err(RecursionPlanError::NotConverged {
+            layer: RecursionLayer::Second,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn base_metadata(basic_proof_count: usize) -> ProofMetadata {
+        ProofMetadata {
+            basic_proof_count,
+            delegation_proof_count: vec![(
+                BLAKE2_ROUND_FUNCTION_WITH_EXTENDED_CONTROL_ACCESS_ID,
+                1,
+            )],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_simulate_plan() {
+        let strategy = RecursionStrategy::use_reduced_log23_machine_multiple();
+        let plan = strategy.simulate_plan(&base_metadata(100)).unwrap();
+        let first_layer_steps = plan
+            .steps
+            .iter()
+            .take_while(|step| step.layer == RecursionLayer::First)
+            .count();
+        assert!(first_layer_steps > 0);
+        assert!(plan.steps[first_layer_steps..]
+            .iter()
+            .all(|step| step.layer == RecursionLayer::Second
+                && step.machine == Machine::ReducedLog23));
+        let last = plan.steps.last().unwrap();
+        assert_eq!(last.main_proof_count, 1);
+        assert!(last.delegation_proof_count.iter().all(|(_, x)| *x <= 1));
+    }
+
+    #[test]
+    fn test_simulate_plan_fixed_depth() {
+        let strategy = RecursionStrategy::FixedDepth(3);
+        let plan = strategy.simulate_plan(&base_metadata(2)).unwrap();
+        assert_eq!(plan.steps.len(), 3);
+        let result = RecursionStrategy::FixedDepth(1).simulate_plan(&base_metadata(100));
+        assert_eq!(
+            result,
+            Err(RecursionPlanError::NotCollapsed { proof_level: 0 })
+        );
+    }
+
+    #[test]
+    fn test_simulate_plan_not_converged() {
+        // every proof needs more than a full chunk to verify, so the count never goes down
+        let cost_model = VerifierCostModel {
+            cycles_per_proof: reduced_risc_v_log_23_machine::NUM_CYCLES + 1,
+            blake_calls_per_proof: 0,
+        };
+        let result = RecursionStrategy::UseReducedLog23MachineOnly
+            .simulate_plan_with_cost_model(&base_metadata(2), &cost_model);
+        assert_eq!(
+            result,
+            Err(RecursionPlanError::NotConverged {
+                layer: RecursionLayer::Second
+            })
+        );
+    }
+}
@@ -1,13 +1,16 @@
 // Code related to verifiers (creating oracles a.k.a input data etc).
 
 use risc_v_simulator::cycle::{
-    IMStandardIsaConfig, IWithoutByteAccessIsaConfigWithDelegation, MachineConfig,
+    IMStandardIsaConfig, IWithoutByteAccessIsaConfig, IWithoutByteAccessIsaConfigWithDelegation,
+    MachineConfig,
 };
 use verifier_common::cs::utils::split_timestamp;
+use verifier_common::prover::prover_stages::Proof;
 
-use crate::{ProofList, ProofMetadata};
+use crate::{Machine, ProofList, ProofMetadata};
 
 /// Prefix byte for universal verifier, to distinguish between different payloads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum VerifierCircuitsIdentifiers {
     // This enum is used inside tools/verifier/main.rs
     BaseLayer = 0,
@@ -26,125 +29,646 @@ pub enum VerifierCircuitsIdentifiers {
     CombinedMultipleRecursionLayers = 6,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OracleError {
+    /// Metadata doesn't contain any base, recursion or log23 proofs.
+    NoSupportedProofs,
+    /// Metadata claims more proofs of the given kind than the proof list holds.
+    MissingProofs {
+        kind: &'static str,
+        expected: usize,
+        available: usize,
+    },
+    /// Delegation type is not allowed for the main proofs from metadata.
+    UnexpectedDelegationType(u32),
+}
+
+impl std::fmt::Display for OracleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OracleError::NoSupportedProofs => write!(
+                f,
+                "Final proofs are no longer supported. Use log23 proofs instead."
+            ),
+            OracleError::MissingProofs {
+                kind,
+                expected,
+                available,
+            } => write!(
+                f,
+                "Metadata expects {} {} proofs, but only {} are provided.",
+                expected, kind, available
+            ),
+            OracleError::UnexpectedDelegationType(delegation_type) => write!(
+                f,
+                "No delegation circuit for {} is allowed for these proofs.",
+                delegation_type
+            ),
+        }
+    }
+}
+
+impl std::error::Error for OracleError {}
+
+/// Returns the prefix that universal verifier expects for the proofs described by metadata.
+// Base layer takes precedence over recursion, and recursion over log23, same as in the verifier.
+pub fn oracle_identifier_for(
+    metadata: &ProofMetadata,
+) -> Result<VerifierCircuitsIdentifiers, OracleError> {
+    if metadata.basic_proof_count > 0 {
+        Ok(VerifierCircuitsIdentifiers::BaseLayer)
+    } else if metadata.reduced_proof_count > 0 {
+        Ok(VerifierCircuitsIdentifiers::RecursionLayer)
+    } else if metadata.reduced_log_23_proof_count > 0 {
+        Ok(VerifierCircuitsIdentifiers::RecursionLog23Layer)
+    } else {
+        Err(OracleError::NoSupportedProofs)
+    }
+}
+
 /// Create oracle data for universal verifier.
 // Universal verifier requires a prefix byte at the beginning to know what type of data this is.
 pub fn generate_oracle_data_for_universal_verifier(
     metadata: &ProofMetadata,
     proofs: &ProofList,
 ) -> Vec<u32> {
+    let identifier = oracle_identifier_for(metadata).unwrap_or_else(|err| panic!("{}", err));
     let mut oracle = generate_oracle_data_from_metadata_and_proof_list(metadata, proofs);
+    oracle.insert(0, identifier as u32);
+    oracle
+}
 
-    if metadata.basic_proof_count > 0 {
-        oracle.insert(0, VerifierCircuitsIdentifiers::BaseLayer as u32);
-    } else if metadata.reduced_proof_count > 0 {
-        oracle.insert(0, VerifierCircuitsIdentifiers::RecursionLayer as u32);
-    } else if metadata.reduced_log_23_proof_count > 0 {
-        oracle.insert(0, VerifierCircuitsIdentifiers::RecursionLog23Layer as u32);
-    } else {
-        panic!("Final proofs are no longer supported. Use log23 proofs instead.");
-    };
+// Combined verifiers only accept proofs produced by the reduced recursion layer.
+fn assert_reduced_recursion_proof(metadata: &ProofMetadata) {
+    assert_eq!(
+        oracle_identifier_for(metadata),
+        Ok(VerifierCircuitsIdentifiers::RecursionLayer),
+        "only reduced recursion layer proofs can be combined"
+    );
+}
+
+/// Create oracle data for universal verifier that combines previous block proof with the current one.
+// Each proof carries its own prev_end_params_output (if any) at the end of its oracle data.
+pub fn generate_oracle_data_for_combined_pair(
+    prev: (&ProofMetadata, &ProofList),
+    current: (&ProofMetadata, &ProofList),
+) -> Vec<u32> {
+    assert_reduced_recursion_proof(prev.0);
+    assert_reduced_recursion_proof(current.0);
+    let mut oracle = vec![VerifierCircuitsIdentifiers::CombinedRecursionLayers as u32];
+    oracle.extend(generate_oracle_data_from_metadata_and_proof_list(
+        prev.0, prev.1,
+    ));
+    oracle.extend(generate_oracle_data_from_metadata_and_proof_list(
+        current.0, current.1,
+    ));
+    oracle
+}
+
+/// Create oracle data for universal verifier that combines multiple recursion proofs into one.
+// Identifier is followed by the number of combined proofs and then by the oracle data of every proof in order.
+pub fn generate_oracle_data_for_combined_recursion(
+    metadatas: &[ProofMetadata],
+    proofs: &[ProofList],
+) -> Vec<u32> {
+    assert_eq!(
+        metadatas.len(),
+        proofs.len(),
+        "every proof list must have its metadata"
+    );
+    assert!(
+        metadatas.len() >= 2,
+        "combined verifier needs at least two proofs"
+    );
+    let mut oracle = vec![
+        VerifierCircuitsIdentifiers::CombinedMultipleRecursionLayers as u32,
+        metadatas.len().try_into().unwrap(),
+    ];
+    for (metadata, proofs) in metadatas.iter().zip(proofs.iter()) {
+        assert_reduced_recursion_proof(metadata);
+        oracle.extend(generate_oracle_data_from_metadata_and_proof_list(
+            metadata, proofs,
+        ));
+    }
     oracle
 }
 
+/// Checks that proof list contains every proof described by metadata, and that all delegation types
+/// are allowed for its main proofs.
+pub fn validate_metadata_and_proof_list(
+    metadata: &ProofMetadata,
+    proofs: &ProofList,
+) -> Result<(), OracleError> {
+    let allowed_delegation_types = match oracle_identifier_for(metadata)? {
+        VerifierCircuitsIdentifiers::BaseLayer => allowed_delegation_types(Machine::Standard),
+        VerifierCircuitsIdentifiers::RecursionLayer => allowed_delegation_types(Machine::Reduced),
+        _ => allowed_delegation_types(Machine::ReducedLog23),
+    };
+    let check = |kind, expected, available| {
+        if expected > available {
+            Err(OracleError::MissingProofs {
+                kind,
+                expected,
+                available,
+            })
+        } else {
+            Ok(())
+        }
+    };
+    check(
+        "basic",
+        metadata.basic_proof_count,
+        proofs.basic_proofs.len(),
+    )?;
+    check(
+        "reduced",
+        metadata.reduced_proof_count,
+        proofs.reduced_proofs.len(),
+    )?;
+    check(
+        "reduced log 23",
+        metadata.reduced_log_23_proof_count,
+        proofs.reduced_log_23_proofs.len(),
+    )?;
+    for (delegation_type, count) in metadata.delegation_proof_count.iter() {
+        if !allowed_delegation_types.contains(delegation_type) {
+            return Err(OracleError::UnexpectedDelegationType(*delegation_type));
+        }
+        check(
+            "delegation",
+            *count,
+            select_delegation_proofs(proofs, *delegation_type).len(),
+        )?;
+    }
+
+    Ok(())
+}
+
 /// Create oracle data for a verifier from metadata and proof list.
 pub fn generate_oracle_data_from_metadata_and_proof_list(
     metadata: &ProofMetadata,
     proofs: &ProofList,
 ) -> Vec<u32> {
+    validate_metadata_and_proof_list(metadata, proofs).unwrap_or_else(|err| panic!("{}", err));
     let mut oracle_data = vec![];
+    visit_oracle_words(metadata, proofs, |words| {
+        oracle_data.extend_from_slice(words);
+        Ok::<(), std::convert::Infallible>(())
+    })
+    .unwrap();
+    oracle_data
+}
+
+/// Same as `generate_oracle_data_from_metadata_and_proof_list`, but streams every word (little-endian)
+/// to `writer` instead of collecting the whole oracle in memory.
+pub fn write_oracle_data<W: std::io::Write>(
+    metadata: &ProofMetadata,
+    proofs: &ProofList,
+    mut writer: W,
+) -> std::io::Result<()> {
+    validate_metadata_and_proof_list(metadata, proofs)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+    let mut bytes = vec![];
+    visit_oracle_words(metadata, proofs, |words| {
+        bytes.clear();
+        bytes.extend(words.iter().flat_map(|word| word.to_le_bytes()));
+        writer.write_all(&bytes)
+    })?;
+    writer.flush()
+}
+
+// Passes the oracle data to `sink` section by section, so callers decide where the words go.
+fn visit_oracle_words<E>(
+    metadata: &ProofMetadata,
+    proofs: &ProofList,
+    mut sink: impl FnMut(&[u32]) -> Result<(), E>,
+) -> Result<(), E> {
     // first - it reads all the register values.
 
     assert_eq!(32, metadata.register_values.len());
+    let mut registers = Vec::with_capacity(32 * 3);
     for register in metadata.register_values.iter() {
-        oracle_data.push(register.value);
         let (low, high) = split_timestamp(register.last_access_timestamp);
-        oracle_data.push(low);
-        oracle_data.push(high);
+        registers.extend([register.value, low, high]);
     }
+    sink(&registers)?;
 
-    let delegations: Vec<u32> = if metadata.basic_proof_count > 0 {
-        // Then it needs the number of circuits.
-        oracle_data.push(metadata.basic_proof_count.try_into().unwrap());
+    let (main_proofs, delegations) = select_main_proofs(metadata, proofs);
 
-        assert_eq!(metadata.reduced_proof_count, 0);
+    // Then it needs the number of circuits.
+    sink(&[main_proofs.len().try_into().unwrap()])?;
 
-        // Then circuit proofs themselves.
-        for i in 0..metadata.basic_proof_count {
-            let proof = &proofs.basic_proofs[i];
-            oracle_data
-                .extend(verifier_common::proof_flattener::flatten_proof_for_skeleton(&proof, true));
-            for query in proof.queries.iter() {
-                oracle_data.extend(verifier_common::proof_flattener::flatten_query(query));
-            }
-        }
+    // Then circuit proofs themselves.
+    for proof in main_proofs {
+        sink(&flatten_proof(proof, true))?;
+    }
 
-        full_machine_allowed_delegation_types()
-    } else if metadata.reduced_proof_count > 0 {
-        oracle_data.push(metadata.reduced_proof_count.try_into().unwrap());
-
-        // Or reduced proofs
-        for i in 0..metadata.reduced_proof_count {
-            let proof = &proofs.reduced_proofs[i];
-            oracle_data
-                .extend(verifier_common::proof_flattener::flatten_proof_for_skeleton(&proof, true));
-            for query in proof.queries.iter() {
-                oracle_data.extend(verifier_common::proof_flattener::flatten_query(query));
-            }
+    for delegation_type in &delegations {
+        let delegation_proofs = select_delegation_proofs(proofs, *delegation_type);
+        sink(&[delegation_proofs.len() as u32])?;
+
+        for proof in delegation_proofs {
+            // Notice, that apply_shuffle is assumed false for delegation proofs.
+            sink(&flatten_proof(proof, false))?;
         }
+    }
+    if let Some(prev_params) = metadata.prev_end_params_output {
+        sink(&prev_params)?;
+    }
+    Ok(())
+}
 
-        reduced_machine_allowed_delegation_types()
-    } else if metadata.reduced_log_23_proof_count > 0 {
-        oracle_data.push(metadata.reduced_log_23_proof_count.try_into().unwrap());
-
-        // Or reduced log 23 proofs
-        for i in 0..metadata.reduced_log_23_proof_count {
-            let proof = &proofs.reduced_log_23_proofs[i];
-            oracle_data
-                .extend(verifier_common::proof_flattener::flatten_proof_for_skeleton(&proof, true));
-            for query in proof.queries.iter() {
-                oracle_data.extend(verifier_common::proof_flattener::flatten_query(query));
-            }
+/// Create oracle data for the first block in a chain. Genesis block has no previous block to chain to,
+/// so it must not carry `prev_end_params_output`.
+pub fn generate_oracle_data_genesis(metadata: &ProofMetadata, proofs: &ProofList) -> Vec<u32> {
+    assert!(
+        metadata.prev_end_params_output.is_none(),
+        "genesis block must not have prev_end_params_output, got {:?}",
+        metadata.prev_end_params_output
+    );
+    generate_oracle_data_from_metadata_and_proof_list(metadata, proofs)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OracleSectionKind {
+    RegisterValues,
+    MainProofCount,
+    MainProof { index: usize },
+    DelegationProofCount { delegation_type: u32 },
+    DelegationProof { delegation_type: u32, index: usize },
+    PrevEndParams,
+}
+
+/// Part of the oracle data, `offset` and `len` are in words.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OracleSection {
+    pub kind: OracleSectionKind,
+    pub offset: usize,
+    pub len: usize,
+}
+
+/// Describes, in order, the sections of the data produced by `generate_oracle_data_from_metadata_and_proof_list`.
+// Proof sizes depend on the number of queries and FRI steps, so proofs are needed to compute them.
+pub fn oracle_sections(metadata: &ProofMetadata, proofs: &ProofList) -> Vec<OracleSection> {
+    let mut sections = vec![];
+    let mut offset = 0;
+    let mut push = |kind, len| {
+        sections.push(OracleSection { kind, offset, len });
+        offset += len;
+    };
+
+    assert_eq!(32, metadata.register_values.len());
+    push(OracleSectionKind::RegisterValues, 32 * 3);
+
+    let (main_proofs, delegations) = select_main_proofs(metadata, proofs);
+    push(OracleSectionKind::MainProofCount, 1);
+    for (index, proof) in main_proofs.iter().enumerate() {
+        push(
+            OracleSectionKind::MainProof { index },
+            flatten_proof(proof, true).len(),
+        );
+    }
+
+    for delegation_type in delegations {
+        push(
+            OracleSectionKind::DelegationProofCount { delegation_type },
+            1,
+        );
+        for (index, proof) in select_delegation_proofs(proofs, delegation_type)
+            .iter()
+            .enumerate()
+        {
+            push(
+                OracleSectionKind::DelegationProof {
+                    delegation_type,
+                    index,
+                },
+                flatten_proof(proof, false).len(),
+            );
         }
+    }
+    if let Some(prev_params) = metadata.prev_end_params_output {
+        push(OracleSectionKind::PrevEndParams, prev_params.len());
+    }
+    sections
+}
 
-        reduced_machine_allowed_delegation_types()
+/// Returns main circuit proofs (either basic, reduced or reduced log 23) and delegation types allowed for them.
+/// Delegation types are in the order of the machine's `ALLOWED_DELEGATION_CSRS`, as the universal verifier
+/// reads delegation proofs in that order.
+fn select_main_proofs<'a>(
+    metadata: &ProofMetadata,
+    proofs: &'a ProofList,
+) -> (&'a [Proof], Vec<u32>) {
+    let result = if metadata.basic_proof_count > 0 {
+        assert_eq!(metadata.reduced_proof_count, 0);
+        (
+            &proofs.basic_proofs[..metadata.basic_proof_count],
+            allowed_delegation_types(Machine::Standard),
+        )
+    } else if metadata.reduced_proof_count > 0 {
+        (
+            &proofs.reduced_proofs[..metadata.reduced_proof_count],
+            allowed_delegation_types(Machine::Reduced),
+        )
+    } else if metadata.reduced_log_23_proof_count > 0 {
+        (
+            &proofs.reduced_log_23_proofs[..metadata.reduced_log_23_proof_count],
+            allowed_delegation_types(Machine::ReducedLog23),
+        )
     } else {
         panic!("No proofs");
     };
+    // delegation proofs are written in this order, and re-sorting would silently desync the oracle
+    // from the verifier if the list changes, so we only check that it's ascending
+    assert!(
+        result.1.is_sorted(),
+        "allowed delegation types {:?} must be sorted",
+        result.1
+    );
 
     for (k, _) in metadata.delegation_proof_count.iter() {
-        assert!(delegations.contains(k), "No delegation circuit for {}", k);
+        assert!(result.1.contains(k), "No delegation circuit for {}", k);
     }
 
-    for delegation_type in &delegations {
-        let empty = vec![];
-        let delegation_proofs = proofs
-            .delegation_proofs
-            .iter()
-            .find(|(k, _)| k == delegation_type)
-            .map(|(_, v)| v)
-            .unwrap_or(&empty);
-        oracle_data.push(delegation_proofs.len() as u32);
+    result
+}
 
-        for proof in delegation_proofs {
-            // Notice, that apply_shuffle is assumed false for delegation proofs.
-            oracle_data.extend(
-                verifier_common::proof_flattener::flatten_proof_for_skeleton(&proof, false),
-            );
-            for query in proof.queries.iter() {
-                oracle_data.extend(verifier_common::proof_flattener::flatten_query(query));
+fn select_delegation_proofs(proofs: &ProofList, delegation_type: u32) -> &[Proof] {
+    proofs
+        .delegation_proofs
+        .iter()
+        .find(|(k, _)| *k == delegation_type)
+        .map(|(_, v)| &v[..])
+        .unwrap_or(&[])
+}
+
+fn flatten_proof(proof: &Proof, apply_shuffle_ram_lazy_init: bool) -> Vec<u32> {
+    let mut result = verifier_common::proof_flattener::flatten_proof_for_skeleton(
+        proof,
+        apply_shuffle_ram_lazy_init,
+    );
+    for query in proof.queries.iter() {
+        result.extend(verifier_common::proof_flattener::flatten_query(query));
+    }
+    result
+}
+
+/// Returns delegation types that proofs of the given machine can be accompanied by.
+pub fn allowed_delegation_types(machine: Machine) -> Vec<u32> {
+    match machine {
+        Machine::Standard => IMStandardIsaConfig::ALLOWED_DELEGATION_CSRS.to_vec(),
+        Machine::Reduced | Machine::ReducedLog23 => {
+            IWithoutByteAccessIsaConfigWithDelegation::ALLOWED_DELEGATION_CSRS.to_vec()
+        }
+        Machine::ReducedFinal => IWithoutByteAccessIsaConfig::ALLOWED_DELEGATION_CSRS.to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ProgramProof;
+
+    #[test]
+    fn test_allowed_delegation_types() {
+        assert_eq!(
+            allowed_delegation_types(Machine::Reduced),
+            IWithoutByteAccessIsaConfigWithDelegation::ALLOWED_DELEGATION_CSRS
+        );
+        assert_eq!(
+            allowed_delegation_types(Machine::ReducedLog23),
+            allowed_delegation_types(Machine::Reduced)
+        );
+        assert_eq!(
+            allowed_delegation_types(Machine::Standard),
+            IMStandardIsaConfig::ALLOWED_DELEGATION_CSRS
+        );
+        for machine in [
+            Machine::Standard,
+            Machine::Reduced,
+            Machine::ReducedLog23,
+            Machine::ReducedFinal,
+        ] {
+            assert!(allowed_delegation_types(machine).is_sorted());
+        }
+    }
+
+    #[test]
+    fn test_oracle_identifier_for() {
+        let mut metadata = ProofMetadata::default();
+        assert_eq!(
+            oracle_identifier_for(&metadata),
+            Err(OracleError::NoSupportedProofs)
+        );
+
+        metadata.deprecated_final_proof_count = 1;
+        assert_eq!(
+            oracle_identifier_for(&metadata),
+            Err(OracleError::NoSupportedProofs)
+        );
+
+        metadata.reduced_log_23_proof_count = 1;
+        assert_eq!(
+            oracle_identifier_for(&metadata),
+            Ok(VerifierCircuitsIdentifiers::RecursionLog23Layer)
+        );
+
+        metadata.reduced_proof_count = 1;
+        assert_eq!(
+            oracle_identifier_for(&metadata),
+            Ok(VerifierCircuitsIdentifiers::RecursionLayer)
+        );
+
+        metadata.basic_proof_count = 1;
+        assert_eq!(
+            oracle_identifier_for(&metadata),
+            Ok(VerifierCircuitsIdentifiers::BaseLayer)
+        );
+    }
+
+    fn load_test_proofs() -> (ProofMetadata, ProofList) {
+        let mut src =
+            std::fs::File::open("final_recursion_over_final_recursion_layer.json").unwrap();
+        let proofs: ProgramProof = serde_json::from_reader(&mut src).unwrap();
+        proofs.to_metadata_and_proof_list()
+    }
+
+    #[test]
+    fn test_oracle_data_for_combined_recursion() {
+        let (first_metadata, first_proofs) = load_test_proofs();
+        let (second_metadata, second_proofs) = load_test_proofs();
+        let single =
+            generate_oracle_data_from_metadata_and_proof_list(&first_metadata, &first_proofs);
+
+        let oracle = generate_oracle_data_for_combined_recursion(
+            &[first_metadata, second_metadata],
+            &[first_proofs, second_proofs],
+        );
+        assert_eq!(oracle[0], 6);
+        assert_eq!(oracle[1], 2);
+        assert_eq!(oracle.len(), 2 + 2 * single.len());
+        assert_eq!(&oracle[2..2 + single.len()], &single[..]);
+        assert_eq!(&oracle[2 + single.len()..], &single[..]);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least two proofs")]
+    fn test_combined_recursion_rejects_single_proof() {
+        let (metadata, proofs) = load_test_proofs();
+        generate_oracle_data_for_combined_recursion(&[metadata], &[proofs]);
+    }
+
+    #[test]
+    #[should_panic(expected = "only reduced recursion layer proofs")]
+    fn test_combined_recursion_rejects_base_layer_proofs() {
+        let (first_metadata, first_proofs) = load_test_proofs();
+        let (mut second_metadata, second_proofs) = load_test_proofs();
+        second_metadata.basic_proof_count = 1;
+        generate_oracle_data_for_combined_recursion(
+            &[first_metadata, second_metadata],
+            &[first_proofs, second_proofs],
+        );
+    }
+
+    #[test]
+    fn test_oracle_data_for_combined_pair() {
+        let (prev_metadata, prev_proofs) = load_test_proofs();
+        let (mut current_metadata, current_proofs) = load_test_proofs();
+        for register in current_metadata.register_values.iter_mut() {
+            register.value = register.value.wrapping_add(1);
+        }
+        let prev_len =
+            generate_oracle_data_from_metadata_and_proof_list(&prev_metadata, &prev_proofs).len();
+
+        let oracle = generate_oracle_data_for_combined_pair(
+            (&prev_metadata, &prev_proofs),
+            (&current_metadata, &current_proofs),
+        );
+        assert_eq!(oracle[0], 4);
+        for (offset, metadata) in [(1, &prev_metadata), (1 + prev_len, &current_metadata)] {
+            for (index, register) in metadata.register_values.iter().enumerate() {
+                assert_eq!(oracle[offset + index * 3], register.value);
             }
         }
     }
-    if let Some(prev_params) = metadata.prev_end_params_output {
-        oracle_data.extend(prev_params);
+
+    #[test]
+    #[should_panic(expected = "only reduced recursion layer proofs")]
+    fn test_combined_pair_rejects_base_layer_proofs() {
+        let (mut prev_metadata, prev_proofs) = load_test_proofs();
+        let (current_metadata, current_proofs) = load_test_proofs();
+        prev_metadata.basic_proof_count = 1;
+        generate_oracle_data_for_combined_pair(
+            (&prev_metadata, &prev_proofs),
+            (&current_metadata, &current_proofs),
+        );
     }
-    oracle_data
-}
 
-fn reduced_machine_allowed_delegation_types() -> Vec<u32> {
-    IWithoutByteAccessIsaConfigWithDelegation::ALLOWED_DELEGATION_CSRS.to_vec()
-}
+    #[test]
+    fn test_write_oracle_data() {
+        let (metadata, proofs) = load_test_proofs();
+        assert_eq!(proofs.reduced_proofs.len(), 1);
+        assert!(proofs.delegation_proofs.is_empty());
+
+        // expected layout written out by hand: registers, circuit count, the proof, previous params
+        let mut expected = vec![];
+        for register in metadata.register_values.iter() {
+            let (low, high) = split_timestamp(register.last_access_timestamp);
+            expected.extend([register.value, low, high]);
+        }
+        expected.push(1);
+        expected.extend(flatten_proof(&proofs.reduced_proofs[0], true));
+        expected.extend(metadata.prev_end_params_output.unwrap());
 
-fn full_machine_allowed_delegation_types() -> Vec<u32> {
-    IMStandardIsaConfig::ALLOWED_DELEGATION_CSRS.to_vec()
+        let mut bytes = vec![];
+        write_oracle_data(&metadata, &proofs, &mut bytes).unwrap();
+        let expected_bytes: Vec<u8> = expected
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect();
+        assert_eq!(bytes, expected_bytes);
+        assert_eq!(
+            generate_oracle_data_from_metadata_and_proof_list(&metadata, &proofs),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_oracle_data_genesis() {
+        let (mut metadata, proofs) = load_test_proofs();
+        metadata.prev_end_params_output = None;
+        assert_eq!(
+            generate_oracle_data_genesis(&metadata, &proofs),
+            generate_oracle_data_from_metadata_and_proof_list(&metadata, &proofs)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "genesis block must not have prev_end_params_output")]
+    fn test_oracle_data_genesis_rejects_prev_params() {
+        let (mut metadata, proofs) = load_test_proofs();
+        metadata.prev_end_params_output = Some([0; 16]);
+        generate_oracle_data_genesis(&metadata, &proofs);
+    }
+
+    #[test]
+    fn test_validate_metadata_and_proof_list() {
+        let (mut metadata, proofs) = load_test_proofs();
+        assert_eq!(validate_metadata_and_proof_list(&metadata, &proofs), Ok(()));
+
+        metadata.reduced_proof_count = proofs.reduced_proofs.len() + 1;
+        assert_eq!(
+            validate_metadata_and_proof_list(&metadata, &proofs),
+            Err(OracleError::MissingProofs {
+                kind: "reduced",
+                expected: proofs.reduced_proofs.len() + 1,
+                available: proofs.reduced_proofs.len(),
+            })
+        );
+
+        metadata.reduced_proof_count = proofs.reduced_proofs.len();
+        metadata.delegation_proof_count.push((u32::MAX, 1));
+        assert_eq!(
+            validate_metadata_and_proof_list(&metadata, &proofs),
+            Err(OracleError::UnexpectedDelegationType(u32::MAX))
+        );
+    }
+
+    #[test]
+    fn test_oracle_data_is_deterministic() {
+        let (metadata, proofs) = load_test_proofs();
+        let (mut reordered_metadata, mut reordered_proofs) = load_test_proofs();
+        reordered_metadata.delegation_proof_count.reverse();
+        reordered_proofs.delegation_proofs.reverse();
+
+        assert_eq!(
+            generate_oracle_data_from_metadata_and_proof_list(&metadata, &proofs),
+            generate_oracle_data_from_metadata_and_proof_list(
+                &reordered_metadata,
+                &reordered_proofs
+            )
+        );
+    }
+
+    #[test]
+    fn test_oracle_sections() {
+        let (metadata, proofs) = load_test_proofs();
+
+        let oracle = generate_oracle_data_from_metadata_and_proof_list(&metadata, &proofs);
+        let sections = oracle_sections(&metadata, &proofs);
+
+        assert_eq!(sections[0].kind, OracleSectionKind::RegisterValues);
+        assert_eq!(sections[1].kind, OracleSectionKind::MainProofCount);
+        assert_eq!(
+            oracle[sections[1].offset] as usize,
+            metadata.reduced_proof_count
+        );
+        let mut offset = 0;
+        for section in sections.iter() {
+            assert_eq!(section.offset, offset);
+            offset += section.len;
+        }
+        assert_eq!(offset, oracle.len());
+    }
 }
@@ -159,7 +159,7 @@ EXTERN __global__ void ab_gather_merkle_paths_kernel(const unsigned *indexes, co
   results[dst_index] = values[src_index];
 }
 
-EXTERN __global__ void ab_blake2s_pow_kernel(const u64 *seed, const u32 bits_count, const u64 max_nonce, volatile u64 *result) {
+EXTERN __global__ void ab_blake2s_pow_kernel(const u64 *seed, const u32 bits_count, const u64 start_nonce, const u64 max_nonce, volatile u64 *result) {
   const uint32_t digest_mask = 0xffffffff << 32 - bits_count;
   __align__(8) u32 m_u32[BLOCK_SIZE] = {};
   auto m_u64 = reinterpret_cast<u64 *>(m_u32);
@@ -167,18 +167,21 @@ EXTERN __global__ void ab_blake2s_pow_kernel(const u64 *seed, const u32 bits_cou
   for (unsigned i = 0; i < 4; i++)
     m_u64[i] = seed[i];
   const unsigned stride = blockDim.x * gridDim.x;
-  for (uint64_t nonce = threadIdx.x + blockIdx.x * blockDim.x; nonce < max_nonce; nonce += stride) {
+  for (uint64_t nonce = start_nonce + threadIdx.x + blockIdx.x * blockDim.x; nonce < max_nonce; nonce += stride) {
+    // result only decreases, so every nonce skipped here is greater than the final result,
+    // which makes the found nonce the smallest one in the range
+    if (nonce > *result)
+      return;
     m_u64[STATE_SIZE / 2] = nonce;
     u32 state[STATE_SIZE];
     initialize(state);
     u32 t = 0;
     compress<true>(state, t, m_u32, STATE_SIZE + 2);
     if (!(state[0] & digest_mask)) {
-      atomicCAS(reinterpret_cast<unsigned long long *>(const_cast<u64 *>(result)), UINT64_MAX, nonce);
+      atomicMin(reinterpret_cast<unsigned long long *>(const_cast<u64 *>(result)), nonce);
       __threadfence();
-    }
-    if (*result != UINT64_MAX)
       return;
+    }
   }
 }
 
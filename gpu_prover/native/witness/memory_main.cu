@@ -174,24 +174,25 @@ template <bool COMPUTE_WITNESS>
 DEVICE_FORCEINLINE void generate(const MainMemorySubtree &subtree, const MemoryQueriesTimestampComparisonAuxVars &memory_queries_timestamp_comparison_aux_vars,
                                  const ShuffleRamSetupAndTeardown &setup_and_teardown, const ShuffleRamAuxComparisonSet &lazy_init_address_aux_vars,
                                  const MainTrace &trace, const TimestampScalar timestamp_high_from_circuit_sequence, matrix_setter<bf, st_modifier::cg> memory,
-                                 matrix_setter<bf, st_modifier::cg> witness, const unsigned count) {
+                                 matrix_setter<bf, st_modifier::cg> witness, const unsigned count, const unsigned offset, const unsigned rows_count) {
   const unsigned gid = blockIdx.x * blockDim.x + threadIdx.x;
-  if (gid >= count)
+  if (gid >= rows_count)
     return;
-  memory.add_row(gid);
-  witness.add_row(gid);
-  process_lazy_inits_and_teardowns<COMPUTE_WITNESS>(subtree, setup_and_teardown, lazy_init_address_aux_vars, memory, witness, count, gid);
+  const unsigned index = offset + gid;
+  memory.add_row(index);
+  witness.add_row(index);
+  process_lazy_inits_and_teardowns<COMPUTE_WITNESS>(subtree, setup_and_teardown, lazy_init_address_aux_vars, memory, witness, count, index);
   process_shuffle_ram_access_sets<COMPUTE_WITNESS>(subtree, memory_queries_timestamp_comparison_aux_vars, trace, timestamp_high_from_circuit_sequence, memory,
-                                                   witness, gid);
+                                                   witness, index);
   if (subtree.delegation_request_layout.tag == Some)
-    process_delegation_requests(subtree, trace, memory, gid);
+    process_delegation_requests(subtree, trace, memory, index);
 }
 
 EXTERN __global__ void ab_generate_memory_values_main_kernel(const __grid_constant__ MainMemorySubtree subtree,
                                                              const __grid_constant__ ShuffleRamSetupAndTeardown setup_and_teardown,
                                                              const __grid_constant__ MainTrace trace, const matrix_setter<bf, st_modifier::cg> memory,
-                                                             const unsigned count) {
-  generate<false>(subtree, {}, setup_and_teardown, {}, trace, {}, memory, memory, count);
+                                                             const unsigned count, const unsigned offset, const unsigned rows_count) {
+  generate<false>(subtree, {}, setup_and_teardown, {}, trace, {}, memory, memory, count, offset, rows_count);
 }
 
 EXTERN __global__ void ab_generate_memory_and_witness_values_main_kernel(
@@ -199,9 +200,10 @@ EXTERN __global__ void ab_generate_memory_and_witness_values_main_kernel(
     const __grid_constant__ MemoryQueriesTimestampComparisonAuxVars memory_queries_timestamp_comparison_aux_vars,
     const __grid_constant__ ShuffleRamSetupAndTeardown setup_and_teardown, const __grid_constant__ ShuffleRamAuxComparisonSet lazy_init_address_aux_vars,
     const __grid_constant__ MainTrace trace, const __grid_constant__ TimestampScalar timestamp_high_from_circuit_sequence,
-    const matrix_setter<bf, st_modifier::cg> memory, const matrix_setter<bf, st_modifier::cg> witness, const unsigned count) {
+    const matrix_setter<bf, st_modifier::cg> memory, const matrix_setter<bf, st_modifier::cg> witness, const unsigned count, const unsigned offset,
+    const unsigned rows_count) {
   generate<true>(subtree, memory_queries_timestamp_comparison_aux_vars, setup_and_teardown, lazy_init_address_aux_vars, trace,
-                 timestamp_high_from_circuit_sequence, memory, witness, count);
+                 timestamp_high_from_circuit_sequence, memory, witness, count, offset, rows_count);
 }
 
 } // namespace airbender::witness::memory::main
\ No newline at end of file
@@ -403,12 +403,14 @@ template <class R> struct WitnessProxy {
 #define KERNEL(NAME, ORACLE)                                                                                                                                   \
   EXTERN __global__ void KERNEL_NAME(NAME)(const __grid_constant__ ORACLE oracle, const wrapped_f *const __restrict__ generic_lookup_tables,                   \
                                            const wrapped_f *const __restrict__ memory, wrapped_f *const __restrict__ witness,                                  \
-                                           u32 *const __restrict__ lookup_mappings, const unsigned stride, const unsigned count) {                             \
+                                           u32 *const __restrict__ lookup_mappings, const unsigned stride, const unsigned offset,                              \
+                                           const unsigned count) {                                                                                             \
     const unsigned gid = blockIdx.x * blockDim.x + threadIdx.x;                                                                                                \
     if (gid >= count)                                                                                                                                          \
       return;                                                                                                                                                  \
+    const unsigned index = offset + gid;                                                                                                                       \
     SCRATCH                                                                                                                                                    \
-    const WitnessProxy<ORACLE> p = {oracle, generic_lookup_tables, memory, witness, lookup_mappings, scratch, stride, gid};                                    \
+    const WitnessProxy<ORACLE> p = {oracle, generic_lookup_tables, memory, witness, lookup_mappings, scratch, stride, index};                                  \
     FN_CALL(generate)                                                                                                                                          \
   }
 
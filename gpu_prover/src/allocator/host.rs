@@ -1,7 +1,7 @@
 use crate::allocator::allocation_data::StaticAllocationData;
 use crate::allocator::tracker::AllocationPlacement;
 use crate::allocator::{
-    ConcurrentInnerStaticAllocatorWrapper, InnerStaticAllocatorWrapper,
+    ConcurrentInnerStaticAllocatorWrapper, InnerStaticAllocator, InnerStaticAllocatorWrapper,
     NonConcurrentInnerStaticAllocatorWrapper, StaticAllocation, StaticAllocationBackend,
     StaticAllocator,
 };
@@ -13,9 +13,13 @@ use std::fmt::{Debug, Formatter};
 use std::ops::{Deref, DerefMut};
 use std::ptr::NonNull;
 use std::slice;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::OnceLock;
 
+// the handle is created once and never replaced, so cloning it stays cheap,
+// initialization and shutdown swap the backends behind it instead
 pub static STATIC_HOST_ALLOCATOR: OnceLock<ConcurrentStaticHostAllocator> = OnceLock::new();
+static IS_STATIC_HOST_ALLOCATOR_INITIALIZED: AtomicBool = AtomicBool::new(false);
 
 impl StaticAllocationBackend for HostAllocation<u8> {
     fn as_non_null(&mut self) -> NonNull<u8> {
@@ -53,8 +57,36 @@ impl ConcurrentStaticHostAllocator {
         backends: impl IntoIterator<Item = HostAllocation<u8>>,
         log_chunk_size: u32,
     ) {
-        let allocator = ConcurrentStaticHostAllocator::new(backends, log_chunk_size);
-        assert!(STATIC_HOST_ALLOCATOR.set(allocator).is_ok());
+        assert!(
+            IS_STATIC_HOST_ALLOCATOR_INITIALIZED
+                .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok(),
+            "global host allocator is already initialized"
+        );
+        let allocator = STATIC_HOST_ALLOCATOR
+            .get_or_init(|| ConcurrentStaticHostAllocator::new([], log_chunk_size));
+        let inner = InnerStaticAllocator::new(backends, log_chunk_size);
+        allocator.inner.execute(|current| *current = inner);
+    }
+
+    /// Resets the global allocator to uninitialized and releases its backing host allocations,
+    /// so it can be initialized again. All allocations must be freed before this call,
+    /// clones of the allocator obtained earlier fail to allocate until it is reinitialized.
+    pub fn shutdown_global() {
+        assert!(
+            IS_STATIC_HOST_ALLOCATOR_INITIALIZED
+                .compare_exchange(true, false, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok(),
+            "global host allocator is not initialized"
+        );
+        STATIC_HOST_ALLOCATOR.get().unwrap().inner.execute(|inner| {
+            assert_eq!(
+                inner.tracker.get_used_mem_current(),
+                0,
+                "global host allocator has outstanding allocations"
+            );
+            *inner = InnerStaticAllocator::new([], inner.log_chunk_size);
+        });
     }
 
     pub fn get_global() -> &'static ConcurrentStaticHostAllocator {
@@ -62,7 +94,7 @@ impl ConcurrentStaticHostAllocator {
     }
 
     pub fn is_initialized_global() -> bool {
-        STATIC_HOST_ALLOCATOR.get().is_some()
+        IS_STATIC_HOST_ALLOCATOR_INITIALIZED.load(Ordering::Acquire)
     }
 }
 
@@ -88,15 +120,16 @@ impl<T, W: InnerStaticHostAllocatorWrapper> DerefMut
 unsafe impl<W: InnerStaticHostAllocatorWrapper> Allocator for StaticHostAllocator<W> {
     fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
         let len = layout.size();
-        if let Ok(data) = self
-            .inner
-            .execute(|inner| inner.alloc(len, AllocationPlacement::BestFit))
-        {
+        if let Ok((data, log_chunk_size)) = self.inner.execute(|inner| {
+            inner
+                .alloc(len, AllocationPlacement::BestFit)
+                .map(|data| (data, inner.log_chunk_size))
+        }) {
             let ptr = data.ptr;
             assert!(ptr.is_aligned_to(layout.align()));
             assert_eq!(data.len, len);
             let len = data.alloc_len;
-            assert_eq!(data.len.next_multiple_of(1 << self.log_chunk_size), len);
+            assert_eq!(data.len.next_multiple_of(1 << log_chunk_size), len);
             Ok(NonNull::slice_from_raw_parts(ptr, len))
         } else {
             error!("allocation of {len} bytes in StaticHostAllocator failed");
@@ -106,9 +139,11 @@ unsafe impl<W: InnerStaticHostAllocatorWrapper> Allocator for StaticHostAllocato
 
     unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
         let len = layout.size();
-        let alloc_len = len.next_multiple_of(1 << self.log_chunk_size);
-        let data = StaticAllocationData::new(ptr, len, alloc_len);
-        self.inner.execute(|inner| inner.free(data));
+        self.inner.execute(|inner| {
+            let alloc_len = len.next_multiple_of(1 << inner.log_chunk_size);
+            let data = StaticAllocationData::new(ptr, len, alloc_len);
+            inner.free(data)
+        });
     }
 }
 
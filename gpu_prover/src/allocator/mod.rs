@@ -8,6 +8,8 @@ use era_cudart::result::CudaResult;
 use era_cudart_sys::CudaError;
 use itertools::Itertools;
 use std::cell::RefCell;
+#[cfg(feature = "log_gpu_mem_usage")]
+use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::mem::forget;
 use std::ptr::NonNull;
@@ -15,6 +17,15 @@ use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 use tracker::{AllocationPlacement, AllocationsTracker};
 
+/// Snapshot of the allocator state, all sizes are in blocks of `2^log_chunk_size` bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AllocatorStats {
+    pub total_blocks: usize,
+    pub used_blocks: usize,
+    pub largest_free_run_blocks: usize,
+    pub free_fragments: usize,
+}
+
 pub trait StaticAllocationBackend: Sized {
     fn as_non_null(&mut self) -> NonNull<u8>;
     fn len(&self) -> usize;
@@ -25,6 +36,8 @@ pub struct InnerStaticAllocator<B: StaticAllocationBackend> {
     _backends: Vec<B>,
     tracker: AllocationsTracker,
     log_chunk_size: u32,
+    #[cfg(feature = "log_gpu_mem_usage")]
+    labels: HashMap<NonNull<u8>, (&'static str, usize)>,
 }
 
 impl<B: StaticAllocationBackend> InnerStaticAllocator<B> {
@@ -45,6 +58,8 @@ impl<B: StaticAllocationBackend> InnerStaticAllocator<B> {
             _backends: backends,
             tracker,
             log_chunk_size,
+            #[cfg(feature = "log_gpu_mem_usage")]
+            labels: HashMap::new(),
         }
     }
 
@@ -72,6 +87,8 @@ impl<B: StaticAllocationBackend> InnerStaticAllocator<B> {
         let ptr = data.ptr.cast::<u8>();
         let len = data.alloc_len;
         assert_eq!(len & ((1 << lcs) - 1), 0);
+        #[cfg(feature = "log_gpu_mem_usage")]
+        self.labels.remove(&ptr);
         self.tracker.free(ptr, len);
     }
 }
@@ -138,15 +155,13 @@ impl<B: StaticAllocationBackend> InnerStaticAllocatorWrapper<B>
 
 pub struct StaticAllocator<B: StaticAllocationBackend, W: InnerStaticAllocatorWrapper<B>> {
     inner: W,
-    log_chunk_size: u32,
     _phantom: PhantomData<B>,
 }
 
 impl<B: StaticAllocationBackend, W: InnerStaticAllocatorWrapper<B>> StaticAllocator<B, W> {
-    fn from_inner(inner: W, log_chunk_size: u32) -> Self {
+    fn from_inner(inner: W) -> Self {
         Self {
             inner,
-            log_chunk_size,
             _phantom: Default::default(),
         }
     }
@@ -154,7 +169,7 @@ impl<B: StaticAllocationBackend, W: InnerStaticAllocatorWrapper<B>> StaticAlloca
     pub fn new(backends: impl IntoIterator<Item = B>, log_chunk_size: u32) -> Self {
         let allocator = InnerStaticAllocator::new(backends, log_chunk_size);
         let inner = W::new(allocator);
-        Self::from_inner(inner, log_chunk_size)
+        Self::from_inner(inner)
     }
 
     pub fn alloc<T>(
@@ -180,7 +195,7 @@ impl<B: StaticAllocationBackend, W: InnerStaticAllocatorWrapper<B>> StaticAlloca
     }
 
     pub fn log_chunk_size(&self) -> u32 {
-        self.log_chunk_size
+        self.inner.execute(|inner| inner.log_chunk_size)
     }
 
     pub fn get_used_mem_current(&self) -> usize {
@@ -197,12 +212,82 @@ impl<B: StaticAllocationBackend, W: InnerStaticAllocatorWrapper<B>> StaticAlloca
         self.inner
             .execute(|inner| inner.tracker.reset_used_mem_peak())
     }
+
+    pub fn get_stats(&self) -> AllocatorStats {
+        self.inner.execute(|inner| {
+            let lcs = inner.log_chunk_size;
+            let tracker = &inner.tracker;
+            AllocatorStats {
+                total_blocks: tracker.get_total_mem() >> lcs,
+                used_blocks: tracker.get_used_mem_current() >> lcs,
+                largest_free_run_blocks: tracker.get_largest_free_len() >> lcs,
+                free_fragments: tracker.get_free_regions_count(),
+            }
+        })
+    }
+
+    #[cfg(feature = "log_gpu_mem_usage")]
+    pub(crate) fn set_label<T>(&self, allocation: &StaticAllocation<T, B, W>, label: &'static str) {
+        let ptr = allocation.data.ptr.cast::<u8>();
+        let len = allocation.data.alloc_len;
+        self.inner
+            .execute(|inner| inner.labels.insert(ptr, (label, len)));
+    }
+
+    #[cfg(feature = "log_gpu_mem_usage")]
+    pub(crate) fn get_used_mem_by_label(&self) -> HashMap<&'static str, usize> {
+        self.inner.execute(|inner| {
+            let mut result = HashMap::new();
+            for (label, len) in inner.labels.values() {
+                *result.entry(*label).or_default() += len;
+            }
+            result
+        })
+    }
 }
 
 impl<B: StaticAllocationBackend, W: InnerStaticAllocatorWrapper<B>> Clone
     for StaticAllocator<B, W>
 {
     fn clone(&self) -> Self {
-        Self::from_inner(self.inner.clone(), self.log_chunk_size)
+        Self::from_inner(self.inner.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::device::{ConcurrentStaticDeviceAllocator, StaticDeviceAllocationBackend};
+    use super::*;
+
+    const LOG_CHUNK_SIZE: u32 = 20;
+    const CHUNKS_COUNT: usize = 4;
+
+    fn create_allocator() -> CudaResult<ConcurrentStaticDeviceAllocator> {
+        let allocation =
+            era_cudart::memory::DeviceAllocation::<u8>::alloc(CHUNKS_COUNT << LOG_CHUNK_SIZE)?;
+        let backend = StaticDeviceAllocationBackend::DeviceAllocation(allocation);
+        Ok(ConcurrentStaticDeviceAllocator::new(
+            [backend],
+            LOG_CHUNK_SIZE,
+        ))
+    }
+
+    #[test]
+    fn test_stats_after_free() -> CudaResult<()> {
+        let allocator = create_allocator()?;
+        let chunk_size = 1 << LOG_CHUNK_SIZE;
+        let first = allocator.alloc::<u8>(chunk_size * 2, AllocationPlacement::Bottom)?;
+        let _second = allocator.alloc::<u8>(chunk_size * 2, AllocationPlacement::Bottom)?;
+        let stats = allocator.get_stats();
+        assert_eq!(stats.total_blocks, CHUNKS_COUNT);
+        assert_eq!(stats.used_blocks, CHUNKS_COUNT);
+        assert_eq!(stats.largest_free_run_blocks, 0);
+        assert_eq!(stats.free_fragments, 0);
+        drop(first);
+        let stats = allocator.get_stats();
+        assert_eq!(stats.used_blocks, 2);
+        assert_eq!(stats.largest_free_run_blocks, 2);
+        assert_eq!(stats.free_fragments, 1);
+        Ok(())
     }
 }
@@ -206,6 +206,20 @@ impl AllocationsTracker {
     pub fn reset_used_mem_peak(&mut self) {
         self.used_mem_peak = self.used_mem_current;
     }
+
+    pub fn get_total_mem(&self) -> usize {
+        self.lens.iter().sum()
+    }
+
+    pub fn get_largest_free_len(&self) -> usize {
+        self.free_ptrs_by_len
+            .last_key_value()
+            .map_or(0, |(&len, _)| len)
+    }
+
+    pub fn get_free_regions_count(&self) -> usize {
+        self.free_len_by_ptr.len()
+    }
 }
 
 unsafe impl Send for AllocationsTracker {}
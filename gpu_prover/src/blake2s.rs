@@ -278,16 +278,26 @@ pub fn merkle_tree_cap(
     &values[offset..offset + (1 << log_tree_cap_size)]
 }
 
-cuda_kernel!(Blake2SPow, ab_blake2s_pow_kernel(seed: *const u32, bits_count: u32, max_nonce: u64, result: *mut u64));
+cuda_kernel!(Blake2SPow, ab_blake2s_pow_kernel(seed: *const u32, bits_count: u32, start_nonce: u64, max_nonce: u64, result: *mut u64));
 
+/// Estimates how many hashes were tried to find `nonce`. The kernel finds the smallest nonce in the range,
+/// so the distance from the start of the range is a good proxy for the amount of work done.
+pub fn blake2s_pow_searched_count_estimate(start_nonce: u64, nonce: u64) -> u64 {
+    assert!(nonce >= start_nonce);
+    nonce - start_nonce + 1
+}
+
+/// Finds the smallest nonce in `start_nonce..max_nonce`, result is set to `u64::MAX` if none is found.
 pub fn blake2s_pow(
     seed: &DeviceSlice<u32>,
     bits_count: u32,
+    start_nonce: u64,
     max_nonce: u64,
     result: &mut DeviceVariable<u64>,
     stream: &CudaStream,
 ) -> CudaResult<()> {
     assert_eq!(seed.len(), STATE_SIZE);
+    assert!(start_nonce < max_nonce);
     unsafe {
         memory_set_async(result.transmute_mut(), 0xff, stream)?;
     }
@@ -303,6 +313,7 @@ pub fn blake2s_pow(
     let args = Blake2SPowArguments {
         seed,
         bits_count,
+        start_nonce,
         max_nonce,
         result,
     };
@@ -595,16 +606,30 @@ mod tests {
         assert_eq!(cap_host, values_host[N..3 * N / 2]);
     }
 
-    #[test]
-    fn pow() {
-        const BITS_COUNT: u32 = 24;
-        let h_seed = [42u32; STATE_SIZE];
+    fn pow_in_range(bits_count: u32, start_nonce: u64, max_nonce: u64) -> u64 {
+        pow_with_seed([42u32; STATE_SIZE], bits_count, start_nonce, max_nonce)
+    }
+
+    fn pow_with_seed(
+        h_seed: [u32; STATE_SIZE],
+        bits_count: u32,
+        start_nonce: u64,
+        max_nonce: u64,
+    ) -> u64 {
         let mut h_result = [0u64; 1];
         let mut d_seed = DeviceAllocation::alloc(STATE_SIZE).unwrap();
         let mut d_result = DeviceAllocation::alloc(1).unwrap();
         let stream = CudaStream::default();
         memory_copy_async(&mut d_seed, &h_seed, &stream).unwrap();
-        blake2s_pow(&d_seed, BITS_COUNT, u64::MAX, &mut d_result[0], &stream).unwrap();
+        blake2s_pow(
+            &d_seed,
+            bits_count,
+            start_nonce,
+            max_nonce,
+            &mut d_result[0],
+            &stream,
+        )
+        .unwrap();
         memory_copy_async(&mut h_result, &d_result, &stream).unwrap();
         stream.synchronize().unwrap();
         let mut state = Blake2sState::new();
@@ -614,6 +639,35 @@ mod tests {
         block[STATE_SIZE + 1] = (h_result[0] >> 32) as u32;
         let mut digest = Digest::default();
         state.absorb_final_block::<true>(&block, STATE_SIZE + 2, &mut digest);
-        assert!(digest[0].leading_zeros() >= BITS_COUNT);
+        assert!(digest[0].leading_zeros() >= bits_count);
+        h_result[0]
+    }
+
+    #[test]
+    fn pow() {
+        pow_in_range(24, 0, u64::MAX);
+    }
+
+    #[test]
+    fn pow_searched_count_estimate() {
+        const SEEDS_COUNT: u32 = 8;
+        let average_estimate = |bits_count| {
+            (0..SEEDS_COUNT)
+                .map(|seed| {
+                    let nonce = pow_with_seed([seed; STATE_SIZE], bits_count, 0, u64::MAX);
+                    blake2s_pow_searched_count_estimate(0, nonce)
+                })
+                .sum::<u64>()
+                / SEEDS_COUNT as u64
+        };
+        assert!(average_estimate(24) > average_estimate(8));
+    }
+
+    #[test]
+    fn pow_restricted_range() {
+        const START_NONCE: u64 = 1 << 40;
+        const MAX_NONCE: u64 = START_NONCE + (1 << 28);
+        let nonce = pow_in_range(16, START_NONCE, MAX_NONCE);
+        assert!((START_NONCE..MAX_NONCE).contains(&nonce));
     }
 }
@@ -8,6 +8,27 @@ use setups::{
     risc_v_cycles,
 };
 
+/// Column counts of the compiled circuit layouts. All fields are meaningful for both kinds of
+/// circuits, but the memory trace of main circuits holds shuffle RAM accesses and lazy init and
+/// teardown columns, while the memory trace of delegation circuits holds register and indirect
+/// accesses, so the widths are not comparable between the two kinds.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct LayoutWidths {
+    pub witness: usize,
+    pub memory: usize,
+    pub setup: usize,
+}
+
+macro_rules! layout_widths {
+    ($circuit:ident) => {
+        LayoutWidths {
+            witness: $circuit::WITNESS_LAYOUT_WIDTH,
+            memory: $circuit::MEMORY_LAYOUT_WIDTH,
+            setup: $circuit::SETUP_LAYOUT_WIDTH,
+        }
+    };
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub enum CircuitType {
     Main(MainCircuitType),
@@ -36,6 +57,13 @@ impl CircuitType {
         }
     }
 
+    pub fn get_num_cycles(&self) -> usize {
+        match self {
+            CircuitType::Main(main_type) => main_type.get_num_cycles(),
+            CircuitType::Delegation(delegation_type) => delegation_type.get_num_delegation_cycles(),
+        }
+    }
+
     pub fn get_domain_size(&self) -> usize {
         match self {
             CircuitType::Main(main_type) => main_type.get_domain_size(),
@@ -57,12 +85,72 @@ impl CircuitType {
         }
     }
 
+    /// Number of LDE cosets that have to be materialized
+    pub fn num_lde_source_cosets(&self) -> usize {
+        self.get_lde_source_cosets().len()
+    }
+
+    /// Panics if the LDE factor is not a power of two or is too small to hold the source cosets
+    pub fn assert_lde_consistency(&self) {
+        assert_lde_parameters(self, self.get_lde_factor(), self.num_lde_source_cosets());
+    }
+
     pub fn get_tree_cap_size(&self) -> usize {
         match self {
             CircuitType::Main(main_type) => main_type.get_tree_cap_size(),
             CircuitType::Delegation(delegation_type) => delegation_type.get_tree_cap_size(),
         }
     }
+
+    pub fn layout_widths(&self) -> LayoutWidths {
+        match self {
+            CircuitType::Main(main_type) => main_type.layout_widths(),
+            CircuitType::Delegation(delegation_type) => delegation_type.layout_widths(),
+        }
+    }
+
+    /// Delegation circuits are the delegated work themselves, so they never request the challenge
+    /// and use the one drawn for the main circuit
+    pub fn needs_delegation_challenge(&self) -> bool {
+        match self {
+            CircuitType::Main(main_type) => main_type.needs_delegation_challenge(),
+            CircuitType::Delegation(_) => false,
+        }
+    }
+
+    /// All main circuit types followed by all delegation circuit types
+    pub fn all() -> impl Iterator<Item = CircuitType> {
+        let main = MainCircuitType::all().into_iter().map(CircuitType::Main);
+        let delegation = DelegationCircuitType::all()
+            .iter()
+            .copied()
+            .map(CircuitType::Delegation);
+        main.chain(delegation)
+    }
+}
+
+impl From<MainCircuitType> for CircuitType {
+    #[inline(always)]
+    fn from(circuit_type: MainCircuitType) -> Self {
+        CircuitType::Main(circuit_type)
+    }
+}
+
+impl From<DelegationCircuitType> for CircuitType {
+    #[inline(always)]
+    fn from(circuit_type: DelegationCircuitType) -> Self {
+        CircuitType::Delegation(circuit_type)
+    }
+}
+
+/// Stable names like `main:risc_v_cycles`, also used as keys for metrics and artifact file names
+impl std::fmt::Display for CircuitType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CircuitType::Main(circuit_type) => write!(f, "main:{circuit_type}"),
+            CircuitType::Delegation(circuit_type) => write!(f, "delegation:{circuit_type}"),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
@@ -75,6 +163,26 @@ pub enum MainCircuitType {
 }
 
 impl MainCircuitType {
+    pub const fn all() -> [Self; 5] {
+        // the match is exhaustive, so a new variant does not compile until it is listed below
+        const fn _is_listed(circuit_type: MainCircuitType) {
+            match circuit_type {
+                MainCircuitType::FinalReducedRiscVMachine
+                | MainCircuitType::MachineWithoutSignedMulDiv
+                | MainCircuitType::ReducedRiscVLog23Machine
+                | MainCircuitType::ReducedRiscVMachine
+                | MainCircuitType::RiscVCycles => {}
+            }
+        }
+        [
+            MainCircuitType::FinalReducedRiscVMachine,
+            MainCircuitType::MachineWithoutSignedMulDiv,
+            MainCircuitType::ReducedRiscVLog23Machine,
+            MainCircuitType::ReducedRiscVMachine,
+            MainCircuitType::RiscVCycles,
+        ]
+    }
+
     pub fn get_num_cycles(&self) -> usize {
         match self {
             MainCircuitType::FinalReducedRiscVMachine => final_reduced_risc_v_machine::NUM_CYCLES,
@@ -127,6 +235,22 @@ impl MainCircuitType {
         }
     }
 
+    pub fn layout_widths(&self) -> LayoutWidths {
+        match self {
+            MainCircuitType::FinalReducedRiscVMachine => {
+                layout_widths!(final_reduced_risc_v_machine)
+            }
+            MainCircuitType::MachineWithoutSignedMulDiv => {
+                layout_widths!(machine_without_signed_mul_div)
+            }
+            MainCircuitType::ReducedRiscVLog23Machine => {
+                layout_widths!(reduced_risc_v_log_23_machine)
+            }
+            MainCircuitType::ReducedRiscVMachine => layout_widths!(reduced_risc_v_machine),
+            MainCircuitType::RiscVCycles => layout_widths!(risc_v_cycles),
+        }
+    }
+
     pub fn get_tree_cap_size(&self) -> usize {
         match self {
             MainCircuitType::FinalReducedRiscVMachine => {
@@ -163,6 +287,12 @@ impl MainCircuitType {
         .map(|id| DelegationCircuitType::from(*id as u16))
     }
 
+    /// Whether this machine can request any delegation at all, for machines that can not
+    /// there is nothing to trace or count for delegation circuits
+    pub fn allows_delegations(&self) -> bool {
+        self.get_allowed_delegation_circuit_types().next().is_some()
+    }
+
     pub fn needs_delegation_challenge(&self) -> bool {
         match self {
             MainCircuitType::FinalReducedRiscVMachine => false,
@@ -174,6 +304,19 @@ impl MainCircuitType {
     }
 }
 
+impl std::fmt::Display for MainCircuitType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            MainCircuitType::FinalReducedRiscVMachine => "final_reduced_risc_v_machine",
+            MainCircuitType::MachineWithoutSignedMulDiv => "machine_without_signed_mul_div",
+            MainCircuitType::ReducedRiscVLog23Machine => "reduced_risc_v_log_23_machine",
+            MainCircuitType::ReducedRiscVMachine => "reduced_risc_v_machine",
+            MainCircuitType::RiscVCycles => "risc_v_cycles",
+        };
+        f.write_str(name)
+    }
+}
+
 #[repr(u32)]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
 pub enum DelegationCircuitType {
@@ -182,6 +325,54 @@ pub enum DelegationCircuitType {
 }
 
 impl DelegationCircuitType {
+    const VARIANTS: [Self; 2] = {
+        // the match is exhaustive, so a new variant does not compile until it is listed below
+        const fn _is_listed(circuit_type: DelegationCircuitType) {
+            match circuit_type {
+                DelegationCircuitType::BigIntWithControl
+                | DelegationCircuitType::Blake2WithCompression => {}
+            }
+        }
+        [Self::BigIntWithControl, Self::Blake2WithCompression]
+    };
+
+    const VALID_DELEGATION_TYPE_IDS: [u16; Self::VARIANTS.len()] = {
+        let mut ids = [0u16; Self::VARIANTS.len()];
+        let mut i = 0;
+        while i < ids.len() {
+            ids[i] = Self::VARIANTS[i] as u16;
+            i += 1;
+        }
+        // insertion sort, as we can not use slice::sort in const context
+        let mut i = 1;
+        while i < ids.len() {
+            let mut j = i;
+            while j > 0 && ids[j - 1] > ids[j] {
+                let tmp = ids[j - 1];
+                ids[j - 1] = ids[j];
+                ids[j] = tmp;
+                j -= 1;
+            }
+            i += 1;
+        }
+        ids
+    };
+
+    pub fn all() -> &'static [Self] {
+        &Self::VARIANTS
+    }
+
+    /// Sorted list of all known delegation type ids, suitable for binary search
+    pub fn valid_delegation_type_ids() -> &'static [u16] {
+        &Self::VALID_DELEGATION_TYPE_IDS
+    }
+
+    pub fn is_valid_delegation_type_id(delegation_type: u16) -> bool {
+        Self::valid_delegation_type_ids()
+            .binary_search(&delegation_type)
+            .is_ok()
+    }
+
     pub fn get_delegation_type_id(&self) -> u16 {
         *self as u16
     }
@@ -225,6 +416,15 @@ impl DelegationCircuitType {
         }
     }
 
+    pub fn layout_widths(&self) -> LayoutWidths {
+        match self {
+            DelegationCircuitType::BigIntWithControl => layout_widths!(bigint_with_control),
+            DelegationCircuitType::Blake2WithCompression => {
+                layout_widths!(blake2_with_compression)
+            }
+        }
+    }
+
     pub fn get_witness_factory_fn<A: GoodAllocator>(&self) -> fn(A) -> DelegationWitness<A> {
         match self {
             DelegationCircuitType::BigIntWithControl => |allocator| {
@@ -245,6 +445,16 @@ impl DelegationCircuitType {
     }
 }
 
+impl std::fmt::Display for DelegationCircuitType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            DelegationCircuitType::BigIntWithControl => "bigint_with_control",
+            DelegationCircuitType::Blake2WithCompression => "blake2_with_compression",
+        };
+        f.write_str(name)
+    }
+}
+
 impl From<u16> for DelegationCircuitType {
     #[inline(always)]
     fn from(delegation_type: u16) -> Self {
@@ -257,3 +467,188 @@ impl From<u16> for DelegationCircuitType {
         }
     }
 }
+
+fn assert_lde_parameters(
+    circuit_type: &CircuitType,
+    lde_factor: usize,
+    num_lde_source_cosets: usize,
+) {
+    assert!(
+        lde_factor.is_power_of_two(),
+        "{circuit_type} LDE factor {lde_factor} is not a power of two"
+    );
+    assert!(
+        lde_factor >= num_lde_source_cosets,
+        "{circuit_type} LDE factor {lde_factor} is smaller than the number of source cosets {num_lde_source_cosets}"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cs::one_row_compiler::CompiledCircuitArtifact;
+    use execution_utils::get_padded_binary;
+    use field::Mersenne31Field;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_get_num_cycles() {
+        assert_eq!(
+            CircuitType::Main(MainCircuitType::RiscVCycles).get_num_cycles(),
+            risc_v_cycles::NUM_CYCLES
+        );
+        assert_eq!(
+            CircuitType::Delegation(DelegationCircuitType::Blake2WithCompression).get_num_cycles(),
+            blake2_with_compression::NUM_DELEGATION_CYCLES
+        );
+    }
+
+    fn compile_circuit(circuit_type: CircuitType) -> CompiledCircuitArtifact<Mersenne31Field> {
+        let binary = get_padded_binary(&[]);
+        macro_rules! get_machine {
+            ($circuit:ident) => {
+                $circuit::get_machine(&binary, $circuit::ALLOWED_DELEGATION_CSRS)
+            };
+        }
+        match circuit_type {
+            CircuitType::Main(main_type) => match main_type {
+                MainCircuitType::FinalReducedRiscVMachine => {
+                    get_machine!(final_reduced_risc_v_machine)
+                }
+                MainCircuitType::MachineWithoutSignedMulDiv => {
+                    get_machine!(machine_without_signed_mul_div)
+                }
+                MainCircuitType::ReducedRiscVLog23Machine => {
+                    get_machine!(reduced_risc_v_log_23_machine)
+                }
+                MainCircuitType::ReducedRiscVMachine => get_machine!(reduced_risc_v_machine),
+                MainCircuitType::RiscVCycles => get_machine!(risc_v_cycles),
+            },
+            CircuitType::Delegation(delegation_type) => match delegation_type {
+                DelegationCircuitType::BigIntWithControl => {
+                    bigint_with_control::get_delegation_circuit().compiled_circuit
+                }
+                DelegationCircuitType::Blake2WithCompression => {
+                    blake2_with_compression::get_delegation_circuit().compiled_circuit
+                }
+            },
+        }
+    }
+
+    #[test]
+    fn test_layout_widths() {
+        for circuit_type in CircuitType::all() {
+            let circuit = compile_circuit(circuit_type);
+            let widths = circuit_type.layout_widths();
+            assert_eq!(
+                widths.witness, circuit.witness_layout.total_width,
+                "{circuit_type} witness width"
+            );
+            assert_eq!(
+                widths.memory, circuit.memory_layout.total_width,
+                "{circuit_type} memory width"
+            );
+            assert_eq!(
+                widths.setup, circuit.setup_layout.total_width,
+                "{circuit_type} setup width"
+            );
+        }
+    }
+
+    #[test]
+    fn test_all() {
+        let main = MainCircuitType::all();
+        assert_eq!(main.len(), 5);
+        assert_eq!(main.iter().collect::<HashSet<_>>().len(), main.len());
+        let delegation = DelegationCircuitType::all();
+        assert_eq!(delegation.len(), 2);
+        assert_eq!(
+            delegation.iter().collect::<HashSet<_>>().len(),
+            delegation.len()
+        );
+        let all = CircuitType::all().collect::<HashSet<_>>();
+        assert_eq!(all.len(), main.len() + delegation.len());
+    }
+
+    #[test]
+    fn test_allows_delegations() {
+        for circuit_type in MainCircuitType::all() {
+            assert_eq!(
+                circuit_type.allows_delegations(),
+                circuit_type.needs_delegation_challenge()
+            );
+        }
+        let circuit_type = MainCircuitType::FinalReducedRiscVMachine;
+        assert!(!circuit_type.allows_delegations());
+        assert_eq!(
+            circuit_type.get_allowed_delegation_circuit_types().count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_needs_delegation_challenge() {
+        assert!(CircuitType::Main(MainCircuitType::RiscVCycles).needs_delegation_challenge());
+        assert!(
+            !CircuitType::Main(MainCircuitType::FinalReducedRiscVMachine)
+                .needs_delegation_challenge()
+        );
+        for circuit_type in MainCircuitType::all() {
+            assert_eq!(
+                CircuitType::Main(circuit_type).needs_delegation_challenge(),
+                circuit_type.needs_delegation_challenge()
+            );
+        }
+        for circuit_type in DelegationCircuitType::all() {
+            assert!(!CircuitType::Delegation(*circuit_type).needs_delegation_challenge());
+        }
+    }
+
+    #[test]
+    fn test_lde_consistency() {
+        for circuit_type in CircuitType::all() {
+            circuit_type.assert_lde_consistency();
+            // all circuits are proven with LDE factor 2 and both cosets materialized
+            assert_eq!(circuit_type.num_lde_source_cosets(), 2, "{circuit_type}");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "LDE factor 3 is not a power of two")]
+    fn test_lde_factor_not_power_of_two() {
+        assert_lde_parameters(&CircuitType::Main(MainCircuitType::RiscVCycles), 3, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "LDE factor 1 is smaller than the number of source cosets 2")]
+    fn test_lde_factor_smaller_than_source_cosets() {
+        assert_lde_parameters(&CircuitType::Main(MainCircuitType::RiscVCycles), 1, 2);
+    }
+
+    #[test]
+    fn test_display() {
+        let names = CircuitType::all()
+            .map(|circuit_type| circuit_type.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "main:final_reduced_risc_v_machine",
+                "main:machine_without_signed_mul_div",
+                "main:reduced_risc_v_log_23_machine",
+                "main:reduced_risc_v_machine",
+                "main:risc_v_cycles",
+                "delegation:bigint_with_control",
+                "delegation:blake2_with_compression",
+            ]
+        );
+        assert_eq!(
+            CircuitType::from(MainCircuitType::RiscVCycles),
+            CircuitType::Main(MainCircuitType::RiscVCycles)
+        );
+        assert_eq!(
+            CircuitType::from(DelegationCircuitType::Blake2WithCompression),
+            CircuitType::Delegation(DelegationCircuitType::Blake2WithCompression)
+        );
+    }
+}
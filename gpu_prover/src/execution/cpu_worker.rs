@@ -3,7 +3,7 @@ use super::tracer::{
     create_setup_and_teardown_chunker, BoxedMemoryImplWithRom, CycleTracingData, DelegationCounter,
     DelegationTracingData, DelegationTracingType, ExecutionTracer, RamTracingData,
 };
-use crate::circuit_type::{CircuitType, MainCircuitType};
+use crate::circuit_type::{CircuitType, DelegationCircuitType, MainCircuitType};
 use crossbeam_channel::{Receiver, Sender};
 use crossbeam_utils::sync::WaitGroup;
 use cs::definitions::timestamp_from_chunk_cycle_and_sequence;
@@ -14,12 +14,14 @@ use prover::risc_v_simulator::abstractions::non_determinism::NonDeterminismCSRSo
 use prover::risc_v_simulator::cycle::state_new::RiscV32StateForUnrolledProver;
 use prover::risc_v_simulator::cycle::MachineConfig;
 use prover::risc_v_simulator::delegations::DelegationsCSRProcessor;
+use prover::tracers::delegation::DelegationWitness;
 use prover::ShuffleRamSetupAndTeardown;
 use std::alloc::Global;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, HashSet};
-use std::ops::Deref;
-use std::time::Instant;
+use std::ops::{Deref, Range};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use trace_and_split::{setups, FinalRegisterValue, ENTRY_POINT};
 
 pub trait NonDeterminism:
@@ -39,7 +41,17 @@ const ROM_ADDRESS_SPACE_SECOND_WORD_BITS: usize = {
 };
 
 const LOG_ROM_SIZE: u32 = 16 + ROM_ADDRESS_SPACE_SECOND_WORD_BITS as u32;
-const RAM_SIZE: usize = 1 << 30;
+const LOG_RAM_SIZE: u32 = 30;
+const RAM_SIZE: usize = 1 << LOG_RAM_SIZE;
+
+pub(crate) fn get_ram_size(max_ram_log_size: Option<u32>) -> usize {
+    let log_ram_size = max_ram_log_size.unwrap_or(LOG_RAM_SIZE);
+    assert!(
+        log_ram_size >= LOG_ROM_SIZE && log_ram_size <= LOG_RAM_SIZE,
+        "RAM log size must be in range [{LOG_ROM_SIZE}, {LOG_RAM_SIZE}], got {log_ram_size}"
+    );
+    1 << log_ram_size
+}
 
 pub struct SetupAndTeardownChunk<A: GoodAllocator> {
     pub index: usize,
@@ -51,43 +63,227 @@ pub struct CyclesChunk<A: GoodAllocator> {
     pub data: CycleTracingData<A>,
 }
 
+#[derive(Clone, Copy, Debug)]
+pub struct ProgressEvent {
+    pub worker_id: usize,
+    pub chunks_traced_count: usize,
+    pub mhz: f64,
+    pub finished: bool,
+}
+
+/// Callback that CPU workers invoke after every simulated chunk. It is called synchronously
+/// from the simulation loop, so it must be cheap and must not block.
+pub type ProgressCallback = Arc<dyn Fn(ProgressEvent) + Send + Sync>;
+
+fn report_progress(
+    progress_callback: &Option<ProgressCallback>,
+    worker_id: usize,
+    chunks_traced_count: usize,
+    mhz: f64,
+    finished: bool,
+) {
+    if let Some(callback) = progress_callback {
+        callback(ProgressEvent {
+            worker_id,
+            chunks_traced_count,
+            mhz,
+            finished,
+        });
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WorkerError {
+    /// Execution did not finish within the upper bound of main circuit chunks.
+    ExecutionNotFinished { chunks_traced: usize, last_pc: u32 },
+    /// No free allocator was received within the timeout, or the allocators channel was closed.
+    AllocatorStarved {
+        worker_id: usize,
+        chunk_index: usize,
+    },
+}
+
+impl std::fmt::Display for WorkerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorkerError::ExecutionNotFinished {
+                chunks_traced,
+                last_pc,
+            } => write!(
+                f,
+                "end of execution was not reached after {chunks_traced} chunks, last PC is 0x{last_pc:08x}"
+            ),
+            WorkerError::AllocatorStarved {
+                worker_id,
+                chunk_index,
+            } => write!(
+                f,
+                "worker {worker_id} did not receive a free allocator for chunk {chunk_index}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WorkerError {}
+
+/// Default time a worker waits for a free allocator, healthy runs never come close to it.
+pub const DEFAULT_FREE_ALLOCATOR_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Default fill ratio of setup and teardown chunks, chunks are assumed to be filled completely.
+pub const DEFAULT_SETUP_TEARDOWN_FILL_RATIO: f64 = 1.0;
+
+fn recv_free_allocator<A: GoodAllocator>(
+    free_allocator: &Receiver<A>,
+    timeout: Duration,
+    worker_id: usize,
+    chunk_index: usize,
+) -> Result<A, WorkerError> {
+    free_allocator
+        .recv_timeout(timeout)
+        .map_err(|_| WorkerError::AllocatorStarved {
+            worker_id,
+            chunk_index,
+        })
+}
+
+/// Defines which main circuit chunks are traced by which of the cycles tracing workers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplitStrategy {
+    /// Worker traces chunks with `chunk_index % split_count == split_index`,
+    /// so every worker simulates the whole execution.
+    RoundRobin,
+    /// Worker traces a contiguous range of chunks, the actual number of chunks is split evenly
+    /// between workers, so the program is simulated once more before the workers start to count
+    /// the chunks. Worker only simulates up to the end of its range, starting from the checkpoint
+    /// at the start of its range produced by `send_simulation_checkpoints`.
+    Contiguous,
+}
+
 #[derive(Clone)]
 pub enum CpuWorkerMode<A: GoodAllocator> {
     TraceTouchedRam {
         circuit_type: MainCircuitType,
         skip_set: HashSet<(CircuitType, usize)>,
         free_allocator: Receiver<A>,
+        free_allocator_timeout: Duration,
+        /// Fraction of a chunk that touched RAM cells are expected to fill when deciding if
+        /// a chunk needs setup and teardown data, must be in `(0.0, 1.0]`. Values below 1.0
+        /// are more conservative and declare fewer chunks as not needing setup and teardown,
+        /// the extra setup and teardown chunks are filled with padding.
+        setup_teardown_fill_ratio: f64,
+        /// If false, final register values are not produced and are sent as `None`
+        want_final_registers: bool,
+        /// If true, `WorkerResult::TouchedRamStats` is sent after every traced chunk
+        collect_stats: bool,
     },
     TraceCycles {
         circuit_type: MainCircuitType,
         skip_set: HashSet<(CircuitType, usize)>,
+        /// Number of chunks split between the workers, the contiguous split needs the actual
+        /// number of chunks of the program, the round-robin split takes the upper bound
+        split_chunks_count: usize,
         split_count: usize,
         split_index: usize,
+        split_strategy: SplitStrategy,
         free_allocator: Receiver<A>,
+        free_allocator_timeout: Duration,
     },
     TraceDelegations {
         circuit_type: MainCircuitType,
         skip_set: HashSet<(CircuitType, usize)>,
         free_allocator: Receiver<A>,
+        free_allocator_timeout: Duration,
     },
+    CountDelegations {
+        circuit_type: MainCircuitType,
+    },
+}
+
+impl<A: GoodAllocator> CpuWorkerMode<A> {
+    // completed chunks are skipped the same way as chunks from the skip set
+    fn resume_from(&mut self, resume_state: BatchResumeState) {
+        match self {
+            CpuWorkerMode::TraceTouchedRam { skip_set, .. }
+            | CpuWorkerMode::TraceCycles { skip_set, .. }
+            | CpuWorkerMode::TraceDelegations { skip_set, .. } => {
+                skip_set.extend(resume_state.completed)
+            }
+            CpuWorkerMode::CountDelegations { .. } => {}
+        }
+    }
 }
 
-pub fn get_cpu_worker_func<C: MachineConfig, A: GoodAllocator + 'static>(
+/// Chunks of a batch that are already done, for example by an earlier run that failed midway.
+/// Workers still simulate these chunks to advance the machine state, so chunk counts and final
+/// register values are the same as in a full run, but no results are sent for them.
+#[derive(Clone, Debug, Default)]
+pub struct BatchResumeState {
+    completed: HashSet<(CircuitType, usize)>,
+}
+
+impl BatchResumeState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mark_completed(&mut self, circuit_type: CircuitType, circuit_sequence: usize) {
+        self.completed.insert((circuit_type, circuit_sequence));
+    }
+
+    pub fn is_completed(&self, circuit_type: CircuitType, circuit_sequence: usize) -> bool {
+        self.completed.contains(&(circuit_type, circuit_sequence))
+    }
+
+    pub fn completed_count(&self) -> usize {
+        self.completed.len()
+    }
+
+    /// Sequences of the completed chunks of the circuit, in no particular order.
+    pub fn completed_sequences(
+        &self,
+        circuit_type: CircuitType,
+    ) -> impl Iterator<Item = usize> + '_ {
+        self.completed
+            .iter()
+            .filter(move |(t, _)| *t == circuit_type)
+            .map(|(_, sequence)| *sequence)
+    }
+}
+
+pub fn get_cpu_worker_func<
+    C: MachineConfig,
+    N: NonDeterminism + Send + 'static,
+    A: GoodAllocator + 'static,
+>(
     wait_group: WaitGroup,
     batch_id: u64,
     worker_id: usize,
     num_main_chunks_upper_bound: usize,
     binary: impl Deref<Target = impl Deref<Target = [u32]>> + Send + 'static,
-    non_determinism: impl Deref<Target = impl NonDeterminism> + Send + 'static,
-    mode: CpuWorkerMode<A>,
+    non_determinism: impl Deref<Target = N> + Send + 'static,
+    initial_registers: Option<[u32; 32]>,
+    max_ram_log_size: Option<u32>,
+    mut mode: CpuWorkerMode<A>,
+    resume_state: Option<BatchResumeState>,
+    checkpoint: Option<Receiver<SimulationCheckpoint<C, N>>>,
     results: Sender<WorkerResult<A>>,
-) -> impl FnOnce() + Send + 'static {
+    progress_callback: Option<ProgressCallback>,
+) -> impl FnOnce() -> Result<(), WorkerError> + Send + 'static {
     move || {
-        match mode {
+        let initial_registers = initial_registers.unwrap_or_default();
+        let ram_size = get_ram_size(max_ram_log_size);
+        if let Some(resume_state) = resume_state {
+            mode.resume_from(resume_state);
+        }
+        let result = match mode {
             CpuWorkerMode::TraceTouchedRam {
                 circuit_type,
                 skip_set,
                 free_allocator,
+                free_allocator_timeout,
+                setup_teardown_fill_ratio,
+                want_final_registers,
+                collect_stats,
             } => trace_touched_ram::<C, A>(
                 batch_id,
                 worker_id,
@@ -95,73 +291,333 @@ pub fn get_cpu_worker_func<C: MachineConfig, A: GoodAllocator + 'static>(
                 circuit_type,
                 binary,
                 non_determinism,
+                initial_registers,
+                ram_size,
                 skip_set,
                 free_allocator,
+                free_allocator_timeout,
+                setup_teardown_fill_ratio,
+                want_final_registers,
+                collect_stats,
                 results,
+                progress_callback,
             ),
             CpuWorkerMode::TraceCycles {
                 circuit_type,
                 skip_set,
+                split_chunks_count,
                 split_count,
                 split_index,
+                split_strategy,
                 free_allocator,
-            } => trace_cycles::<C, A>(
+                free_allocator_timeout,
+            } => match checkpoint.map(|r| r.recv()).transpose() {
+                Ok(checkpoint) => trace_cycles::<C, N, A>(
+                    batch_id,
+                    worker_id,
+                    num_main_chunks_upper_bound,
+                    circuit_type,
+                    binary,
+                    non_determinism,
+                    initial_registers,
+                    ram_size,
+                    checkpoint,
+                    skip_set,
+                    split_chunks_count,
+                    split_count,
+                    split_index,
+                    split_strategy,
+                    free_allocator,
+                    free_allocator_timeout,
+                    results,
+                    progress_callback,
+                ),
+                // execution ended before the range of this worker
+                Err(_) => Ok(()),
+            },
+            CpuWorkerMode::TraceDelegations {
+                circuit_type,
+                skip_set,
+                free_allocator,
+                free_allocator_timeout,
+            } => trace_delegations::<C, A>(
                 batch_id,
                 worker_id,
                 num_main_chunks_upper_bound,
                 circuit_type,
                 binary,
                 non_determinism,
+                initial_registers,
+                ram_size,
                 skip_set,
-                split_count,
-                split_index,
                 free_allocator,
+                free_allocator_timeout,
                 results,
+                progress_callback,
             ),
-            CpuWorkerMode::TraceDelegations {
-                circuit_type,
-                skip_set,
-                free_allocator,
-            } => trace_delegations::<C, A>(
+            CpuWorkerMode::CountDelegations { circuit_type } => count_delegations::<C, A>(
                 batch_id,
                 worker_id,
                 num_main_chunks_upper_bound,
                 circuit_type,
                 binary,
                 non_determinism,
-                skip_set,
-                free_allocator,
+                initial_registers,
+                ram_size,
                 results,
+                progress_callback,
             ),
         };
         drop(wait_group);
+        result
     }
 }
 
-fn trace_touched_ram<C: MachineConfig, A: GoodAllocator>(
+/// Wraps the worker function so that an error is sent to `results` as `WorkerResult::WorkerError`.
+pub fn send_error_to_results<A: GoodAllocator + 'static>(
+    func: impl FnOnce() -> Result<(), WorkerError> + Send + 'static,
+    results: Sender<WorkerResult<A>>,
+) -> impl FnOnce() + Send + 'static {
+    move || {
+        if let Err(error) = func() {
+            results.send(WorkerResult::WorkerError(error)).unwrap();
+        }
+    }
+}
+
+fn create_initial_state<C: MachineConfig>(
+    initial_registers: [u32; 32],
+) -> RiscV32StateForUnrolledProver<C> {
+    assert_eq!(initial_registers[0], 0, "register x0 must be zero");
+    let mut state = RiscV32StateForUnrolledProver::<C>::initial(ENTRY_POINT);
+    state.observable.registers = initial_registers;
+    state
+}
+
+/// Simulation state at the boundary of the main circuit chunks, so cycles tracing can be resumed
+/// from it instead of fast-forwarding the simulation from the very first cycle
+#[derive(Clone)]
+pub struct SimulationCheckpoint<C: MachineConfig, N: NonDeterminism> {
+    /// index of the first chunk that is not simulated yet
+    pub chunk_index: usize,
+    pub state: RiscV32StateForUnrolledProver<C>,
+    pub memory: BoxedMemoryImplWithRom<RAM_SIZE, LOG_ROM_SIZE>,
+    pub ram_tracing_data: RamTracingData<RAM_SIZE, false>,
+    pub non_determinism: N,
+}
+
+impl<C: MachineConfig, N: NonDeterminism> SimulationCheckpoint<C, N> {
+    fn new(
+        binary: &[u32],
+        non_determinism: &N,
+        initial_registers: [u32; 32],
+        ram_size: usize,
+    ) -> Self {
+        let mut memory = BoxedMemoryImplWithRom::<RAM_SIZE, LOG_ROM_SIZE>::new(ram_size);
+        for (idx, instruction) in binary.iter().enumerate() {
+            memory.populate(ENTRY_POINT + idx as u32 * 4, *instruction);
+        }
+        Self {
+            chunk_index: 0,
+            state: create_initial_state::<C>(initial_registers),
+            memory,
+            ram_tracing_data: RamTracingData::new(ram_size),
+            non_determinism: non_determinism.clone(),
+        }
+    }
+
+    fn fast_forward_chunk(&mut self, cycles_per_chunk: usize) -> bool {
+        let delegation_tracing_data = DelegationTracingData::default();
+        let delegation_swap_fn = |_, _| unreachable!();
+        let initial_timestamp =
+            timestamp_from_chunk_cycle_and_sequence(0, cycles_per_chunk, self.chunk_index);
+        let cycle_tracing_data = CycleTracingData::with_cycles_capacity(0);
+        let mut tracer =
+            ExecutionTracer::<RAM_SIZE, LOG_ROM_SIZE, _, Global, Global, false, false, false>::new(
+                &mut self.ram_tracing_data,
+                cycle_tracing_data,
+                delegation_tracing_data,
+                delegation_swap_fn,
+                initial_timestamp,
+            );
+        let finished = self.state.run_cycles(
+            &mut self.memory,
+            &mut tracer,
+            &mut self.non_determinism,
+            &mut DelegationsCSRProcessor,
+            cycles_per_chunk,
+        );
+        self.chunk_index += 1;
+        finished
+    }
+}
+
+/// Simulates the first `chunks_count` chunks of the main circuit without tracing and captures the state
+/// at the end of them. Returns `None` if execution finishes within those chunks.
+pub fn create_simulation_checkpoint<C: MachineConfig, N: NonDeterminism>(
+    circuit_type: MainCircuitType,
+    binary: &[u32],
+    non_determinism: &N,
+    initial_registers: Option<[u32; 32]>,
+    max_ram_log_size: Option<u32>,
+    chunks_count: usize,
+) -> Option<SimulationCheckpoint<C, N>> {
+    let domain_size = circuit_type.get_domain_size();
+    assert!(domain_size.is_power_of_two());
+    let cycles_per_chunk = domain_size - 1;
+    let mut checkpoint = SimulationCheckpoint::<C, N>::new(
+        binary,
+        non_determinism,
+        initial_registers.unwrap_or_default(),
+        get_ram_size(max_ram_log_size),
+    );
+    for _ in 0..chunks_count {
+        if checkpoint.fast_forward_chunk(cycles_per_chunk) {
+            return None;
+        }
+    }
+    Some(checkpoint)
+}
+
+/// Chunks traced by the worker with `split_index` out of `split_count` workers of the contiguous
+/// split, the last ranges are empty if there are fewer chunks than workers.
+pub(crate) fn get_contiguous_range(
+    split_chunks_count: usize,
+    split_count: usize,
+    split_index: usize,
+) -> Range<usize> {
+    let chunks_per_split = split_chunks_count.div_ceil(split_count);
+    let range_start = (split_index * chunks_per_split).min(split_chunks_count);
+    let range_end = (range_start + chunks_per_split).min(split_chunks_count);
+    range_start..range_end
+}
+
+/// Simulates the program once without tracing and sends the state at each of the requested chunks,
+/// which must be in increasing order, so the cycles tracing workers of the contiguous split start
+/// at their ranges without simulating the earlier chunks. The state is only cloned for all but the
+/// last request, senders of the chunks after the end of execution are dropped without sending.
+pub(crate) fn send_simulation_checkpoints<C: MachineConfig, N: NonDeterminism>(
+    batch_id: u64,
+    circuit_type: MainCircuitType,
+    binary: &[u32],
+    non_determinism: &N,
+    initial_registers: Option<[u32; 32]>,
+    max_ram_log_size: Option<u32>,
+    requests: Vec<(usize, Sender<SimulationCheckpoint<C, N>>)>,
+) {
+    assert!(requests.is_sorted_by_key(|(chunk_index, _)| *chunk_index));
+    let domain_size = circuit_type.get_domain_size();
+    assert!(domain_size.is_power_of_two());
+    let cycles_per_chunk = domain_size - 1;
+    let mut checkpoint = SimulationCheckpoint::<C, N>::new(
+        binary,
+        non_determinism,
+        initial_registers.unwrap_or_default(),
+        get_ram_size(max_ram_log_size),
+    );
+    let mut requests = requests.into_iter().peekable();
+    while let Some((chunk_index, sender)) = requests.next() {
+        while checkpoint.chunk_index < chunk_index {
+            if checkpoint.fast_forward_chunk(cycles_per_chunk) {
+                return;
+            }
+        }
+        trace!("BATCH[{batch_id}] sending simulation checkpoint at chunk {chunk_index}");
+        if requests.peek().is_none() {
+            sender.send(checkpoint).unwrap();
+            return;
+        }
+        sender.send(checkpoint.clone()).unwrap();
+    }
+}
+
+/// Number of circuits of each kind needed to prove a program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CircuitCounts {
+    pub main_chunks_count: usize,
+    pub setup_and_teardown_chunks_count: usize,
+    pub touched_ram_cells_count: usize,
+}
+
+/// Simulates the program tracing only the touched RAM, which is enough to know how many main circuits
+/// and how many of them with setup and teardown data are needed, without tracing cycles or delegations.
+pub fn estimate_circuit_counts<C: MachineConfig>(
+    binary: &[u32],
+    non_determinism: &impl NonDeterminism,
+    circuit_type: MainCircuitType,
+    num_main_chunks_upper_bound: usize,
+    initial_registers: Option<[u32; 32]>,
+    max_ram_log_size: Option<u32>,
+    setup_teardown_fill_ratio: f64,
+) -> Result<CircuitCounts, WorkerError> {
+    let simulation = simulate_touched_ram::<C, _>(
+        0,
+        0,
+        num_main_chunks_upper_bound,
+        circuit_type,
+        binary,
+        non_determinism,
+        initial_registers.unwrap_or_default(),
+        get_ram_size(max_ram_log_size),
+        setup_teardown_fill_ratio,
+        &None,
+        |_| {},
+        |_, _| {},
+    )?;
+    let touched_ram_cells_count =
+        simulation.ram_tracing_data.get_touched_ram_cells_count() as usize;
+    let counts = CircuitCounts {
+        main_chunks_count: simulation.chunks_traced_count,
+        setup_and_teardown_chunks_count: simulation.setup_and_teardown_chunks_count(),
+        touched_ram_cells_count,
+    };
+    Ok(counts)
+}
+
+struct TouchedRamSimulation<C: MachineConfig> {
+    state: RiscV32StateForUnrolledProver<C>,
+    memory: BoxedMemoryImplWithRom<RAM_SIZE, LOG_ROM_SIZE>,
+    ram_tracing_data: RamTracingData<RAM_SIZE, true>,
+    chunks_traced_count: usize,
+    next_chunk_index_with_no_setup_and_teardown: usize,
+}
+
+impl<C: MachineConfig> TouchedRamSimulation<C> {
+    // chunks from the first one that still needs setup and teardown data to the end of the program
+    fn setup_and_teardown_chunks_count(&self) -> usize {
+        self.chunks_traced_count - self.next_chunk_index_with_no_setup_and_teardown
+    }
+}
+
+fn simulate_touched_ram<C: MachineConfig, N: NonDeterminism>(
     batch_id: u64,
     worker_id: usize,
     num_main_chunks_upper_bound: usize,
     circuit_type: MainCircuitType,
-    binary: impl Deref<Target = impl Deref<Target = [u32]>>,
-    non_determinism: impl Deref<Target = impl NonDeterminism>,
-    skip_set: HashSet<(CircuitType, usize)>,
-    free_allocator: Receiver<A>,
-    results: Sender<WorkerResult<A>>,
-) {
-    trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] worker for tracing touched RAM started");
+    binary: &[u32],
+    non_determinism: &N,
+    initial_registers: [u32; 32],
+    ram_size: usize,
+    setup_teardown_fill_ratio: f64,
+    progress_callback: &Option<ProgressCallback>,
+    mut on_chunk_with_no_setup_and_teardown: impl FnMut(usize),
+    mut on_chunk_traced: impl FnMut(usize, usize),
+) -> Result<TouchedRamSimulation<C>, WorkerError> {
+    assert!(setup_teardown_fill_ratio > 0.0 && setup_teardown_fill_ratio <= 1.0);
     let domain_size = circuit_type.get_domain_size();
     assert!(domain_size.is_power_of_two());
     let log_domain_size = domain_size.trailing_zeros();
     let mut non_determinism = non_determinism.clone();
-    let mut memory = BoxedMemoryImplWithRom::<RAM_SIZE, LOG_ROM_SIZE>::new();
+    let mut memory = BoxedMemoryImplWithRom::<RAM_SIZE, LOG_ROM_SIZE>::new(ram_size);
     for (idx, instruction) in binary.iter().enumerate() {
         memory.populate(ENTRY_POINT + idx as u32 * 4, *instruction);
     }
     let cycles_per_chunk = domain_size - 1;
-    let mut state = RiscV32StateForUnrolledProver::<C>::initial(ENTRY_POINT);
+    let setup_and_teardown_fill_target =
+        ((cycles_per_chunk as f64 * setup_teardown_fill_ratio).ceil() as usize).max(1);
+    let mut state = create_initial_state::<C>(initial_registers);
     let mut custom_csr_processor = DelegationsCSRProcessor;
-    let mut ram_tracing_data = RamTracingData::<RAM_SIZE, true>::new();
+    let mut ram_tracing_data = RamTracingData::<RAM_SIZE, true>::new(ram_size);
     let cycle_tracing_data = CycleTracingData::with_cycles_capacity(0);
     let delegation_tracing_data = DelegationTracingData::default();
     let delegation_swap_fn = |_, _| unreachable!();
@@ -192,26 +648,22 @@ fn trace_touched_ram<C: MachineConfig, A: GoodAllocator>(
         let mhz = (cycles_per_chunk as f64) / (elapsed_ms * 1000.0);
         trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] chunk {chunks_traced_count} finished in {elapsed_ms:.3} ms @ {mhz:.3} MHz");
         chunks_traced_count += 1;
+        report_progress(
+            progress_callback,
+            worker_id,
+            chunks_traced_count,
+            mhz,
+            finished,
+        );
         let touched_ram_cells_count =
             tracer.ram_tracing_data.get_touched_ram_cells_count() as usize;
+        on_chunk_traced(chunks_traced_count - 1, touched_ram_cells_count);
         let chunks_needed_for_setup_and_teardowns =
-            touched_ram_cells_count.div_ceil(cycles_per_chunk);
+            touched_ram_cells_count.div_ceil(setup_and_teardown_fill_target);
         let chunks_diff = chunks_traced_count - next_chunk_index_with_no_setup_and_teardown;
         if chunks_needed_for_setup_and_teardowns < chunks_diff {
             trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] chunk {next_chunk_index_with_no_setup_and_teardown} does not need setup and teardown");
-            if skip_set.contains(&(
-                CircuitType::Main(circuit_type),
-                next_chunk_index_with_no_setup_and_teardown,
-            )) {
-                trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] chunk {next_chunk_index_with_no_setup_and_teardown} skipped");
-            } else {
-                let chunk = SetupAndTeardownChunk {
-                    index: next_chunk_index_with_no_setup_and_teardown,
-                    chunk: None,
-                };
-                let result = WorkerResult::SetupAndTeardownChunk(chunk);
-                results.send(result).unwrap();
-            }
+            on_chunk_with_no_setup_and_teardown(next_chunk_index_with_no_setup_and_teardown);
             next_chunk_index_with_no_setup_and_teardown += 1;
         }
         if finished {
@@ -232,10 +684,86 @@ fn trace_touched_ram<C: MachineConfig, A: GoodAllocator>(
             timestamp_from_chunk_cycle_and_sequence(0, cycles_per_chunk, chunks_traced_count);
         tracer.current_timestamp = new_timestamp;
     }
-    assert!(
-        end_reached,
-        "BATCH[{batch_id}] CPU_WORKER[{worker_id}] end of execution was not reached after {num_main_chunks_upper_bound} chunks"
-    );
+    if !end_reached {
+        return Err(WorkerError::ExecutionNotFinished {
+            chunks_traced: chunks_traced_count,
+            last_pc: state.observable.pc,
+        });
+    }
+    let simulation = TouchedRamSimulation {
+        state,
+        memory,
+        ram_tracing_data,
+        chunks_traced_count,
+        next_chunk_index_with_no_setup_and_teardown,
+    };
+    Ok(simulation)
+}
+
+fn trace_touched_ram<C: MachineConfig, A: GoodAllocator>(
+    batch_id: u64,
+    worker_id: usize,
+    num_main_chunks_upper_bound: usize,
+    circuit_type: MainCircuitType,
+    binary: impl Deref<Target = impl Deref<Target = [u32]>>,
+    non_determinism: impl Deref<Target = impl NonDeterminism>,
+    initial_registers: [u32; 32],
+    ram_size: usize,
+    skip_set: HashSet<(CircuitType, usize)>,
+    free_allocator: Receiver<A>,
+    free_allocator_timeout: Duration,
+    setup_teardown_fill_ratio: f64,
+    want_final_registers: bool,
+    collect_stats: bool,
+    results: Sender<WorkerResult<A>>,
+    progress_callback: Option<ProgressCallback>,
+) -> Result<(), WorkerError> {
+    trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] worker for tracing touched RAM started");
+    let cycles_per_chunk = circuit_type.get_domain_size() - 1;
+    let send_chunk_with_no_setup_and_teardown = |index| {
+        if skip_set.contains(&(CircuitType::Main(circuit_type), index)) {
+            trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] chunk {index} skipped");
+        } else {
+            let chunk = SetupAndTeardownChunk { index, chunk: None };
+            let result = WorkerResult::SetupAndTeardownChunk(chunk);
+            results.send(result).unwrap();
+        }
+    };
+    let mut previous_touched_cells = 0;
+    let send_touched_ram_stats = |chunk_index, touched_cells| {
+        if collect_stats {
+            let delta = touched_cells - previous_touched_cells;
+            previous_touched_cells = touched_cells;
+            let result = WorkerResult::TouchedRamStats {
+                chunk_index,
+                touched_cells,
+                delta,
+            };
+            results.send(result).unwrap();
+        }
+    };
+    let simulation = simulate_touched_ram::<C, _>(
+        batch_id,
+        worker_id,
+        num_main_chunks_upper_bound,
+        circuit_type,
+        &binary,
+        &*non_determinism,
+        initial_registers,
+        ram_size,
+        setup_teardown_fill_ratio,
+        &progress_callback,
+        send_chunk_with_no_setup_and_teardown,
+        send_touched_ram_stats,
+    )?;
+    let setup_and_teardown_chunks_count = simulation.setup_and_teardown_chunks_count();
+    let TouchedRamSimulation {
+        state,
+        memory,
+        ram_tracing_data,
+        chunks_traced_count,
+        next_chunk_index_with_no_setup_and_teardown,
+    } = simulation;
     let RamTracingData {
         register_last_live_timestamps,
         ram_words_last_live_timestamps,
@@ -243,19 +771,15 @@ fn trace_touched_ram<C: MachineConfig, A: GoodAllocator>(
         ..
     } = ram_tracing_data;
     let memory_final_state = memory.get_final_ram_state();
+    trace!(
+        "BATCH[{batch_id}] CPU_WORKER[{worker_id}] {setup_and_teardown_chunks_count} setup and teardown chunk(s) are needed"
+    );
     let mut chunker = create_setup_and_teardown_chunker(
         &num_touched_ram_cells_in_pages,
         &memory_final_state,
         &ram_words_last_live_timestamps,
         cycles_per_chunk,
-    );
-    let setup_and_teardown_chunks_count = chunker.get_chunks_count();
-    trace!(
-        "BATCH[{batch_id}] CPU_WORKER[{worker_id}] {setup_and_teardown_chunks_count} setup and teardown chunk(s) are needed"
-    );
-    assert_eq!(
-        chunks_traced_count,
-        setup_and_teardown_chunks_count + next_chunk_index_with_no_setup_and_teardown
+        setup_and_teardown_chunks_count,
     );
     let now = Instant::now();
     for index in next_chunk_index_with_no_setup_and_teardown..chunks_traced_count {
@@ -266,10 +790,12 @@ fn trace_touched_ram<C: MachineConfig, A: GoodAllocator>(
                 index
             );
         } else {
-            let allocator = free_allocator.recv().unwrap();
-            let lazy_init_data = Vec::with_capacity_in(cycles_per_chunk, allocator);
+            let allocator =
+                recv_free_allocator(&free_allocator, free_allocator_timeout, worker_id, index)?;
+            let len = chunker.chunk_len(chunker.next_chunk_index);
+            let lazy_init_data = Vec::with_capacity_in(len, allocator);
             let mut setup_and_teardown = ShuffleRamSetupAndTeardown { lazy_init_data };
-            unsafe { setup_and_teardown.lazy_init_data.set_len(cycles_per_chunk) };
+            unsafe { setup_and_teardown.lazy_init_data.set_len(len) };
             chunker.populate_next_chunk(&mut setup_and_teardown.lazy_init_data);
             let chunk = Some(setup_and_teardown);
             let chunk = SetupAndTeardownChunk { index, chunk };
@@ -281,65 +807,95 @@ fn trace_touched_ram<C: MachineConfig, A: GoodAllocator>(
         "BATCH[{batch_id}] CPU_WORKER[{worker_id}] setup and teardown chunk(s) collected in {:.3} ms",
         now.elapsed().as_secs_f64() * 1000.0
     );
-    let final_register_values = state
-        .observable
-        .registers
-        .into_iter()
-        .zip(register_last_live_timestamps.into_iter())
-        .map(|(value, last_access_timestamp)| FinalRegisterValue {
-            value,
-            last_access_timestamp,
-        })
-        .collect_array()
-        .unwrap();
+    let final_register_values = want_final_registers.then(|| {
+        state
+            .observable
+            .registers
+            .into_iter()
+            .zip(register_last_live_timestamps.into_iter())
+            .map(|(value, last_access_timestamp)| FinalRegisterValue {
+                value,
+                last_access_timestamp,
+            })
+            .collect_array()
+            .unwrap()
+    });
     let result = WorkerResult::RAMTracingResult {
         chunks_traced_count,
         final_register_values,
     };
     results.send(result).unwrap();
     trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] tracing touched RAM finished");
+    Ok(())
 }
 
-fn trace_cycles<C: MachineConfig, A: GoodAllocator + 'static>(
+pub(crate) fn trace_cycles<C: MachineConfig, N: NonDeterminism, A: GoodAllocator + 'static>(
     batch_id: u64,
     worker_id: usize,
     num_main_chunks_upper_bound: usize,
     circuit_type: MainCircuitType,
     binary: impl Deref<Target = impl Deref<Target = [u32]>>,
-    non_determinism: impl Deref<Target = impl NonDeterminism>,
+    non_determinism: impl Deref<Target = N>,
+    initial_registers: [u32; 32],
+    ram_size: usize,
+    checkpoint: Option<SimulationCheckpoint<C, N>>,
     skip_set: HashSet<(CircuitType, usize)>,
+    split_chunks_count: usize,
     split_count: usize,
     split_index: usize,
+    split_strategy: SplitStrategy,
     free_allocator: Receiver<A>,
+    free_allocator_timeout: Duration,
     results: Sender<WorkerResult<A>>,
-) {
+    progress_callback: Option<ProgressCallback>,
+) -> Result<(), WorkerError> {
     trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] worker for tracing cycles started");
     let domain_size = circuit_type.get_domain_size();
     assert!(domain_size.is_power_of_two());
     let log_domain_size = domain_size.trailing_zeros();
-    let mut non_determinism = non_determinism.clone();
-    let mut memory = BoxedMemoryImplWithRom::<RAM_SIZE, LOG_ROM_SIZE>::new();
-    for (idx, instruction) in binary.iter().enumerate() {
-        memory.populate(ENTRY_POINT + idx as u32 * 4, *instruction);
-    }
+    let mut checkpoint = if let Some(checkpoint) = checkpoint {
+        trace!(
+            "BATCH[{batch_id}] CPU_WORKER[{worker_id}] resuming simulation from chunk {}",
+            checkpoint.chunk_index
+        );
+        checkpoint
+    } else {
+        SimulationCheckpoint::new(&binary, &*non_determinism, initial_registers, ram_size)
+    };
+    let first_chunk_index = checkpoint.chunk_index;
+    assert!(split_chunks_count <= num_main_chunks_upper_bound);
+    let Range {
+        start: range_start,
+        end: range_end,
+    } = get_contiguous_range(split_chunks_count, split_count, split_index);
+    let simulation_end = match split_strategy {
+        SplitStrategy::RoundRobin => num_main_chunks_upper_bound,
+        SplitStrategy::Contiguous => range_end,
+    };
     let cycles_per_chunk = domain_size - 1;
-    let mut state = RiscV32StateForUnrolledProver::<C>::initial(ENTRY_POINT);
     let mut custom_csr_processor = DelegationsCSRProcessor;
-    let mut ram_tracing_data = RamTracingData::<RAM_SIZE, false>::new();
     let mut end_reached = false;
-    let mut chunks_traced_count = 0;
+    let mut chunks_traced_count = first_chunk_index;
     trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] starting simulation");
     let now = Instant::now();
-    for chunk_index in 0..num_main_chunks_upper_bound {
-        let delegation_tracing_data = DelegationTracingData::default();
-        let delegation_swap_fn = |_, _| unreachable!();
-        let initial_timestamp =
-            timestamp_from_chunk_cycle_and_sequence(0, cycles_per_chunk, chunk_index);
+    for chunk_index in first_chunk_index..simulation_end {
         let finished;
-        if chunk_index % split_count == split_index
-            && !skip_set.contains(&(CircuitType::Main(circuit_type), chunk_index))
-        {
-            let allocator = free_allocator.recv().unwrap();
+        let mhz;
+        let is_assigned = match split_strategy {
+            SplitStrategy::RoundRobin => chunk_index % split_count == split_index,
+            SplitStrategy::Contiguous => chunk_index >= range_start,
+        };
+        if is_assigned && !skip_set.contains(&(CircuitType::Main(circuit_type), chunk_index)) {
+            let delegation_tracing_data = DelegationTracingData::default();
+            let delegation_swap_fn = |_, _| unreachable!();
+            let initial_timestamp =
+                timestamp_from_chunk_cycle_and_sequence(0, cycles_per_chunk, chunk_index);
+            let allocator = recv_free_allocator(
+                &free_allocator,
+                free_allocator_timeout,
+                worker_id,
+                chunk_index,
+            )?;
             let per_cycle_data = Vec::with_capacity_in(cycles_per_chunk, allocator);
             let cycle_tracing_data = CycleTracingData { per_cycle_data };
             trace!(
@@ -347,22 +903,23 @@ fn trace_cycles<C: MachineConfig, A: GoodAllocator + 'static>(
             );
             let mut tracer =
                 ExecutionTracer::<RAM_SIZE, LOG_ROM_SIZE, _, A, Global, false, true, false>::new(
-                    &mut ram_tracing_data,
+                    &mut checkpoint.ram_tracing_data,
                     cycle_tracing_data,
                     delegation_tracing_data,
                     delegation_swap_fn,
                     initial_timestamp,
                 );
             let now = Instant::now();
-            finished = state.run_cycles(
-                &mut memory,
+            finished = checkpoint.state.run_cycles(
+                &mut checkpoint.memory,
                 &mut tracer,
-                &mut non_determinism,
+                &mut checkpoint.non_determinism,
                 &mut custom_csr_processor,
                 cycles_per_chunk,
             );
+            checkpoint.chunk_index += 1;
             let elapsed_ms = now.elapsed().as_secs_f64() * 1000.0;
-            let mhz = (cycles_per_chunk as f64) / (elapsed_ms * 1000.0);
+            mhz = (cycles_per_chunk as f64) / (elapsed_ms * 1000.0);
             trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] tracing cycles for chunk {chunk_index} finished in {elapsed_ms:.3} ms @ {mhz:.3} MHz");
             let chunk = CyclesChunk {
                 index: chunk_index,
@@ -373,62 +930,58 @@ fn trace_cycles<C: MachineConfig, A: GoodAllocator + 'static>(
         } else {
             // fast-forward the simulation
             trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] fast-forwarding chunk {chunk_index}");
-            let cycle_tracing_data = CycleTracingData::with_cycles_capacity(0);
-            let mut tracer = ExecutionTracer::<
-                RAM_SIZE,
-                LOG_ROM_SIZE,
-                _,
-                Global,
-                Global,
-                false,
-                false,
-                false,
-            >::new(
-                &mut ram_tracing_data,
-                cycle_tracing_data,
-                delegation_tracing_data,
-                delegation_swap_fn,
-                initial_timestamp,
-            );
             let now = Instant::now();
-            finished = state.run_cycles(
-                &mut memory,
-                &mut tracer,
-                &mut non_determinism,
-                &mut custom_csr_processor,
-                cycles_per_chunk,
-            );
+            finished = checkpoint.fast_forward_chunk(cycles_per_chunk);
             let elapsed_ms = now.elapsed().as_secs_f64() * 1000.0;
-            let mhz = (cycles_per_chunk as f64) / (elapsed_ms * 1000.0);
+            mhz = (cycles_per_chunk as f64) / (elapsed_ms * 1000.0);
             trace!(
                 "BATCH[{batch_id}] CPU_WORKER[{worker_id}] fast-forwarding chunk {chunk_index} finished in {elapsed_ms:.3} ms @ {mhz:.3} MHz"
             );
         }
         chunks_traced_count += 1;
+        report_progress(
+            &progress_callback,
+            worker_id,
+            chunks_traced_count,
+            mhz,
+            finished,
+        );
         if finished {
             let elapsed_ms = now.elapsed().as_secs_f64() * 1000.0;
             let cycles_count = chunks_traced_count * cycles_per_chunk;
             let speed = (cycles_count as f64) / (elapsed_ms * 1000.0);
             trace!(
                 "BATCH[{batch_id}] CPU_WORKER[{worker_id}] simulation ended at address 0x{:08x} and took {chunks_traced_count} chunks to finish execution",
-                state.observable.pc,
+                checkpoint.state.observable.pc,
             );
             debug!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] simulator tracing 1/{split_count} cycles ran {chunks_traced_count}x(2^{log_domain_size}-1) cycles in {elapsed_ms:.3} ms @ {speed:.3} MHz");
             end_reached = true;
             break;
         }
     }
-    assert!(
-        end_reached,
-        "BATCH[{batch_id}] CPU_WORKER[{worker_id}] end of execution was not reached after {num_main_chunks_upper_bound} chunks"
-    );
+    if !end_reached && simulation_end < split_chunks_count {
+        // the total number of chunks is reported by the worker that reaches the end of execution
+        trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] tracing cycles for chunks {range_start}..{range_end} finished");
+        return Ok(());
+    }
+    if !end_reached {
+        return Err(WorkerError::ExecutionNotFinished {
+            chunks_traced: chunks_traced_count,
+            last_pc: checkpoint.state.observable.pc,
+        });
+    }
     let result = WorkerResult::CyclesTracingResult {
         chunks_traced_count,
     };
     results.send(result).unwrap();
     trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] tracing cycles finished");
+    Ok(())
 }
 
+// checks every produced delegation witness before it is sent, so a corrupted witness is reported
+// together with the chunk it belongs to instead of failing somewhere down the line
+const CHECK_DELEGATION_WITNESSES: bool = cfg!(debug_assertions);
+
 fn trace_delegations<C: MachineConfig, A: GoodAllocator + 'static>(
     batch_id: u64,
     worker_id: usize,
@@ -436,26 +989,39 @@ fn trace_delegations<C: MachineConfig, A: GoodAllocator + 'static>(
     circuit_type: MainCircuitType,
     binary: impl Deref<Target = impl Deref<Target = [u32]>>,
     non_determinism: impl Deref<Target = impl NonDeterminism>,
+    initial_registers: [u32; 32],
+    ram_size: usize,
     skip_set: HashSet<(CircuitType, usize)>,
     free_allocator: Receiver<A>,
+    free_allocator_timeout: Duration,
     results: Sender<WorkerResult<A>>,
-) {
+    progress_callback: Option<ProgressCallback>,
+) -> Result<(), WorkerError> {
     trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] worker for tracing delegations started");
+    if !circuit_type.allows_delegations() {
+        trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] circuit {circuit_type:?} allows no delegations, skipping tracing");
+        let result = WorkerResult::DelegationTracingResult {
+            delegation_chunks_counts: HashMap::new(),
+        };
+        results.send(result).unwrap();
+        return Ok(());
+    }
     let domain_size = circuit_type.get_domain_size();
     assert!(domain_size.is_power_of_two());
     let log_domain_size = domain_size.trailing_zeros();
     let mut non_determinism = non_determinism.clone();
-    let mut memory = BoxedMemoryImplWithRom::<RAM_SIZE, LOG_ROM_SIZE>::new();
+    let mut memory = BoxedMemoryImplWithRom::<RAM_SIZE, LOG_ROM_SIZE>::new(ram_size);
     for (idx, instruction) in binary.iter().enumerate() {
         memory.populate(ENTRY_POINT + idx as u32 * 4, *instruction);
     }
     let cycles_per_chunk = domain_size - 1;
-    let mut state = RiscV32StateForUnrolledProver::<C>::initial(ENTRY_POINT);
+    let mut state = create_initial_state::<C>(initial_registers);
     let mut custom_csr_processor = DelegationsCSRProcessor;
-    let mut ram_tracing_data = RamTracingData::<RAM_SIZE, false>::new();
+    let mut ram_tracing_data = RamTracingData::<RAM_SIZE, false>::new(ram_size);
     let cycle_tracing_data = CycleTracingData::with_cycles_capacity(0);
     let delegation_tracing_data = DelegationTracingData::default();
     let delegation_chunks_counts = RefCell::new(HashMap::new());
+    let starvation_error = Cell::new(None);
     let delegation_swap_fn = |circuit_type, tracing_type: Option<DelegationTracingType<A>>| {
         if let Some(tracing_type) = tracing_type {
             let mut borrow = delegation_chunks_counts.borrow_mut();
@@ -466,6 +1032,11 @@ fn trace_delegations<C: MachineConfig, A: GoodAllocator + 'static>(
                 }
                 DelegationTracingType::Witness(witness) => {
                     trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] full delegation {:?} chunk {value} witness with {} delegations produced", circuit_type, witness.num_requests);
+                    if CHECK_DELEGATION_WITNESSES {
+                        if let Err(error) = witness.check_consistency() {
+                            panic!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] delegation {circuit_type:?} chunk {value} {error}");
+                        }
+                    }
                     let result = WorkerResult::DelegationWitness {
                         circuit_sequence: *value,
                         witness,
@@ -480,21 +1051,38 @@ fn trace_delegations<C: MachineConfig, A: GoodAllocator + 'static>(
             .get(&circuit_type)
             .copied()
             .unwrap_or_default();
+        let counter = DelegationTracingType::Counter(DelegationCounter {
+            num_requests: circuit_type.get_num_delegation_cycles(),
+            count: 0,
+        });
         if skip_set.contains(&(CircuitType::Delegation(circuit_type), current_count)) {
             trace!(
                 "BATCH[{batch_id}] CPU_WORKER[{worker_id}] skipping delegation {:?} chunk {current_count}",
                 circuit_type
             );
-            let counter = DelegationCounter {
-                num_requests: circuit_type.get_num_delegation_cycles(),
-                count: 0,
-            };
-            DelegationTracingType::Counter(counter)
+            counter
+        } else if starvation_error.get().is_some() {
+            // the worker already failed, so it does not wait for allocators anymore
+            counter
         } else {
-            let allocator = free_allocator.recv().unwrap();
-            let factory = circuit_type.get_witness_factory_fn();
-            let witness = factory(allocator);
-            DelegationTracingType::Witness(witness)
+            match recv_free_allocator(
+                &free_allocator,
+                free_allocator_timeout,
+                worker_id,
+                current_count,
+            ) {
+                Ok(allocator) => {
+                    let factory = circuit_type.get_witness_factory_fn();
+                    let witness = factory(allocator);
+                    DelegationTracingType::Witness(witness)
+                }
+                Err(error) => {
+                    // the error can not be returned from the tracer, so the chunk is only counted
+                    // and the error is reported after the current main chunk is simulated
+                    starvation_error.set(Some(error));
+                    counter
+                }
+            }
         }
     };
     let initial_timestamp = timestamp_from_chunk_cycle_and_sequence(0, cycles_per_chunk, 0);
@@ -519,10 +1107,20 @@ fn trace_delegations<C: MachineConfig, A: GoodAllocator + 'static>(
             &mut custom_csr_processor,
             cycles_per_chunk,
         );
+        if let Some(error) = starvation_error.get() {
+            return Err(error);
+        }
         let elapsed_ms = chunk_now.elapsed().as_secs_f64() * 1000.0;
         let mhz = (cycles_per_chunk as f64) / (elapsed_ms * 1000.0);
         trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] chunk {chunks_traced_count} finished in {elapsed_ms:.3} ms @ {mhz:.3} MHz");
         chunks_traced_count += 1;
+        report_progress(
+            &progress_callback,
+            worker_id,
+            chunks_traced_count,
+            mhz,
+            finished,
+        );
         if finished {
             let elapsed_ms = now.elapsed().as_secs_f64() * 1000.0;
             let cycles_count = chunks_traced_count * cycles_per_chunk;
@@ -539,10 +1137,12 @@ fn trace_delegations<C: MachineConfig, A: GoodAllocator + 'static>(
             timestamp_from_chunk_cycle_and_sequence(0, cycles_per_chunk, chunks_traced_count);
         tracer.current_timestamp = new_timestamp;
     }
-    assert!(
-        end_reached,
-        "end of execution was not reached after {num_main_chunks_upper_bound} chunks"
-    );
+    if !end_reached {
+        return Err(WorkerError::ExecutionNotFinished {
+            chunks_traced: chunks_traced_count,
+            last_pc: state.observable.pc,
+        });
+    }
     let mut delegation_chunks_counts = delegation_chunks_counts.borrow().clone();
     for (circuit_type, tracing_type) in tracer.delegation_tracing_data.tracing_types.drain() {
         let value = delegation_chunks_counts.entry(circuit_type).or_default();
@@ -553,7 +1153,11 @@ fn trace_delegations<C: MachineConfig, A: GoodAllocator + 'static>(
                 trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] delegation {circuit_type:?} chunk {value} counter with {count} delegations counted");
             }
             DelegationTracingType::Witness(witness) => {
-                witness.assert_consistency();
+                if CHECK_DELEGATION_WITNESSES {
+                    if let Err(error) = witness.check_consistency() {
+                        panic!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] delegation {circuit_type:?} chunk {value} {error}");
+                    }
+                }
                 let is_empty = witness.write_timestamp.is_empty();
                 trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] delegation {circuit_type:?} chunk {value} witness with {} delegations produced", witness.write_timestamp.len());
                 let result = WorkerResult::DelegationWitness {
@@ -573,4 +1177,947 @@ fn trace_delegations<C: MachineConfig, A: GoodAllocator + 'static>(
     };
     results.send(result).unwrap();
     trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] tracing delegations finished");
+    Ok(())
+}
+
+/// Runs the simulation only counting delegations, no witness buffers are allocated.
+/// Delegation types that were never requested are absent from the produced counts.
+fn count_delegations<C: MachineConfig, A: GoodAllocator>(
+    batch_id: u64,
+    worker_id: usize,
+    num_main_chunks_upper_bound: usize,
+    circuit_type: MainCircuitType,
+    binary: impl Deref<Target = impl Deref<Target = [u32]>>,
+    non_determinism: impl Deref<Target = impl NonDeterminism>,
+    initial_registers: [u32; 32],
+    ram_size: usize,
+    results: Sender<WorkerResult<A>>,
+    progress_callback: Option<ProgressCallback>,
+) -> Result<(), WorkerError> {
+    trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] worker for counting delegations started");
+    if !circuit_type.allows_delegations() {
+        trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] circuit {circuit_type:?} allows no delegations, skipping counting");
+        let result = WorkerResult::DelegationCountingResult {
+            delegation_counts: HashMap::new(),
+        };
+        results.send(result).unwrap();
+        return Ok(());
+    }
+    let domain_size = circuit_type.get_domain_size();
+    assert!(domain_size.is_power_of_two());
+    let mut non_determinism = non_determinism.clone();
+    let mut memory = BoxedMemoryImplWithRom::<RAM_SIZE, LOG_ROM_SIZE>::new(ram_size);
+    for (idx, instruction) in binary.iter().enumerate() {
+        memory.populate(ENTRY_POINT + idx as u32 * 4, *instruction);
+    }
+    let cycles_per_chunk = domain_size - 1;
+    let mut state = create_initial_state::<C>(initial_registers);
+    let mut custom_csr_processor = DelegationsCSRProcessor;
+    let mut ram_tracing_data = RamTracingData::<RAM_SIZE, false>::new(ram_size);
+    let cycle_tracing_data = CycleTracingData::with_cycles_capacity(0);
+    let delegation_tracing_data = DelegationTracingData::default();
+    // counters never reach capacity, so they are never swapped
+    let delegation_swap_fn = |_, tracing_type: Option<DelegationTracingType<Global>>| {
+        assert!(tracing_type.is_none());
+        let counter = DelegationCounter {
+            num_requests: usize::MAX,
+            count: 0,
+        };
+        DelegationTracingType::Counter(counter)
+    };
+    let initial_timestamp = timestamp_from_chunk_cycle_and_sequence(0, cycles_per_chunk, 0);
+    let mut tracer =
+        ExecutionTracer::<RAM_SIZE, LOG_ROM_SIZE, _, Global, Global, false, false, true>::new(
+            &mut ram_tracing_data,
+            cycle_tracing_data,
+            delegation_tracing_data,
+            delegation_swap_fn,
+            initial_timestamp,
+        );
+    let mut end_reached = false;
+    let mut chunks_traced_count = 0;
+    let now = Instant::now();
+    for _chunk_index in 0..num_main_chunks_upper_bound {
+        let chunk_now = Instant::now();
+        let finished = state.run_cycles(
+            &mut memory,
+            &mut tracer,
+            &mut non_determinism,
+            &mut custom_csr_processor,
+            cycles_per_chunk,
+        );
+        let elapsed_ms = chunk_now.elapsed().as_secs_f64() * 1000.0;
+        let mhz = (cycles_per_chunk as f64) / (elapsed_ms * 1000.0);
+        trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] chunk {chunks_traced_count} finished in {elapsed_ms:.3} ms @ {mhz:.3} MHz");
+        chunks_traced_count += 1;
+        report_progress(
+            &progress_callback,
+            worker_id,
+            chunks_traced_count,
+            mhz,
+            finished,
+        );
+        if finished {
+            let elapsed_ms = now.elapsed().as_secs_f64() * 1000.0;
+            debug!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] simulator counting delegations ran {chunks_traced_count} chunks in {elapsed_ms:.3} ms");
+            end_reached = true;
+            break;
+        }
+        let new_timestamp =
+            timestamp_from_chunk_cycle_and_sequence(0, cycles_per_chunk, chunks_traced_count);
+        tracer.current_timestamp = new_timestamp;
+    }
+    if !end_reached {
+        return Err(WorkerError::ExecutionNotFinished {
+            chunks_traced: chunks_traced_count,
+            last_pc: state.observable.pc,
+        });
+    }
+    let delegation_counts = tracer
+        .delegation_tracing_data
+        .tracing_types
+        .drain()
+        .filter_map(|(circuit_type, tracing_type)| match tracing_type {
+            DelegationTracingType::Counter(counter) => {
+                trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] delegation {circuit_type:?} requested {} times", counter.count);
+                (counter.count != 0).then_some((circuit_type, counter.count))
+            }
+            DelegationTracingType::Witness(_) => unreachable!(),
+        })
+        .collect();
+    let result = WorkerResult::DelegationCountingResult { delegation_counts };
+    results.send(result).unwrap();
+    trace!("BATCH[{batch_id}] CPU_WORKER[{worker_id}] counting delegations finished");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossbeam_channel::unbounded;
+    use cs::definitions::TimestampData;
+    use execution_utils::get_padded_binary;
+    use prover::definitions::LazyInitAndTeardown;
+    use prover::risc_v_simulator::abstractions::non_determinism::QuasiUARTSource;
+    use prover::risc_v_simulator::cycle::{IMStandardIsaConfig, IWithoutByteAccessIsaConfig};
+    use prover::tracers::main_cycle_optimized::SingleCycleTracingData;
+    use std::io::Read;
+    use std::sync::Arc;
+
+    const CIRCUIT_TYPE: MainCircuitType = MainCircuitType::RiscVCycles;
+    const CHECKPOINT_CHUNK_INDEX: usize = 3;
+    // guest programs place the stack right after 2 MB of ROM and 64 MB of stack space
+    const SMALL_RAM_LOG_SIZE: u32 = 27;
+
+    struct Fixture {
+        binary: Arc<Vec<u32>>,
+        non_determinism: Arc<QuasiUARTSource>,
+        free_allocator: Receiver<Global>,
+        results_sender: Sender<WorkerResult<Global>>,
+        results: Receiver<WorkerResult<Global>>,
+    }
+
+    // the test program with `allocators_count` free allocators, workers are starved after those
+    fn fixture(allocators_count: usize) -> Fixture {
+        let mut binary = vec![];
+        std::fs::File::open("../examples/hashed_fibonacci/app.bin")
+            .unwrap()
+            .read_to_end(&mut binary)
+            .unwrap();
+        let binary = Arc::new(get_padded_binary(&binary));
+        let non_determinism = Arc::new(QuasiUARTSource::new_with_reads(vec![1 << 16, 1 << 14]));
+        let (free_allocator_sender, free_allocator) = unbounded();
+        for _ in 0..allocators_count {
+            free_allocator_sender.send(Global).unwrap();
+        }
+        let (results_sender, results) = unbounded();
+        Fixture {
+            binary,
+            non_determinism,
+            free_allocator,
+            results_sender,
+            results,
+        }
+    }
+
+    fn trace_chunk(
+        checkpoint: Option<SimulationCheckpoint<IMStandardIsaConfig, QuasiUARTSource>>,
+        split_strategy: SplitStrategy,
+    ) -> Vec<SingleCycleTracingData> {
+        let Fixture {
+            binary,
+            non_determinism,
+            free_allocator,
+            results_sender,
+            results,
+        } = fixture(1);
+        trace_cycles::<IMStandardIsaConfig, _, Global>(
+            0,
+            0,
+            1 << 10,
+            CIRCUIT_TYPE,
+            binary,
+            non_determinism,
+            [0; 32],
+            RAM_SIZE,
+            checkpoint,
+            HashSet::new(),
+            1 << 10,
+            1 << 10,
+            CHECKPOINT_CHUNK_INDEX,
+            split_strategy,
+            free_allocator,
+            DEFAULT_FREE_ALLOCATOR_TIMEOUT,
+            results_sender,
+            None,
+        )
+        .unwrap();
+        results
+            .try_iter()
+            .find_map(|result| match result {
+                WorkerResult::CyclesChunk(chunk) if chunk.index == CHECKPOINT_CHUNK_INDEX => {
+                    Some(chunk.data.per_cycle_data.to_vec())
+                }
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_resume_from_checkpoint() {
+        let Fixture {
+            binary,
+            non_determinism,
+            ..
+        } = fixture(0);
+
+        let from_scratch = trace_chunk(None, SplitStrategy::RoundRobin);
+
+        let checkpoint = create_simulation_checkpoint::<IMStandardIsaConfig, _>(
+            CIRCUIT_TYPE,
+            &binary,
+            &*non_determinism,
+            None,
+            None,
+            CHECKPOINT_CHUNK_INDEX,
+        )
+        .expect("execution must not finish before the checkpoint");
+        let resumed = trace_chunk(Some(checkpoint), SplitStrategy::RoundRobin);
+
+        assert!(!from_scratch.is_empty());
+        assert!(from_scratch == resumed);
+    }
+
+    #[test]
+    fn test_send_simulation_checkpoints() {
+        let Fixture {
+            binary,
+            non_determinism,
+            ..
+        } = fixture(0);
+
+        let (first_sender, first) = unbounded();
+        let (second_sender, second) = unbounded();
+        let (after_end_sender, after_end) = unbounded();
+        send_simulation_checkpoints::<IMStandardIsaConfig, _>(
+            0,
+            CIRCUIT_TYPE,
+            &binary,
+            &*non_determinism,
+            None,
+            None,
+            vec![
+                (CHECKPOINT_CHUNK_INDEX, first_sender),
+                (CHECKPOINT_CHUNK_INDEX, second_sender),
+                (1 << 10, after_end_sender),
+            ],
+        );
+        let first = first.try_recv().unwrap();
+        let second = second.try_recv().unwrap();
+        assert_eq!(first.chunk_index, CHECKPOINT_CHUNK_INDEX);
+        assert_eq!(second.chunk_index, CHECKPOINT_CHUNK_INDEX);
+        // execution ends long before the last requested chunk
+        assert!(after_end.recv().is_err());
+
+        let from_scratch = trace_chunk(None, SplitStrategy::RoundRobin);
+        assert!(trace_chunk(Some(first), SplitStrategy::RoundRobin) == from_scratch);
+        assert!(trace_chunk(Some(second), SplitStrategy::RoundRobin) == from_scratch);
+    }
+
+    fn trace_touched_ram_with_ram_size(
+        max_ram_log_size: Option<u32>,
+        setup_teardown_fill_ratio: f64,
+        want_final_registers: bool,
+    ) -> (
+        usize,
+        Option<[FinalRegisterValue; 32]>,
+        Vec<(usize, Option<Vec<LazyInitAndTeardown>>)>,
+    ) {
+        let Fixture {
+            binary,
+            non_determinism,
+            free_allocator,
+            results_sender,
+            results,
+        } = fixture(1 << 10);
+        trace_touched_ram::<IMStandardIsaConfig, Global>(
+            0,
+            0,
+            1 << 10,
+            CIRCUIT_TYPE,
+            binary,
+            non_determinism,
+            [0; 32],
+            get_ram_size(max_ram_log_size),
+            HashSet::new(),
+            free_allocator,
+            DEFAULT_FREE_ALLOCATOR_TIMEOUT,
+            setup_teardown_fill_ratio,
+            want_final_registers,
+            false,
+            results_sender,
+            None,
+        )
+        .unwrap();
+        collect_touched_ram_results(results)
+    }
+
+    fn collect_touched_ram_results(
+        results: Receiver<WorkerResult<Global>>,
+    ) -> (
+        usize,
+        Option<[FinalRegisterValue; 32]>,
+        Vec<(usize, Option<Vec<LazyInitAndTeardown>>)>,
+    ) {
+        let mut chunks = vec![];
+        let mut tracing_result = None;
+        for result in results.try_iter() {
+            match result {
+                WorkerResult::SetupAndTeardownChunk(chunk) => {
+                    chunks.push((chunk.index, chunk.chunk.map(|c| c.lazy_init_data.to_vec())))
+                }
+                WorkerResult::RAMTracingResult {
+                    chunks_traced_count,
+                    final_register_values,
+                } => tracing_result = Some((chunks_traced_count, final_register_values)),
+                _ => unreachable!(),
+            }
+        }
+        let (chunks_traced_count, final_register_values) = tracing_result.unwrap();
+        (chunks_traced_count, final_register_values, chunks)
+    }
+
+    fn trace_touched_ram_resumed(
+        resume_state: Option<BatchResumeState>,
+    ) -> (
+        usize,
+        Option<[FinalRegisterValue; 32]>,
+        Vec<(usize, Option<Vec<LazyInitAndTeardown>>)>,
+    ) {
+        let Fixture {
+            binary,
+            non_determinism,
+            free_allocator,
+            results_sender,
+            results,
+        } = fixture(1 << 10);
+        let mode = CpuWorkerMode::TraceTouchedRam {
+            circuit_type: CIRCUIT_TYPE,
+            skip_set: HashSet::new(),
+            free_allocator,
+            free_allocator_timeout: DEFAULT_FREE_ALLOCATOR_TIMEOUT,
+            setup_teardown_fill_ratio: DEFAULT_SETUP_TEARDOWN_FILL_RATIO,
+            want_final_registers: true,
+            collect_stats: false,
+        };
+        let func = get_cpu_worker_func::<IMStandardIsaConfig, _, Global>(
+            WaitGroup::new(),
+            0,
+            0,
+            1 << 10,
+            binary,
+            non_determinism,
+            None,
+            None,
+            mode,
+            resume_state,
+            None,
+            results_sender,
+            None,
+        );
+        func().unwrap();
+        collect_touched_ram_results(results)
+    }
+
+    #[test]
+    fn test_resume_batch() {
+        let (chunks_traced_count, final_register_values, chunks) = trace_touched_ram_resumed(None);
+        assert!(chunks_traced_count > 1);
+        // the first run failed after the first half of the chunks was done
+        let completed_count = chunks_traced_count / 2;
+        let mut resume_state = BatchResumeState::new();
+        for index in 0..completed_count {
+            resume_state.mark_completed(CircuitType::Main(CIRCUIT_TYPE), index);
+        }
+        assert_eq!(resume_state.completed_count(), completed_count);
+        let (resumed_chunks_traced_count, resumed_final_register_values, resumed_chunks) =
+            trace_touched_ram_resumed(Some(resume_state));
+
+        assert_eq!(chunks_traced_count, resumed_chunks_traced_count);
+        assert!(final_register_values == resumed_final_register_values);
+        assert!(resumed_chunks
+            .iter()
+            .all(|(index, _)| *index >= completed_count));
+        let sort = |chunks: Vec<(usize, Option<Vec<LazyInitAndTeardown>>)>| {
+            chunks
+                .into_iter()
+                .sorted_by_key(|(index, _)| *index)
+                .collect_vec()
+        };
+        let union = chunks
+            .iter()
+            .filter(|(index, _)| *index < completed_count)
+            .cloned()
+            .chain(resumed_chunks)
+            .collect_vec();
+        assert!(sort(union) == sort(chunks));
+    }
+
+    #[test]
+    fn test_bounded_ram_size() {
+        let default =
+            trace_touched_ram_with_ram_size(None, DEFAULT_SETUP_TEARDOWN_FILL_RATIO, true);
+        let bounded = trace_touched_ram_with_ram_size(
+            Some(SMALL_RAM_LOG_SIZE),
+            DEFAULT_SETUP_TEARDOWN_FILL_RATIO,
+            true,
+        );
+
+        assert!(default == bounded);
+    }
+
+    #[test]
+    fn test_skip_final_registers() {
+        let (chunks_traced_count, final_register_values, chunks) =
+            trace_touched_ram_with_ram_size(None, DEFAULT_SETUP_TEARDOWN_FILL_RATIO, true);
+        let (skipped_chunks_traced_count, skipped_final_register_values, skipped_chunks) =
+            trace_touched_ram_with_ram_size(None, DEFAULT_SETUP_TEARDOWN_FILL_RATIO, false);
+
+        assert!(final_register_values.is_some());
+        assert!(skipped_final_register_values.is_none());
+        assert_eq!(chunks_traced_count, skipped_chunks_traced_count);
+        assert!(chunks == skipped_chunks);
+    }
+
+    #[test]
+    fn test_setup_teardown_fill_ratio() {
+        let count_skipped = |chunks: &[(usize, Option<Vec<LazyInitAndTeardown>>)]| {
+            chunks.iter().filter(|(_, chunk)| chunk.is_none()).count()
+        };
+
+        let (chunks_traced_count, _, chunks) =
+            trace_touched_ram_with_ram_size(None, DEFAULT_SETUP_TEARDOWN_FILL_RATIO, false);
+        // the program touches few RAM cells compared to the chunk size, so with a tiny ratio
+        // every chunk is assumed to need setup and teardown data
+        let (conservative_chunks_traced_count, _, conservative_chunks) =
+            trace_touched_ram_with_ram_size(None, 1e-6, false);
+
+        assert_eq!(chunks_traced_count, conservative_chunks_traced_count);
+        assert_eq!(chunks.len(), chunks_traced_count);
+        assert_eq!(conservative_chunks.len(), chunks_traced_count);
+        assert!(count_skipped(&chunks) > 0);
+        assert_eq!(count_skipped(&conservative_chunks), 0);
+        // extra chunks consist of padding only, the data itself is the same
+        let collect_data = |chunks: Vec<(usize, Option<Vec<LazyInitAndTeardown>>)>| {
+            chunks
+                .into_iter()
+                .filter_map(|(_, chunk)| chunk)
+                .flatten()
+                .filter(|value| value.address != 0)
+                .map(|value| (value.address, value.teardown_value))
+                .collect_vec()
+        };
+        assert_eq!(collect_data(chunks), collect_data(conservative_chunks));
+    }
+
+    #[test]
+    fn test_count_delegations() {
+        let Fixture {
+            binary,
+            non_determinism,
+            free_allocator,
+            results_sender,
+            results,
+        } = fixture(1 << 10);
+
+        trace_delegations::<IMStandardIsaConfig, Global>(
+            0,
+            0,
+            1 << 10,
+            CIRCUIT_TYPE,
+            binary.clone(),
+            non_determinism.clone(),
+            [0; 32],
+            RAM_SIZE,
+            HashSet::new(),
+            free_allocator,
+            DEFAULT_FREE_ALLOCATOR_TIMEOUT,
+            results_sender.clone(),
+            None,
+        )
+        .unwrap();
+        let mut expected_counts = HashMap::new();
+        for result in results.try_iter() {
+            if let WorkerResult::DelegationWitness { witness, .. } = result {
+                let count = witness.write_timestamp.len();
+                if count != 0 {
+                    let circuit_type = DelegationCircuitType::from(witness.delegation_type);
+                    *expected_counts.entry(circuit_type).or_default() += count;
+                }
+            }
+        }
+
+        count_delegations::<IMStandardIsaConfig, Global>(
+            0,
+            0,
+            1 << 10,
+            CIRCUIT_TYPE,
+            binary,
+            non_determinism,
+            [0; 32],
+            RAM_SIZE,
+            results_sender,
+            None,
+        )
+        .unwrap();
+        let delegation_counts = results
+            .try_iter()
+            .find_map(|result| match result {
+                WorkerResult::DelegationCountingResult { delegation_counts } => {
+                    Some(delegation_counts)
+                }
+                _ => None,
+            })
+            .unwrap();
+
+        assert!(!delegation_counts.is_empty());
+        assert_eq!(delegation_counts, expected_counts);
+    }
+
+    #[test]
+    fn test_no_delegations() {
+        const CIRCUIT_TYPE: MainCircuitType = MainCircuitType::FinalReducedRiscVMachine;
+        assert!(!CIRCUIT_TYPE.allows_delegations());
+        let Fixture {
+            binary,
+            non_determinism,
+            free_allocator,
+            results_sender,
+            results,
+        } = fixture(0);
+
+        trace_delegations::<IWithoutByteAccessIsaConfig, Global>(
+            0,
+            0,
+            1 << 10,
+            CIRCUIT_TYPE,
+            binary.clone(),
+            non_determinism.clone(),
+            [0; 32],
+            RAM_SIZE,
+            HashSet::new(),
+            free_allocator,
+            DEFAULT_FREE_ALLOCATOR_TIMEOUT,
+            results_sender.clone(),
+            None,
+        )
+        .unwrap();
+        let tracing_results = results.try_iter().collect_vec();
+        assert_eq!(tracing_results.len(), 1);
+        match &tracing_results[0] {
+            WorkerResult::DelegationTracingResult {
+                delegation_chunks_counts,
+            } => assert!(delegation_chunks_counts.is_empty()),
+            _ => panic!("unexpected worker result"),
+        }
+
+        count_delegations::<IWithoutByteAccessIsaConfig, Global>(
+            0,
+            0,
+            1 << 10,
+            CIRCUIT_TYPE,
+            binary,
+            non_determinism,
+            [0; 32],
+            RAM_SIZE,
+            results_sender,
+            None,
+        )
+        .unwrap();
+        let delegation_counts = results
+            .try_iter()
+            .find_map(|result| match result {
+                WorkerResult::DelegationCountingResult { delegation_counts } => {
+                    Some(delegation_counts)
+                }
+                _ => None,
+            })
+            .unwrap();
+        assert!(delegation_counts.is_empty());
+    }
+
+    #[test]
+    fn test_estimate_circuit_counts() {
+        let Fixture {
+            binary,
+            non_determinism,
+            free_allocator,
+            results_sender,
+            results,
+        } = fixture(1 << 10);
+
+        trace_touched_ram::<IMStandardIsaConfig, Global>(
+            0,
+            0,
+            1 << 10,
+            CIRCUIT_TYPE,
+            binary.clone(),
+            non_determinism.clone(),
+            [0; 32],
+            RAM_SIZE,
+            HashSet::new(),
+            free_allocator,
+            DEFAULT_FREE_ALLOCATOR_TIMEOUT,
+            DEFAULT_SETUP_TEARDOWN_FILL_RATIO,
+            false,
+            false,
+            results_sender,
+            None,
+        )
+        .unwrap();
+        let mut setup_and_teardown_chunks_count = 0;
+        let mut main_chunks_count = None;
+        for result in results.try_iter() {
+            match result {
+                WorkerResult::SetupAndTeardownChunk(chunk) => {
+                    if chunk.chunk.is_some() {
+                        setup_and_teardown_chunks_count += 1;
+                    }
+                }
+                WorkerResult::RAMTracingResult {
+                    chunks_traced_count,
+                    ..
+                } => main_chunks_count = Some(chunks_traced_count),
+                _ => unreachable!(),
+            }
+        }
+
+        let counts = estimate_circuit_counts::<IMStandardIsaConfig>(
+            &binary,
+            &*non_determinism,
+            CIRCUIT_TYPE,
+            1 << 10,
+            None,
+            None,
+            DEFAULT_SETUP_TEARDOWN_FILL_RATIO,
+        )
+        .unwrap();
+
+        assert_eq!(counts.main_chunks_count, main_chunks_count.unwrap());
+        assert_eq!(
+            counts.setup_and_teardown_chunks_count,
+            setup_and_teardown_chunks_count
+        );
+        assert!(counts.touched_ram_cells_count > 0);
+    }
+
+    #[test]
+    fn test_touched_ram_stats() {
+        let Fixture {
+            binary,
+            non_determinism,
+            free_allocator,
+            results_sender,
+            results,
+        } = fixture(1 << 10);
+
+        trace_touched_ram::<IMStandardIsaConfig, Global>(
+            0,
+            0,
+            1 << 10,
+            CIRCUIT_TYPE,
+            binary.clone(),
+            non_determinism.clone(),
+            [0; 32],
+            RAM_SIZE,
+            HashSet::new(),
+            free_allocator,
+            DEFAULT_FREE_ALLOCATOR_TIMEOUT,
+            DEFAULT_SETUP_TEARDOWN_FILL_RATIO,
+            false,
+            true,
+            results_sender,
+            None,
+        )
+        .unwrap();
+        let mut stats = vec![];
+        let mut main_chunks_count = None;
+        for result in results.try_iter() {
+            match result {
+                WorkerResult::TouchedRamStats {
+                    chunk_index,
+                    touched_cells,
+                    delta,
+                } => stats.push((chunk_index, touched_cells, delta)),
+                WorkerResult::SetupAndTeardownChunk(_) => {}
+                WorkerResult::RAMTracingResult {
+                    chunks_traced_count,
+                    ..
+                } => main_chunks_count = Some(chunks_traced_count),
+                _ => unreachable!(),
+            }
+        }
+
+        let counts = estimate_circuit_counts::<IMStandardIsaConfig>(
+            &binary,
+            &*non_determinism,
+            CIRCUIT_TYPE,
+            1 << 10,
+            None,
+            None,
+            DEFAULT_SETUP_TEARDOWN_FILL_RATIO,
+        )
+        .unwrap();
+
+        assert_eq!(stats.len(), main_chunks_count.unwrap());
+        assert!(stats
+            .iter()
+            .enumerate()
+            .all(|(index, (chunk_index, _, _))| index == *chunk_index));
+        let total = stats.iter().map(|(_, _, delta)| delta).sum::<usize>();
+        assert_eq!(total, stats.last().unwrap().1);
+        assert_eq!(total, counts.touched_ram_cells_count);
+    }
+
+    #[test]
+    fn test_contiguous_split() {
+        // with the split count equal to the chunks upper bound both strategies assign a single chunk
+        let round_robin = trace_chunk(None, SplitStrategy::RoundRobin);
+        let contiguous = trace_chunk(None, SplitStrategy::Contiguous);
+
+        assert!(!round_robin.is_empty());
+        assert!(round_robin == contiguous);
+    }
+
+    #[test]
+    fn test_contiguous_split_of_actual_chunks_count() {
+        let Fixture {
+            binary,
+            non_determinism,
+            free_allocator,
+            results_sender,
+            results,
+        } = fixture(1 << 10);
+        let main_chunks_count = estimate_circuit_counts::<IMStandardIsaConfig>(
+            &binary,
+            &*non_determinism,
+            CIRCUIT_TYPE,
+            1 << 10,
+            None,
+            None,
+            DEFAULT_SETUP_TEARDOWN_FILL_RATIO,
+        )
+        .unwrap()
+        .main_chunks_count;
+        assert!(main_chunks_count >= 2);
+
+        let second_range = get_contiguous_range(main_chunks_count, 2, 1);
+        let (checkpoint_sender, checkpoint_receiver) = unbounded();
+        send_simulation_checkpoints::<IMStandardIsaConfig, _>(
+            0,
+            CIRCUIT_TYPE,
+            &binary,
+            &*non_determinism,
+            None,
+            None,
+            vec![(second_range.start, checkpoint_sender)],
+        );
+        let mut checkpoints = vec![None, Some(checkpoint_receiver.try_recv().unwrap())];
+        let mut traced_ranges = vec![];
+        let mut chunks_traced_counts = vec![];
+        // the upper bound is far above the actual count, the workers still get a half each
+        for split_index in 0..2 {
+            trace_cycles::<IMStandardIsaConfig, _, Global>(
+                0,
+                split_index,
+                1 << 10,
+                CIRCUIT_TYPE,
+                binary.clone(),
+                non_determinism.clone(),
+                [0; 32],
+                RAM_SIZE,
+                checkpoints[split_index].take(),
+                HashSet::new(),
+                main_chunks_count,
+                2,
+                split_index,
+                SplitStrategy::Contiguous,
+                free_allocator.clone(),
+                DEFAULT_FREE_ALLOCATOR_TIMEOUT,
+                results_sender.clone(),
+                None,
+            )
+            .unwrap();
+            let mut traced = vec![];
+            for result in results.try_iter() {
+                match result {
+                    WorkerResult::CyclesChunk(chunk) => traced.push(chunk.index),
+                    WorkerResult::CyclesTracingResult {
+                        chunks_traced_count,
+                    } => chunks_traced_counts.push(chunks_traced_count),
+                    _ => unreachable!(),
+                }
+            }
+            traced_ranges.push(traced);
+        }
+
+        assert_eq!(
+            traced_ranges,
+            vec![
+                (0..second_range.start).collect::<Vec<_>>(),
+                second_range.clone().collect::<Vec<_>>(),
+            ]
+        );
+        assert_eq!(second_range.start, main_chunks_count.div_ceil(2));
+        assert_eq!(second_range.end, main_chunks_count);
+        assert_eq!(chunks_traced_counts, vec![main_chunks_count]);
+    }
+
+    #[test]
+    fn test_execution_not_finished() {
+        let Fixture {
+            binary,
+            non_determinism,
+            results_sender,
+            ..
+        } = fixture(0);
+
+        let result = count_delegations::<IMStandardIsaConfig, Global>(
+            0,
+            0,
+            1,
+            CIRCUIT_TYPE,
+            binary,
+            non_determinism,
+            [0; 32],
+            RAM_SIZE,
+            results_sender,
+            None,
+        );
+
+        assert!(matches!(
+            result,
+            Err(WorkerError::ExecutionNotFinished {
+                chunks_traced: 1,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_allocator_starved() {
+        let Fixture {
+            binary,
+            non_determinism,
+            free_allocator,
+            results_sender,
+            ..
+        } = fixture(0);
+
+        let result = trace_cycles::<IMStandardIsaConfig, _, Global>(
+            0,
+            3,
+            1 << 10,
+            CIRCUIT_TYPE,
+            binary,
+            non_determinism,
+            [0; 32],
+            RAM_SIZE,
+            None,
+            HashSet::new(),
+            1 << 10,
+            1,
+            0,
+            SplitStrategy::RoundRobin,
+            free_allocator,
+            DEFAULT_FREE_ALLOCATOR_TIMEOUT,
+            results_sender,
+            None,
+        );
+
+        assert_eq!(
+            result,
+            Err(WorkerError::AllocatorStarved {
+                worker_id: 3,
+                chunk_index: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_delegations_allocator_starved() {
+        let Fixture {
+            binary,
+            non_determinism,
+            free_allocator,
+            results_sender,
+            results,
+        } = fixture(0);
+
+        let result = trace_delegations::<IMStandardIsaConfig, Global>(
+            0,
+            5,
+            1 << 10,
+            CIRCUIT_TYPE,
+            binary,
+            non_determinism,
+            [0; 32],
+            RAM_SIZE,
+            HashSet::new(),
+            free_allocator,
+            DEFAULT_FREE_ALLOCATOR_TIMEOUT,
+            results_sender,
+            None,
+        );
+
+        // the worker stops after the main chunk with the first starved delegation chunk
+        assert_eq!(
+            result,
+            Err(WorkerError::AllocatorStarved {
+                worker_id: 5,
+                chunk_index: 0,
+            })
+        );
+        assert!(results.try_iter().next().is_none());
+    }
+
+    #[test]
+    fn test_delegation_witness_consistency() {
+        let circuit_type = DelegationCircuitType::Blake2WithCompression;
+        let mut witness = circuit_type.get_witness_factory_fn()(Global);
+        assert!(witness.check_consistency().is_ok());
+
+        // a delegation without any of its register accesses
+        witness
+            .write_timestamp
+            .push(TimestampData::from_scalar(1 << 20));
+        let error = witness.check_consistency().unwrap_err();
+        assert!(error.contains("register accesses"), "{error}");
+
+        // capacity of a witness is always one less than a power of two
+        let mut witness = circuit_type.get_witness_factory_fn()(Global);
+        witness.num_requests += 1;
+        let error = witness.check_consistency().unwrap_err();
+        assert!(error.contains("power of two"), "{error}");
+    }
 }
@@ -64,6 +64,7 @@ pub struct ProofRequest<A: GoodAllocator> {
     pub precomputations: CircuitPrecomputations,
     pub tracing_data: TracingDataHost<A>,
     pub external_challenges: ExternalChallenges,
+    pub pow_start_nonce: Option<u64>,
 }
 
 pub struct ProofResult<A: GoodAllocator> {
@@ -368,6 +369,7 @@ fn gpu_worker(
                         NUM_QUERIES,
                         POW_BITS,
                         None,
+                        request.pow_start_nonce,
                         false,
                         trees_cache_mode,
                         &context,
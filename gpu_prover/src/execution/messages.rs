@@ -1,4 +1,4 @@
-use super::cpu_worker::{CyclesChunk, SetupAndTeardownChunk};
+use super::cpu_worker::{CyclesChunk, SetupAndTeardownChunk, WorkerError};
 use super::gpu_worker::{MemoryCommitmentResult, ProofResult};
 use crate::circuit_type::DelegationCircuitType;
 use fft::GoodAllocator;
@@ -10,7 +10,15 @@ pub enum WorkerResult<A: GoodAllocator> {
     SetupAndTeardownChunk(SetupAndTeardownChunk<A>),
     RAMTracingResult {
         chunks_traced_count: usize,
-        final_register_values: [FinalRegisterValue; 32],
+        /// `None` if the worker was asked not to produce final register values
+        final_register_values: Option<[FinalRegisterValue; 32]>,
+    },
+    /// Touched RAM cells after the chunk with index `chunk_index` was traced, and how many
+    /// of them were touched for the first time in that chunk
+    TouchedRamStats {
+        chunk_index: usize,
+        touched_cells: usize,
+        delta: usize,
     },
     CyclesChunk(CyclesChunk<A>),
     CyclesTracingResult {
@@ -23,6 +31,11 @@ pub enum WorkerResult<A: GoodAllocator> {
     DelegationTracingResult {
         delegation_chunks_counts: HashMap<DelegationCircuitType, usize>,
     },
+    DelegationCountingResult {
+        delegation_counts: HashMap<DelegationCircuitType, usize>,
+    },
     MemoryCommitment(MemoryCommitmentResult<A>),
     Proof(ProofResult<A>),
+    /// CPU worker stopped before producing all of its results
+    WorkerError(WorkerError),
 }
@@ -1,5 +1,11 @@
+pub use super::cpu_worker::{
+    create_simulation_checkpoint, estimate_circuit_counts, BatchResumeState, CircuitCounts,
+    ProgressCallback, ProgressEvent, SimulationCheckpoint, SplitStrategy, WorkerError,
+    DEFAULT_FREE_ALLOCATOR_TIMEOUT, DEFAULT_SETUP_TEARDOWN_FILL_RATIO,
+};
 use super::cpu_worker::{
-    get_cpu_worker_func, CpuWorkerMode, CyclesChunk, NonDeterminism, SetupAndTeardownChunk,
+    get_contiguous_range, get_cpu_worker_func, get_ram_size, send_error_to_results,
+    send_simulation_checkpoints, CpuWorkerMode, CyclesChunk, NonDeterminism, SetupAndTeardownChunk,
 };
 use super::gpu_manager::{GpuManager, GpuWorkBatch};
 use super::gpu_worker::{
@@ -32,7 +38,7 @@ use prover::risc_v_simulator::abstractions::tracer::{
 };
 use prover::risc_v_simulator::cycle::{
     IMStandardIsaConfig, IMWithoutSignedMulDivIsaConfig, IWithoutByteAccessIsaConfig,
-    IWithoutByteAccessIsaConfigWithDelegation,
+    IWithoutByteAccessIsaConfigWithDelegation, MachineConfig,
 };
 use prover::tracers::main_cycle_optimized::SingleCycleTracingData;
 use prover::ShuffleRamSetupAndTeardown;
@@ -43,9 +49,8 @@ use std::cmp::min;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::Debug;
 use std::hash::Hash;
-use std::ops::Deref;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use trace_and_split::{fs_transform_for_memory_and_delegation_arguments, FinalRegisterValue};
 use worker::Worker;
 
@@ -69,6 +74,29 @@ pub struct ExecutableBinary<K: Clone + Debug + Eq + Hash, B: Into<Box<[u32]>>> {
     pub bytecode: B,
 }
 
+/// Settings that make proofs of a batch reproducible bit for bit.
+/// CPU workers are already deterministic for a given binary and non-determinism source,
+/// so only the proof of work nonce search needs to be pinned.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReproducibilityConfig {
+    /// Every main and delegation circuit proof searches the proof of work nonce starting from
+    /// this value instead of the nonce range of the prover context. The search returns the
+    /// smallest valid nonce, so the result doesn't depend on the GPU that produced the proof.
+    pub pow_start_nonce: Option<u64>,
+}
+
+/// Touched RAM cells after the main circuit chunk with index `chunk_index` was traced,
+/// and how many of them were touched for the first time in that chunk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TouchedRamStats {
+    pub chunk_index: usize,
+    pub touched_cells: usize,
+    pub delta: usize,
+}
+
+/// Callback that receives touched RAM stats of every main circuit chunk, in chunk order.
+pub type TouchedRamStatsCallback = Arc<dyn Fn(TouchedRamStats) + Send + Sync>;
+
 struct BinaryHolder {
     circuit_type: MainCircuitType,
     bytecode: Arc<Box<[u32]>>,
@@ -84,6 +112,16 @@ pub struct ExecutionProver<K: Debug + Eq + Hash> {
     delegation_circuits_precomputations: HashMap<DelegationCircuitType, CircuitPrecomputations>,
     free_allocator_sender: Sender<A>,
     free_allocator_receiver: Receiver<A>,
+    progress_callback: Option<ProgressCallback>,
+    cycles_split_strategy: SplitStrategy,
+    free_allocator_timeout: Duration,
+    setup_teardown_fill_ratio: f64,
+    reproducibility_config: ReproducibilityConfig,
+    initial_registers: Option<[u32; 32]>,
+    max_ram_log_size: Option<u32>,
+    want_final_registers: bool,
+    touched_ram_stats_callback: Option<TouchedRamStatsCallback>,
+    resume_state: Option<BatchResumeState>,
 }
 
 struct ChunksCacheEntry<A: GoodAllocator> {
@@ -118,6 +156,85 @@ impl<A: GoodAllocator> ChunksCache<A> {
     }
 }
 
+/// Inconsistency in `circuit_sequence` numbering of the chunks of some delegation circuit
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DelegationSequenceError {
+    Duplicate {
+        circuit_type: DelegationCircuitType,
+        circuit_sequence: usize,
+    },
+    Gap {
+        circuit_type: DelegationCircuitType,
+        missing_sequence: usize,
+    },
+    CountMismatch {
+        circuit_type: DelegationCircuitType,
+        expected_count: usize,
+        actual_count: usize,
+    },
+}
+
+impl std::fmt::Display for DelegationSequenceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DelegationSequenceError::Duplicate {
+                circuit_type,
+                circuit_sequence,
+            } => write!(
+                f,
+                "delegation circuit {circuit_type:?} has more than one chunk with sequence {circuit_sequence}"
+            ),
+            DelegationSequenceError::Gap {
+                circuit_type,
+                missing_sequence,
+            } => write!(
+                f,
+                "delegation circuit {circuit_type:?} is missing chunk with sequence {missing_sequence}"
+            ),
+            DelegationSequenceError::CountMismatch {
+                circuit_type,
+                expected_count,
+                actual_count,
+            } => write!(
+                f,
+                "delegation circuit {circuit_type:?} has {actual_count} chunk(s) while {expected_count} were traced"
+            ),
+        }
+    }
+}
+
+/// Checks that chunks of the delegation circuit are numbered exactly as `0..expected_count`,
+/// otherwise the resulting set of proofs can not be represented in the recursion
+pub fn validate_delegation_circuit_sequences(
+    circuit_type: DelegationCircuitType,
+    sequences: impl IntoIterator<Item = usize>,
+    expected_count: usize,
+) -> Result<(), DelegationSequenceError> {
+    let sequences = sequences.into_iter().sorted().collect_vec();
+    for (index, &circuit_sequence) in sequences.iter().enumerate() {
+        if index != 0 && sequences[index - 1] == circuit_sequence {
+            return Err(DelegationSequenceError::Duplicate {
+                circuit_type,
+                circuit_sequence,
+            });
+        }
+        if circuit_sequence != index {
+            return Err(DelegationSequenceError::Gap {
+                circuit_type,
+                missing_sequence: index,
+            });
+        }
+    }
+    if sequences.len() != expected_count {
+        return Err(DelegationSequenceError::CountMismatch {
+            circuit_type,
+            expected_count,
+            actual_count: sequences.len(),
+        });
+    }
+    Ok(())
+}
+
 impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
     ///  Creates a new instance of `ExecutionProver`.
     ///
@@ -268,9 +385,87 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
             delegation_circuits_precomputations,
             free_allocator_sender,
             free_allocator_receiver,
+            progress_callback: None,
+            cycles_split_strategy: SplitStrategy::RoundRobin,
+            free_allocator_timeout: DEFAULT_FREE_ALLOCATOR_TIMEOUT,
+            setup_teardown_fill_ratio: DEFAULT_SETUP_TEARDOWN_FILL_RATIO,
+            reproducibility_config: ReproducibilityConfig::default(),
+            initial_registers: None,
+            max_ram_log_size: None,
+            want_final_registers: true,
+            touched_ram_stats_callback: None,
+            resume_state: None,
         }
     }
 
+    /// Sets the callback that CPU workers of subsequent batches invoke after every simulated chunk.
+    /// The callback runs synchronously inside the simulation loop, so it must return quickly.
+    pub fn set_progress_callback(&mut self, progress_callback: Option<ProgressCallback>) {
+        self.progress_callback = progress_callback;
+    }
+
+    /// Sets how main circuit chunks are split between cycles tracing workers of subsequent batches.
+    pub fn set_cycles_split_strategy(&mut self, split_strategy: SplitStrategy) {
+        self.cycles_split_strategy = split_strategy;
+    }
+
+    /// Sets how long CPU workers of subsequent batches wait for a free allocator before failing
+    /// with `WorkerError::AllocatorStarved`.
+    pub fn set_free_allocator_timeout(&mut self, timeout: Duration) {
+        self.free_allocator_timeout = timeout;
+    }
+
+    /// Sets the expected fill ratio of setup and teardown chunks for subsequent batches,
+    /// must be in `(0.0, 1.0]`. Values below 1.0 declare fewer chunks as not needing
+    /// setup and teardown data, which is more conservative for sparse memory accesses.
+    pub fn set_setup_teardown_fill_ratio(&mut self, ratio: f64) {
+        assert!(ratio > 0.0 && ratio <= 1.0);
+        self.setup_teardown_fill_ratio = ratio;
+    }
+
+    /// Sets the reproducibility settings applied to all proofs of subsequent batches.
+    pub fn set_reproducibility_config(&mut self, config: ReproducibilityConfig) {
+        self.reproducibility_config = config;
+    }
+
+    /// Sets the register values the simulation of subsequent batches starts with,
+    /// `None` starts with all registers set to zero. Register x0 must be zero.
+    pub fn set_initial_registers(&mut self, initial_registers: Option<[u32; 32]>) {
+        assert!(initial_registers.is_none_or(|registers| registers[0] == 0));
+        self.initial_registers = initial_registers;
+    }
+
+    /// Bounds the RAM simulated by CPU workers of subsequent batches to `2^max_ram_log_size` bytes,
+    /// `None` simulates the whole RAM. Smaller RAM makes workers start faster and use less memory.
+    pub fn set_max_ram_log_size(&mut self, max_ram_log_size: Option<u32>) {
+        // panics for sizes that are out of range
+        get_ram_size(max_ram_log_size);
+        self.max_ram_log_size = max_ram_log_size;
+    }
+
+    /// Sets if `commit_memory` and `prove` of subsequent batches return final register values,
+    /// they return `None` instead when set to false. `commit_memory_and_prove` always returns them,
+    /// as memory challenges are derived from them.
+    pub fn set_want_final_registers(&mut self, want_final_registers: bool) {
+        self.want_final_registers = want_final_registers;
+    }
+
+    /// Sets the callback that receives touched RAM stats of subsequent batches, stats are only
+    /// collected if a callback is set. The callback runs on the thread that waits for the results
+    /// of the batch, so it delays processing of other results until it returns.
+    pub fn set_touched_ram_stats_callback(&mut self, callback: Option<TouchedRamStatsCallback>) {
+        self.touched_ram_stats_callback = callback;
+    }
+
+    /// Sets the chunks that `commit_memory` and `prove` of subsequent batches do not produce again,
+    /// the returned commitments and proofs are those of the remaining chunks in sequence order.
+    /// The state applies until it is reset with `None`, so it must be reset before the next batch
+    /// of a different binary. `commit_memory_and_prove` needs all memory commitments and can not
+    /// be resumed.
+    pub fn set_resume_state(&mut self, resume_state: Option<BatchResumeState>) {
+        self.resume_state = resume_state;
+    }
+
     fn get_results(
         &self,
         proving: bool,
@@ -280,18 +475,35 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
         num_instances_upper_bound: usize,
         non_determinism_source: impl NonDeterminism + Send + Sync + 'static,
         external_challenges: Option<ExternalChallenges>,
-    ) -> (
-        [FinalRegisterValue; 32],
-        Vec<Vec<MerkleTreeCapVarLength>>,
-        Vec<(u32, Vec<Vec<MerkleTreeCapVarLength>>)>,
-        Vec<Proof>,
-        Vec<(u32, Vec<Proof>)>,
-    ) {
+        want_final_registers: bool,
+    ) -> Result<
+        (
+            Option<[FinalRegisterValue; 32]>,
+            Vec<Vec<MerkleTreeCapVarLength>>,
+            Vec<(u32, Vec<Vec<MerkleTreeCapVarLength>>)>,
+            Vec<Proof>,
+            Vec<(u32, Vec<Proof>)>,
+        ),
+        WorkerError,
+    > {
         assert!(proving ^ external_challenges.is_none());
         let binary = &self.binaries[&binary_key];
         let trace_len = binary.precomputations.compiled_circuit.trace_len;
         assert!(trace_len.is_power_of_two());
         let cycles_per_circuit = trace_len - 1;
+        // the contiguous split needs the actual number of chunks to balance the ranges
+        let split_chunks_count = match self.cycles_split_strategy {
+            SplitStrategy::RoundRobin => num_instances_upper_bound,
+            SplitStrategy::Contiguous => {
+                self.estimate_circuit_counts(
+                    binary.circuit_type,
+                    &binary.bytecode,
+                    &non_determinism_source,
+                    num_instances_upper_bound,
+                )?
+                .main_chunks_count
+            }
+        };
         let (work_results_sender, worker_results_receiver) = unbounded();
         let (gpu_work_requests_sender, gpu_work_requests_receiver) = unbounded();
         let gpu_work_batch = GpuWorkBatch {
@@ -310,7 +522,12 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
                     .collect::<HashSet<(CircuitType, usize)>>()
             })
             .unwrap_or_default();
-        let mut main_work_requests_count = 0;
+        let resume_state = self.resume_state.clone().unwrap_or_default();
+        let completed_main_chunks_count = resume_state
+            .completed_sequences(CircuitType::Main(binary.circuit_type))
+            .count();
+        // completed chunks are never sent, but are counted towards the total number of chunks
+        let mut main_work_requests_count = completed_main_chunks_count;
         if proving {
             if let Some(cache) = chunks_cache.take() {
                 let external_challenges = external_challenges.unwrap();
@@ -331,6 +548,7 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
                                 precomputations,
                                 tracing_data,
                                 external_challenges,
+                                pow_start_nonce: self.reproducibility_config.pow_start_nonce,
                             };
                             let request = GpuWorkRequest::Proof(request);
                             trace!("BATCH[{batch_id}] PROVER sending cached main circuit {main_circuit_type:?} chunk {circuit_sequence} proof request to GPU manager");
@@ -348,6 +566,7 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
                                 precomputations,
                                 tracing_data,
                                 external_challenges,
+                                pow_start_nonce: self.reproducibility_config.pow_start_nonce,
                             };
                             let request = GpuWorkRequest::Proof(request);
                             trace!("BATCH[{batch_id}] PROVER sending cached delegation circuit {delegation_circuit_type:?} chunk {circuit_sequence} proof request to GPU manager");
@@ -358,57 +577,44 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
             }
         }
         trace!("BATCH[{batch_id}] PROVER spawning CPU workers");
-        let non_determinism_source = Arc::new(non_determinism_source);
-        let mut cpu_worker_id = 0;
+        let mut modes = vec![];
         let ram_tracing_mode = CpuWorkerMode::TraceTouchedRam {
             circuit_type: binary.circuit_type,
             skip_set: skip_set.clone(),
             free_allocator: self.free_allocator_receiver.clone(),
+            free_allocator_timeout: self.free_allocator_timeout,
+            setup_teardown_fill_ratio: self.setup_teardown_fill_ratio,
+            want_final_registers,
+            collect_stats: self.touched_ram_stats_callback.is_some(),
         };
-        self.spawn_cpu_worker(
-            binary.circuit_type,
-            batch_id,
-            cpu_worker_id,
-            num_instances_upper_bound,
-            binary.bytecode.clone(),
-            non_determinism_source.clone(),
-            ram_tracing_mode,
-            work_results_sender.clone(),
-        );
-        cpu_worker_id += 1;
+        modes.push(ram_tracing_mode);
         for split_index in 0..CYCLES_TRACING_WORKERS_COUNT {
             let ram_tracing_mode = CpuWorkerMode::TraceCycles {
                 circuit_type: binary.circuit_type,
                 skip_set: skip_set.clone(),
+                split_chunks_count,
                 split_count: CYCLES_TRACING_WORKERS_COUNT,
                 split_index,
+                split_strategy: self.cycles_split_strategy,
                 free_allocator: self.free_allocator_receiver.clone(),
+                free_allocator_timeout: self.free_allocator_timeout,
             };
-            self.spawn_cpu_worker(
-                binary.circuit_type,
-                batch_id,
-                cpu_worker_id,
-                num_instances_upper_bound,
-                binary.bytecode.clone(),
-                non_determinism_source.clone(),
-                ram_tracing_mode,
-                work_results_sender.clone(),
-            );
-            cpu_worker_id += 1;
+            modes.push(ram_tracing_mode);
         }
         let delegation_mode = CpuWorkerMode::TraceDelegations {
             circuit_type: binary.circuit_type,
             skip_set,
             free_allocator: self.free_allocator_receiver.clone(),
+            free_allocator_timeout: self.free_allocator_timeout,
         };
-        self.spawn_cpu_worker(
+        modes.push(delegation_mode);
+        self.spawn_cpu_workers(
             binary.circuit_type,
             batch_id,
-            cpu_worker_id,
             num_instances_upper_bound,
             binary.bytecode.clone(),
-            non_determinism_source.clone(),
-            delegation_mode,
+            Arc::new(non_determinism_source),
+            modes,
             work_results_sender.clone(),
         );
         trace!("BATCH[{batch_id}] PROVER CPU workers spawned");
@@ -448,6 +654,7 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
                         precomputations,
                         tracing_data,
                         external_challenges: external_challenges.unwrap(),
+                        pow_start_nonce: self.reproducibility_config.pow_start_nonce,
                     };
                     GpuWorkRequest::Proof(proof_request)
                 } else {
@@ -468,6 +675,7 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
                 gpu_work_requests_sender.send(request).unwrap();
             };
         let mut send_main_work_request = Some(send_main_work_request);
+        let mut worker_error = None;
         for result in worker_results_receiver {
             match result {
                 WorkerResult::SetupAndTeardownChunk(chunk) => {
@@ -476,7 +684,9 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
                         chunk: setup_and_teardown_chunk,
                     } = chunk;
                     trace!("BATCH[{batch_id}] PROVER received setup and teardown chunk {index}");
-                    if let Some(cycles_chunk) = cycles_chunks.remove(&index) {
+                    if worker_error.is_some() {
+                        self.release_setup_and_teardown_chunk(setup_and_teardown_chunk);
+                    } else if let Some(cycles_chunk) = cycles_chunks.remove(&index) {
                         let send = send_main_work_request.as_ref().unwrap();
                         send(index, setup_and_teardown_chunk, cycles_chunk);
                         main_work_requests_count += 1;
@@ -488,15 +698,32 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
                     chunks_traced_count,
                     final_register_values: values,
                 } => {
-                    trace!("BATCH[{batch_id}] PROVER received RAM tracing result with final register values and {chunks_traced_count} chunk(s) traced");
+                    trace!("BATCH[{batch_id}] PROVER received RAM tracing result with {chunks_traced_count} chunk(s) traced");
                     let previous_count = final_main_chunks_count.replace(chunks_traced_count);
                     assert!(previous_count.is_none_or(|v| v == chunks_traced_count));
-                    final_register_values = Some(values);
+                    assert_eq!(values.is_some(), want_final_registers);
+                    final_register_values = values;
+                }
+                WorkerResult::TouchedRamStats {
+                    chunk_index,
+                    touched_cells,
+                    delta,
+                } => {
+                    trace!("BATCH[{batch_id}] PROVER received touched RAM stats for chunk {chunk_index}: {touched_cells} cell(s) touched, {delta} new");
+                    let callback = self.touched_ram_stats_callback.as_ref().unwrap();
+                    callback(TouchedRamStats {
+                        chunk_index,
+                        touched_cells,
+                        delta,
+                    });
                 }
                 WorkerResult::CyclesChunk(chunk) => {
                     let CyclesChunk { index, data } = chunk;
                     trace!("BATCH[{batch_id}] PROVER received cycles chunk {index}");
-                    if let Some(setup_and_teardown_chunk) = setup_and_teardown_chunks.remove(&index)
+                    if worker_error.is_some() {
+                        self.release_allocator(data.per_cycle_data.allocator().clone(), data);
+                    } else if let Some(setup_and_teardown_chunk) =
+                        setup_and_teardown_chunks.remove(&index)
                     {
                         let send = send_main_work_request.as_ref().unwrap();
                         send(index, setup_and_teardown_chunk, data);
@@ -518,7 +745,10 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
                 } => {
                     let id = witness.delegation_type;
                     let delegation_circuit_type = DelegationCircuitType::from(id);
-                    if witness.write_timestamp.is_empty() {
+                    if worker_error.is_some() {
+                        let allocator = witness.write_timestamp.allocator().clone();
+                        self.release_allocator(allocator, witness);
+                    } else if witness.write_timestamp.is_empty() {
                         trace!("BATCH[{batch_id}] PROVER skipping empty delegation circuit {delegation_circuit_type:?} chunk {circuit_sequence}");
                         let allocator = witness.write_timestamp.allocator().clone();
                         drop(witness);
@@ -539,6 +769,7 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
                                 precomputations,
                                 tracing_data,
                                 external_challenges: external_challenges.unwrap(),
+                                pow_start_nonce: self.reproducibility_config.pow_start_nonce,
                             };
                             trace!("BATCH[{batch_id}] PROVER sending delegation circuit {delegation_circuit_type:?} chunk {circuit_sequence} proof request");
                             GpuWorkRequest::Proof(proof_request)
@@ -575,6 +806,28 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
                     );
                     delegation_work_sender = None;
                 }
+                WorkerResult::DelegationCountingResult { .. } => unreachable!(),
+                WorkerResult::WorkerError(error) => {
+                    trace!("BATCH[{batch_id}] PROVER received CPU worker error: {error}");
+                    if worker_error.is_none() {
+                        // no more work is sent to GPUs, the loop ends once the requests that were
+                        // already sent are done and the remaining CPU workers stop
+                        worker_error = Some(error);
+                        send_main_work_request = None;
+                        delegation_work_sender = None;
+                        for (_, chunk) in setup_and_teardown_chunks.drain() {
+                            self.release_setup_and_teardown_chunk(chunk);
+                        }
+                        for (_, data) in cycles_chunks.drain() {
+                            self.release_allocator(data.per_cycle_data.allocator().clone(), data);
+                        }
+                        if let Some(cache) = chunks_cache.take() {
+                            for entry in cache.queue {
+                                self.release_tracing_data(entry.tracing_data);
+                            }
+                        }
+                    }
+                }
                 WorkerResult::MemoryCommitment(commitment) => {
                     assert!(!proving);
                     let MemoryCommitmentResult {
@@ -646,11 +899,18 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
                                 assert_eq!(allocator.get_used_mem_current(), 0);
                                 self.free_allocator_sender.send(allocator).unwrap();
                             }
-                            assert!(delegation_memory_commitments
+                            if delegation_memory_commitments
                                 .entry(circuit_type)
                                 .or_insert_with(HashMap::new)
                                 .insert(circuit_sequence, merkle_tree_caps)
-                                .is_none());
+                                .is_some()
+                            {
+                                let error = DelegationSequenceError::Duplicate {
+                                    circuit_type,
+                                    circuit_sequence,
+                                };
+                                panic!("BATCH[{batch_id}] PROVER {error}");
+                            }
                         }
                     }
                 }
@@ -692,11 +952,18 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
                             drop(witness);
                             assert_eq!(allocator.get_used_mem_current(), 0);
                             self.free_allocator_sender.send(allocator).unwrap();
-                            assert!(delegation_proofs
+                            if delegation_proofs
                                 .entry(circuit_type)
                                 .or_insert_with(HashMap::new)
                                 .insert(circuit_sequence, proof)
-                                .is_none());
+                                .is_some()
+                            {
+                                let error = DelegationSequenceError::Duplicate {
+                                    circuit_type,
+                                    circuit_sequence,
+                                };
+                                panic!("BATCH[{batch_id}] PROVER {error}");
+                            }
                         }
                     }
                 }
@@ -710,29 +977,50 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
                 }
             }
         }
+        if let Some(error) = worker_error {
+            return Err(error);
+        }
         assert!(send_main_work_request.is_none());
         assert!(delegation_work_sender.is_none());
         assert!(setup_and_teardown_chunks.is_empty());
         assert!(cycles_chunks.is_empty());
         let final_main_chunks_count = final_main_chunks_count.unwrap();
         assert_ne!(final_main_chunks_count, 0);
-        let final_register_values = final_register_values.unwrap();
+        let validate_sequences = |circuit_type, sequences: Option<Vec<usize>>, count| {
+            let mut sequences = sequences.unwrap_or_default();
+            sequences
+                .extend(resume_state.completed_sequences(CircuitType::Delegation(circuit_type)));
+            if let Err(error) =
+                validate_delegation_circuit_sequences(circuit_type, sequences, count)
+            {
+                panic!("BATCH[{batch_id}] PROVER {error}");
+            }
+        };
         if proving {
             assert!(main_memory_commitments.is_empty());
             assert!(delegation_memory_commitments.is_empty());
-            assert_eq!(main_proofs.len(), final_main_chunks_count);
-            for (id, count) in final_delegation_chunks_counts.unwrap() {
-                assert_eq!(count, delegation_proofs.get(&id.into()).unwrap().len());
+            assert_eq!(
+                main_proofs.len() + completed_main_chunks_count,
+                final_main_chunks_count
+            );
+            for (circuit_type, count) in final_delegation_chunks_counts.unwrap() {
+                let sequences = delegation_proofs
+                    .get(&circuit_type)
+                    .map(|proofs| proofs.keys().copied().collect_vec());
+                validate_sequences(circuit_type, sequences, count);
             }
         } else {
             assert!(main_proofs.is_empty());
             assert!(delegation_proofs.is_empty());
-            assert_eq!(main_memory_commitments.len(), final_main_chunks_count);
-            for (id, count) in final_delegation_chunks_counts.unwrap() {
-                assert_eq!(
-                    count,
-                    delegation_memory_commitments.get(&id.into()).unwrap().len()
-                );
+            assert_eq!(
+                main_memory_commitments.len() + completed_main_chunks_count,
+                final_main_chunks_count
+            );
+            for (circuit_type, count) in final_delegation_chunks_counts.unwrap() {
+                let sequences = delegation_memory_commitments
+                    .get(&circuit_type)
+                    .map(|commitments| commitments.keys().copied().collect_vec());
+                validate_sequences(circuit_type, sequences, count);
             }
         }
         let main_memory_commitments = main_memory_commitments
@@ -769,13 +1057,45 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
                 (t as u32, proofs)
             })
             .collect_vec();
-        (
+        Ok((
             final_register_values,
             main_memory_commitments,
             delegation_memory_commitments,
             main_proofs,
             delegation_proofs,
-        )
+        ))
+    }
+
+    // returns the allocator of data that is not sent to GPUs back to the pool of free allocators
+    fn release_allocator(&self, allocator: A, data: impl Sized) {
+        drop(data);
+        assert_eq!(allocator.get_used_mem_current(), 0);
+        self.free_allocator_sender.send(allocator).unwrap();
+    }
+
+    fn release_setup_and_teardown_chunk(&self, chunk: Option<ShuffleRamSetupAndTeardown<A>>) {
+        if let Some(chunk) = chunk {
+            self.release_allocator(chunk.lazy_init_data.allocator().clone(), chunk);
+        }
+    }
+
+    fn release_tracing_data(&self, tracing_data: TracingDataHost<A>) {
+        match tracing_data {
+            TracingDataHost::Main {
+                setup_and_teardown,
+                trace,
+            } => {
+                if let Some(setup_and_teardown) = setup_and_teardown {
+                    let allocator = setup_and_teardown.lazy_init_data.allocator().clone();
+                    self.release_allocator(allocator, setup_and_teardown);
+                }
+                self.release_allocator(trace.cycle_data.allocator().clone(), trace);
+            }
+            TracingDataHost::Delegation(witness) => {
+                let allocator = witness.write_timestamp.allocator().clone();
+                self.release_allocator(allocator, witness);
+            }
+        }
     }
 
     fn commit_memory_inner(
@@ -785,11 +1105,15 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
         binary_key: &K,
         num_instances_upper_bound: usize,
         non_determinism_source: impl NonDeterminism + Send + Sync + 'static,
-    ) -> (
-        [FinalRegisterValue; 32],
-        Vec<Vec<MerkleTreeCapVarLength>>,
-        Vec<(u32, Vec<Vec<MerkleTreeCapVarLength>>)>,
-    ) {
+        want_final_registers: bool,
+    ) -> Result<
+        (
+            Option<[FinalRegisterValue; 32]>,
+            Vec<Vec<MerkleTreeCapVarLength>>,
+            Vec<(u32, Vec<Vec<MerkleTreeCapVarLength>>)>,
+        ),
+        WorkerError,
+    > {
         info!(
             "BATCH[{batch_id}] PROVER producing memory commitments for binary with key {:?}",
             &binary_key
@@ -809,7 +1133,8 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
             num_instances_upper_bound,
             non_determinism_source,
             None,
-        );
+            want_final_registers,
+        )?;
         assert!(main_proofs.is_empty());
         assert!(delegation_proofs.is_empty());
         info!(
@@ -817,11 +1142,11 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
             binary_key,
             timer.elapsed().as_secs_f64()
         );
-        (
+        Ok((
             final_register_values,
             main_memory_commitments,
             delegation_memory_commitments,
-        )
+        ))
     }
 
     ///  Produces memory commitments.
@@ -834,9 +1159,10 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
     /// * `non_determinism_source`: a value implementing the `NonDeterminism` trait that provides non-deterministic values for the simulation
     ///
     /// returns: a tuple containing:
-    ///     - final register values for the main circuit,
+    ///     - final register values for the main circuit, `None` if disabled with `set_want_final_registers`,
     ///     - a vector of memory commitments for the chunks of the main circuit,
     ///     - a vector of memory commitments for the chunks of the delegation circuits, where each element is a tuple containing the delegation circuit type and a vector of memory commitments for that type
+    ///   or the first error reported by the CPU workers, for example if execution did not finish within `num_instances_upper_bound`
     ///
     pub fn commit_memory(
         &self,
@@ -844,20 +1170,63 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
         binary_key: &K,
         num_instances_upper_bound: usize,
         non_determinism_source: impl NonDeterminism + Send + Sync + 'static,
-    ) -> (
-        [FinalRegisterValue; 32],
-        Vec<Vec<MerkleTreeCapVarLength>>,
-        Vec<(u32, Vec<Vec<MerkleTreeCapVarLength>>)>,
-    ) {
+    ) -> Result<
+        (
+            Option<[FinalRegisterValue; 32]>,
+            Vec<Vec<MerkleTreeCapVarLength>>,
+            Vec<(u32, Vec<Vec<MerkleTreeCapVarLength>>)>,
+        ),
+        WorkerError,
+    > {
         self.commit_memory_inner(
             &mut None,
             batch_id,
             binary_key,
             num_instances_upper_bound,
             non_determinism_source,
+            self.want_final_registers,
         )
     }
 
+    ///  Counts delegation requests of the provided binary without producing any witness, so it can be used to size the allocations before proving
+    ///
+    /// # Arguments
+    ///
+    /// * `batch_id`: a unique identifier for the batch of work, used to distinguish batches in a multithreaded scenario
+    /// * `binary_key`: a key that identifies the binary to work with, this key must match one of the keys in the `binaries` map provided during the creation of the `ExecutionProver`
+    /// * `num_instances_upper_bound`: maximum number of main circuit instances that the prover will try to simulate, if the simulation does not end within this limit, it will fail
+    /// * `non_determinism_source`: a value implementing the `NonDeterminism` trait that provides non-deterministic values for the simulation
+    ///
+    /// returns: a map from the delegation circuit type to the number of delegation requests of that type, delegation types that were never requested are absent from the map, or the error reported by the CPU worker
+    ///
+    pub fn count_delegations(
+        &self,
+        batch_id: u64,
+        binary_key: &K,
+        num_instances_upper_bound: usize,
+        non_determinism_source: impl NonDeterminism + Send + Sync + 'static,
+    ) -> Result<HashMap<DelegationCircuitType, usize>, WorkerError> {
+        let binary = &self.binaries[&binary_key];
+        let (results_sender, results_receiver) = unbounded();
+        let mode = CpuWorkerMode::CountDelegations {
+            circuit_type: binary.circuit_type,
+        };
+        self.spawn_cpu_workers(
+            binary.circuit_type,
+            batch_id,
+            num_instances_upper_bound,
+            binary.bytecode.clone(),
+            Arc::new(non_determinism_source),
+            vec![mode],
+            results_sender,
+        );
+        match results_receiver.recv().unwrap() {
+            WorkerResult::DelegationCountingResult { delegation_counts } => Ok(delegation_counts),
+            WorkerResult::WorkerError(error) => Err(error),
+            _ => unreachable!(),
+        }
+    }
+
     fn prove_inner(
         &self,
         chunks_cache: &mut Option<ChunksCache<A>>,
@@ -866,7 +1235,15 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
         num_instances_upper_bound: usize,
         non_determinism_source: impl NonDeterminism + Send + Sync + 'static,
         external_challenges: ExternalChallenges,
-    ) -> ([FinalRegisterValue; 32], Vec<Proof>, Vec<(u32, Vec<Proof>)>) {
+        want_final_registers: bool,
+    ) -> Result<
+        (
+            Option<[FinalRegisterValue; 32]>,
+            Vec<Proof>,
+            Vec<(u32, Vec<Proof>)>,
+        ),
+        WorkerError,
+    > {
         info!(
             "BATCH[{batch_id}] PROVER producing proofs for binary with key {:?}",
             &binary_key
@@ -886,7 +1263,8 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
             num_instances_upper_bound,
             non_determinism_source,
             Some(external_challenges),
-        );
+            want_final_registers,
+        )?;
         assert!(main_memory_commitments.is_empty());
         assert!(delegation_memory_commitments.is_empty());
         info!(
@@ -894,7 +1272,7 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
             binary_key,
             timer.elapsed().as_secs_f64()
         );
-        (final_register_values, main_proofs, delegation_proofs)
+        Ok((final_register_values, main_proofs, delegation_proofs))
     }
 
     ///  Produces proofs.
@@ -908,9 +1286,10 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
     /// * `external_challenges`: an instance of `ExternalChallenges` that contains the challenges to be used in the proof generation
     ///
     /// returns: a tuple containing:
-    ///     - final register values for the main circuit,
+    ///     - final register values for the main circuit, `None` if disabled with `set_want_final_registers`,
     ///     - a vector of proofs for the chunks of the main circuit,
     ///     - a vector of proofs for the chunks of the delegation circuits, where each element is a tuple containing the delegation circuit type and a vector of memory commitments for that type
+    ///   or the first error reported by the CPU workers, for example if execution did not finish within `num_instances_upper_bound`
     ///
     pub fn prove(
         &self,
@@ -919,7 +1298,14 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
         num_instances_upper_bound: usize,
         non_determinism_source: impl NonDeterminism + Send + Sync + 'static,
         external_challenges: ExternalChallenges,
-    ) -> ([FinalRegisterValue; 32], Vec<Proof>, Vec<(u32, Vec<Proof>)>) {
+    ) -> Result<
+        (
+            Option<[FinalRegisterValue; 32]>,
+            Vec<Proof>,
+            Vec<(u32, Vec<Proof>)>,
+        ),
+        WorkerError,
+    > {
         self.prove_inner(
             &mut None,
             batch_id,
@@ -927,6 +1313,7 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
             num_instances_upper_bound,
             non_determinism_source,
             external_challenges,
+            self.want_final_registers,
         )
     }
 
@@ -943,6 +1330,7 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
     ///     - final register values for the main circuit,
     ///     - a vector of proofs for the chunks of the main circuit,
     ///     - a vector of proofs for the chunks of the delegation circuits, where each element is a tuple containing the delegation circuit type and a vector of memory commitments for that type
+    ///   or the first error reported by the CPU workers, for example if execution did not finish within `num_instances_upper_bound`
     ///
     pub fn commit_memory_and_prove(
         &self,
@@ -950,7 +1338,11 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
         binary_key: &K,
         num_instances_upper_bound: usize,
         non_determinism_source: impl NonDeterminism + Clone + Send + Sync + 'static,
-    ) -> ([FinalRegisterValue; 32], Vec<Proof>, Vec<(u32, Vec<Proof>)>) {
+    ) -> Result<([FinalRegisterValue; 32], Vec<Proof>, Vec<(u32, Vec<Proof>)>), WorkerError> {
+        assert!(
+            self.resume_state.is_none(),
+            "memory challenges need commitments of all chunks, so the batch can not be resumed"
+        );
         let timer = Instant::now();
         let cache_capacity = self.device_count * 2;
         let mut chunks_cache = Some(ChunksCache::new(cache_capacity));
@@ -961,7 +1353,9 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
                 binary_key,
                 num_instances_upper_bound,
                 non_determinism_source.clone(),
-            );
+                true,
+            )?;
+        let final_register_values = final_register_values.unwrap();
         let maximum_cached_count = if CACHE_DELEGATIONS {
             main_memory_commitments.len()
                 + delegation_memory_commitments
@@ -994,17 +1388,19 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
             memory_challenges_seed,
             produce_delegation_challenge,
         );
-        let result = self.prove_inner(
+        let (prove_final_register_values, main_proofs, delegation_proofs) = self.prove_inner(
             &mut chunks_cache,
             batch_id,
             binary_key,
             num_instances_upper_bound,
             non_determinism_source,
             external_challenges,
-        );
+            self.want_final_registers,
+        )?;
         assert!(chunks_cache.is_none());
-        let (prove_final_register_values, main_proofs, delegation_proofs) = &result;
-        assert_eq!(&final_register_values, prove_final_register_values);
+        if let Some(values) = prove_final_register_values {
+            assert_eq!(final_register_values, values);
+        }
         let prove_main_memory_commitments = main_proofs
             .iter()
             .map(|p| p.memory_tree_caps.clone())
@@ -1030,74 +1426,186 @@ impl<K: Clone + Debug + Eq + Hash> ExecutionProver<K> {
             binary_key,
             timer.elapsed().as_secs_f64()
         );
-        result
+        Ok((final_register_values, main_proofs, delegation_proofs))
     }
 
-    fn spawn_cpu_worker(
+    fn estimate_circuit_counts(
+        &self,
+        circuit_type: MainCircuitType,
+        binary: &[u32],
+        non_determinism: &impl NonDeterminism,
+        num_main_chunks_upper_bound: usize,
+    ) -> Result<CircuitCounts, WorkerError> {
+        match circuit_type {
+            MainCircuitType::FinalReducedRiscVMachine => {
+                estimate_circuit_counts::<IWithoutByteAccessIsaConfig>(
+                    binary,
+                    non_determinism,
+                    circuit_type,
+                    num_main_chunks_upper_bound,
+                    self.initial_registers,
+                    self.max_ram_log_size,
+                    self.setup_teardown_fill_ratio,
+                )
+            }
+            MainCircuitType::MachineWithoutSignedMulDiv => {
+                estimate_circuit_counts::<IMWithoutSignedMulDivIsaConfig>(
+                    binary,
+                    non_determinism,
+                    circuit_type,
+                    num_main_chunks_upper_bound,
+                    self.initial_registers,
+                    self.max_ram_log_size,
+                    self.setup_teardown_fill_ratio,
+                )
+            }
+            MainCircuitType::ReducedRiscVLog23Machine | MainCircuitType::ReducedRiscVMachine => {
+                estimate_circuit_counts::<IWithoutByteAccessIsaConfigWithDelegation>(
+                    binary,
+                    non_determinism,
+                    circuit_type,
+                    num_main_chunks_upper_bound,
+                    self.initial_registers,
+                    self.max_ram_log_size,
+                    self.setup_teardown_fill_ratio,
+                )
+            }
+            MainCircuitType::RiscVCycles => estimate_circuit_counts::<IMStandardIsaConfig>(
+                binary,
+                non_determinism,
+                circuit_type,
+                num_main_chunks_upper_bound,
+                self.initial_registers,
+                self.max_ram_log_size,
+                self.setup_teardown_fill_ratio,
+            ),
+        }
+    }
+
+    fn spawn_cpu_workers<N: NonDeterminism + Send + Sync + 'static>(
         &self,
         circuit_type: MainCircuitType,
         batch_id: u64,
-        worker_id: usize,
         num_main_chunks_upper_bound: usize,
-        binary: impl Deref<Target = impl Deref<Target = [u32]>> + Send + 'static,
-        non_determinism: impl Deref<Target = impl NonDeterminism> + Send + 'static,
-        mode: CpuWorkerMode<A>,
+        binary: Arc<Box<[u32]>>,
+        non_determinism: Arc<N>,
+        modes: Vec<CpuWorkerMode<A>>,
         results: Sender<WorkerResult<A>>,
     ) {
-        let wait_group = self.wait_group.as_ref().unwrap().clone();
         match circuit_type {
-            MainCircuitType::FinalReducedRiscVMachine => {
-                let func = get_cpu_worker_func::<IWithoutByteAccessIsaConfig, _>(
-                    wait_group,
+            MainCircuitType::FinalReducedRiscVMachine => self
+                .spawn_cpu_workers_for_config::<IWithoutByteAccessIsaConfig, N>(
                     batch_id,
-                    worker_id,
                     num_main_chunks_upper_bound,
                     binary,
                     non_determinism,
-                    mode,
+                    modes,
                     results,
-                );
-                self.worker.pool.spawn(func);
-            }
-            MainCircuitType::MachineWithoutSignedMulDiv => {
-                let func = get_cpu_worker_func::<IMWithoutSignedMulDivIsaConfig, _>(
-                    wait_group,
+                ),
+            MainCircuitType::MachineWithoutSignedMulDiv => self
+                .spawn_cpu_workers_for_config::<IMWithoutSignedMulDivIsaConfig, N>(
                     batch_id,
-                    worker_id,
                     num_main_chunks_upper_bound,
                     binary,
                     non_determinism,
-                    mode,
+                    modes,
                     results,
-                );
-                self.worker.pool.spawn(func);
-            }
+                ),
             MainCircuitType::ReducedRiscVLog23Machine | MainCircuitType::ReducedRiscVMachine => {
-                let func = get_cpu_worker_func::<IWithoutByteAccessIsaConfigWithDelegation, _>(
-                    wait_group,
+                self.spawn_cpu_workers_for_config::<IWithoutByteAccessIsaConfigWithDelegation, N>(
                     batch_id,
-                    worker_id,
                     num_main_chunks_upper_bound,
                     binary,
                     non_determinism,
-                    mode,
+                    modes,
                     results,
-                );
-                self.worker.pool.spawn(func);
+                )
             }
-            MainCircuitType::RiscVCycles => {
-                let func = get_cpu_worker_func::<IMStandardIsaConfig, _>(
-                    wait_group,
+            MainCircuitType::RiscVCycles => self
+                .spawn_cpu_workers_for_config::<IMStandardIsaConfig, N>(
                     batch_id,
-                    worker_id,
                     num_main_chunks_upper_bound,
                     binary,
                     non_determinism,
-                    mode,
+                    modes,
                     results,
+                ),
+        }
+    }
+
+    fn spawn_cpu_workers_for_config<C: MachineConfig, N: NonDeterminism + Send + Sync + 'static>(
+        &self,
+        batch_id: u64,
+        num_main_chunks_upper_bound: usize,
+        binary: Arc<Box<[u32]>>,
+        non_determinism: Arc<N>,
+        modes: Vec<CpuWorkerMode<A>>,
+        results: Sender<WorkerResult<A>>,
+    ) {
+        // cycles tracing workers of the contiguous split start from the checkpoint at their range,
+        // checkpoints are produced before the workers are spawned, so the workers waiting for them
+        // can not take up the pool
+        let mut checkpoints_circuit_type = None;
+        let mut checkpoint_requests = vec![];
+        let checkpoints = modes
+            .iter()
+            .map(|mode| match mode {
+                CpuWorkerMode::TraceCycles {
+                    circuit_type,
+                    split_chunks_count,
+                    split_count,
+                    split_index,
+                    split_strategy: SplitStrategy::Contiguous,
+                    ..
+                } if *split_index != 0 => {
+                    let range =
+                        get_contiguous_range(*split_chunks_count, *split_count, *split_index);
+                    let (sender, receiver) = unbounded();
+                    checkpoints_circuit_type = Some(*circuit_type);
+                    checkpoint_requests.push((range.start, sender));
+                    Some(receiver)
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if let Some(circuit_type) = checkpoints_circuit_type {
+            let wait_group = self.wait_group.as_ref().unwrap().clone();
+            let binary = binary.clone();
+            let non_determinism = non_determinism.clone();
+            let initial_registers = self.initial_registers;
+            let max_ram_log_size = self.max_ram_log_size;
+            self.worker.pool.spawn(move || {
+                send_simulation_checkpoints::<C, N>(
+                    batch_id,
+                    circuit_type,
+                    &binary,
+                    &*non_determinism,
+                    initial_registers,
+                    max_ram_log_size,
+                    checkpoint_requests,
                 );
-                self.worker.pool.spawn(func);
-            }
+                drop(wait_group);
+            });
+        }
+        for ((worker_id, mode), checkpoint) in modes.into_iter().enumerate().zip(checkpoints) {
+            let func = get_cpu_worker_func::<C, N, A>(
+                self.wait_group.as_ref().unwrap().clone(),
+                batch_id,
+                worker_id,
+                num_main_chunks_upper_bound,
+                binary.clone(),
+                non_determinism.clone(),
+                self.initial_registers,
+                self.max_ram_log_size,
+                mode,
+                self.resume_state.clone(),
+                checkpoint,
+                results.clone(),
+                self.progress_callback.clone(),
+            );
+            self.worker
+                .pool
+                .spawn(send_error_to_results(func, results.clone()));
         }
     }
 }
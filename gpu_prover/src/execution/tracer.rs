@@ -31,9 +31,11 @@ pub struct RamTracingData<const RAM_SIZE: usize, const TRACE_TOUCHED_RAM: bool>
 impl<const RAM_SIZE: usize, const TRACE_TOUCHED_RAM: bool>
     RamTracingData<RAM_SIZE, TRACE_TOUCHED_RAM>
 {
-    pub fn new() -> Self {
-        assert_eq!(RAM_SIZE % 4, 0);
-        let num_words = RAM_SIZE / 4;
+    /// `ram_size` must match the size of the memory that is being traced
+    pub fn new(ram_size: usize) -> Self {
+        assert!(ram_size <= RAM_SIZE);
+        assert_eq!(ram_size % 4, 0);
+        let num_words = ram_size / 4;
         let ram_words_last_live_timestamps =
             unsafe { Box::new_zeroed_slice(num_words).assume_init() };
         let num_pages = if TRACE_TOUCHED_RAM {
@@ -106,39 +108,48 @@ impl<const RAM_SIZE: usize, const TRACE_TOUCHED_RAM: bool>
 pub struct SetupAndTeardownChunker<I: Iterator<Item = LazyInitAndTeardown>> {
     pub touched_ram_cells_count: usize,
     pub chunk_size: usize,
+    pub chunks_count: usize,
     pub next_chunk_index: usize,
     iterator: I,
 }
 
 impl<I: Iterator<Item = LazyInitAndTeardown>> SetupAndTeardownChunker<I> {
-    pub fn get_chunks_count(&self) -> usize {
-        self.touched_ram_cells_count.div_ceil(self.chunk_size)
+    // padding goes to the beginning of the first chunks, so chunks past the minimal count
+    // required for the touched cells consist of padding only
+    fn get_chunk_padding_size(&self, index: usize) -> usize {
+        let padding_size = self.chunks_count * self.chunk_size - self.touched_ram_cells_count;
+        let chunk_start = index * self.chunk_size;
+        padding_size
+            .saturating_sub(chunk_start)
+            .min(self.chunk_size)
+    }
+
+    /// Number of elements chunk `index` holds after it is populated, so buffers can be sized
+    /// before `populate_next_chunk`. Padding fills the beginning of the first chunks instead of
+    /// the end of the last one, so every chunk, including the last, is exactly `chunk_size` long
+    pub fn chunk_len(&self, index: usize) -> usize {
+        assert!(index < self.chunks_count);
+        self.chunk_size
+    }
+
+    /// Number of touched RAM cells in chunk `index`, the rest of the chunk is padding
+    pub fn chunk_touched_cells_count(&self, index: usize) -> usize {
+        self.chunk_len(index) - self.get_chunk_padding_size(index)
     }
 
     pub fn populate_next_chunk(&mut self, chunk: &mut [LazyInitAndTeardown]) {
-        let chunks_count = self.get_chunks_count();
-        assert!(self.next_chunk_index < chunks_count);
-        assert_eq!(self.chunk_size, chunk.len());
-        let dst = if self.next_chunk_index == 0 {
-            let padding_size = chunks_count * self.chunk_size - self.touched_ram_cells_count;
-            let (padding, dst) = chunk.split_at_mut(padding_size);
-            padding.fill(LazyInitAndTeardown::default());
-            dst
-        } else {
-            chunk
-        };
+        assert!(self.next_chunk_index < self.chunks_count);
+        assert_eq!(self.chunk_len(self.next_chunk_index), chunk.len());
+        let padding_size = self.get_chunk_padding_size(self.next_chunk_index);
+        let (padding, dst) = chunk.split_at_mut(padding_size);
+        padding.fill(LazyInitAndTeardown::default());
         dst.fill_with(|| unsafe { self.iterator.next().unwrap_unchecked() });
         self.next_chunk_index += 1;
     }
 
     pub fn skip_next_chunk(&mut self) {
-        let chunks_count = self.get_chunks_count();
-        assert!(self.next_chunk_index < chunks_count);
-        let count = if self.next_chunk_index == 0 {
-            self.touched_ram_cells_count % self.chunk_size
-        } else {
-            self.chunk_size
-        };
+        assert!(self.next_chunk_index < self.chunks_count);
+        let count = self.chunk_touched_cells_count(self.next_chunk_index);
         self.iterator.advance_by(count).unwrap();
         self.next_chunk_index += 1;
     }
@@ -149,8 +160,10 @@ pub fn create_setup_and_teardown_chunker<'a>(
     memory: &'a [u32],
     timestamps: &'a [TimestampScalar],
     chunk_size: usize,
+    chunks_count: usize,
 ) -> SetupAndTeardownChunker<impl Iterator<Item = LazyInitAndTeardown> + 'a> {
     let touched_ram_cells_count = pages.iter().sum::<u32>() as usize;
+    assert!(chunks_count >= touched_ram_cells_count.div_ceil(chunk_size));
     let get_value_fn = |index| unsafe {
         let timestamp = *timestamps.get_unchecked(index);
         if timestamp != 0 {
@@ -179,6 +192,7 @@ pub fn create_setup_and_teardown_chunker<'a>(
     SetupAndTeardownChunker {
         touched_ram_cells_count,
         chunk_size,
+        chunks_count,
         next_chunk_index: 0,
         iterator,
     }
@@ -695,6 +709,8 @@ impl<
     }
 }
 
+/// `RAM_SIZE` is the upper bound, actual size of the allocated memory can be smaller,
+/// and accesses past it are treated the same as accesses past `RAM_SIZE`
 #[derive(Clone, Debug)]
 pub struct BoxedMemoryImplWithRom<const RAM_SIZE: usize, const LOG_ROM_BOUND: u32>(Box<[u32]>);
 
@@ -704,10 +720,16 @@ impl<const RAM_SIZE: usize, const LOG_ROM_BOUND: u32>
     const ROM_BOUND: u32 = 1 << LOG_ROM_BOUND;
     const ROM_BOUND_MASK: u32 = Self::ROM_BOUND - 1;
 
-    pub fn new() -> Self {
-        assert!(RAM_SIZE >= Self::ROM_BOUND as usize);
-        assert_eq!(RAM_SIZE % 4, 0);
-        Self(unsafe { Box::new_zeroed_slice(RAM_SIZE / 4).assume_init() })
+    pub fn new(ram_size: usize) -> Self {
+        assert!(ram_size <= RAM_SIZE);
+        assert!(ram_size >= Self::ROM_BOUND as usize);
+        assert_eq!(ram_size % 4, 0);
+        Self(unsafe { Box::new_zeroed_slice(ram_size / 4).assume_init() })
+    }
+
+    #[inline(always)]
+    pub fn ram_size(&self) -> usize {
+        self.0.len() * 4
     }
 
     pub fn populate(&mut self, address: u32, value: u32) {
@@ -748,7 +770,7 @@ impl<const RAM_SIZE: usize, const LOG_ROM_BOUND: u32> MemorySource
     ) {
         let phys_address = phys_address as u32;
         debug_assert!(phys_address % 4 == 0);
-        if (phys_address as usize) < RAM_SIZE {
+        if (phys_address as usize) < self.ram_size() {
             if phys_address & !Self::ROM_BOUND_MASK == 0 {
                 panic!(
                     "can not set ROM range: requested write into {}, but ROM bound is {}",
@@ -771,7 +793,7 @@ impl<const RAM_SIZE: usize, const LOG_ROM_BOUND: u32> MemorySource
     fn get(&self, phys_address: u64, access_type: AccessType, trap: &mut TrapReason) -> u32 {
         let phys_address = phys_address as u32;
         debug_assert!(phys_address % 4 == 0);
-        if (phys_address as usize) < RAM_SIZE {
+        if (phys_address as usize) < self.ram_size() {
             if phys_address & Self::ROM_BOUND_MASK == 0 {
                 assert!(
                     access_type == AccessType::Instruction || access_type == AccessType::MemLoad
@@ -793,7 +815,7 @@ impl<const RAM_SIZE: usize, const LOG_ROM_BOUND: u32> MemorySource
     fn set_noexcept(&mut self, phys_address: u64, value: u32) {
         let phys_address = phys_address as u32;
         debug_assert!(phys_address % 4 == 0);
-        if (phys_address as usize) < RAM_SIZE {
+        if (phys_address as usize) < self.ram_size() {
             if phys_address & !Self::ROM_BOUND_MASK == 0 {
                 panic!(
                     "can not set ROM range: requested write into {}, but ROM bound is {}",
@@ -811,7 +833,7 @@ impl<const RAM_SIZE: usize, const LOG_ROM_BOUND: u32> MemorySource
     fn get_noexcept(&self, phys_address: u64) -> u32 {
         let phys_address = phys_address as u32;
         debug_assert!(phys_address % 4 == 0);
-        if (phys_address as usize) < RAM_SIZE {
+        if (phys_address as usize) < self.ram_size() {
             unsafe { *self.0.get_unchecked((phys_address / 4) as usize) }
         } else {
             panic!("Out of bound memory access at address 0x{:x}", phys_address);
@@ -831,3 +853,52 @@ impl<const RAM_SIZE: usize, const LOG_ROM_BOUND: u32> MemorySource
         unsafe { *self.0.get_unchecked((phys_address / 4) as usize) }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_len() {
+        const PAGES_COUNT: usize = 3;
+        let touched_words = (1..6).chain([2048 + 7]).chain(2048 + 100..2048 + 110);
+        let mut pages = vec![0u32; PAGES_COUNT];
+        let mut memory = vec![0u32; PAGES_COUNT * PAGE_WORDS_SIZE];
+        let mut timestamps = vec![0; PAGES_COUNT * PAGE_WORDS_SIZE];
+        for (i, index) in touched_words.enumerate() {
+            pages[index >> PAGE_WORDS_LOG_SIZE] += 1;
+            memory[index] = index as u32;
+            timestamps[index] = (i as TimestampScalar + 1) * TIMESTAMP_STEP;
+        }
+        let touched_ram_cells_count = pages.iter().sum::<u32>() as usize;
+        assert_eq!(touched_ram_cells_count, 16);
+        let chunk_size = 6;
+        // one more chunk than needed, so the first one is padding only
+        let chunks_count = touched_ram_cells_count.div_ceil(chunk_size) + 1;
+        let mut chunker = create_setup_and_teardown_chunker(
+            &pages,
+            &memory,
+            &timestamps,
+            chunk_size,
+            chunks_count,
+        );
+        let mut populated_touched_cells_count = 0;
+        for index in 0..chunks_count {
+            let len = chunker.chunk_len(index);
+            let touched_cells_count = chunker.chunk_touched_cells_count(index);
+            let mut chunk = vec![LazyInitAndTeardown::default(); len];
+            chunker.populate_next_chunk(&mut chunk);
+            assert_eq!(chunk.len(), len);
+            let padding_size = len - touched_cells_count;
+            assert!(chunk[..padding_size]
+                .iter()
+                .all(|el| *el == LazyInitAndTeardown::default()));
+            assert!(chunk[padding_size..]
+                .iter()
+                .all(|el| el.teardown_value == el.address >> 2));
+            populated_touched_cells_count += touched_cells_count;
+        }
+        assert_eq!(chunker.chunk_touched_cells_count(0), 0);
+        assert_eq!(populated_touched_cells_count, touched_ram_cells_count);
+    }
+}
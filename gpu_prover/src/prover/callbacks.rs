@@ -1,8 +1,10 @@
 use era_cudart::execution::{launch_host_fn, HostFn};
 use era_cudart::result::CudaResult;
 use era_cudart::stream::CudaStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
-pub(crate) struct Callbacks<'a>(Vec<HostFn<'a>>);
+pub(crate) struct Callbacks<'a>(Vec<(HostFn<'a>, Arc<AtomicBool>)>);
 
 impl<'a> Callbacks<'a> {
     pub fn new() -> Self {
@@ -13,13 +15,62 @@ impl<'a> Callbacks<'a> {
         func: impl Fn() + Send + Sync + 'a,
         stream: &CudaStream,
     ) -> CudaResult<()> {
-        let func = HostFn::new(func);
+        let done = Arc::new(AtomicBool::new(false));
+        let done_clone = done.clone();
+        let func = HostFn::new(move || {
+            func();
+            done_clone.store(true, Ordering::Release);
+        });
         launch_host_fn(stream, &func)?;
-        self.0.push(func);
+        self.0.push((func, done));
         Ok(())
     }
 
     pub fn extend(&mut self, other: Self) {
         self.0.extend(other.0);
     }
+
+    /// Number of scheduled callbacks that did not finish yet
+    pub fn pending_count(&self) -> usize {
+        self.0
+            .iter()
+            .filter(|(_, done)| !done.load(Ordering::Acquire))
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending_count() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn test_pending_count() {
+        const N: usize = 4;
+        let stream = CudaStream::default();
+        // callback `i` blocks the stream until `released` is above `i`
+        let released = AtomicUsize::new(0);
+        let mut callbacks = Callbacks::new();
+        assert!(callbacks.is_empty());
+        for i in 0..N {
+            let released = &released;
+            callbacks
+                .schedule(
+                    move || while released.load(Ordering::Acquire) <= i {},
+                    &stream,
+                )
+                .unwrap();
+        }
+        for i in 0..N {
+            assert_eq!(callbacks.pending_count(), N - i);
+            released.store(i + 1, Ordering::Release);
+            while callbacks.pending_count() != N - i - 1 {}
+        }
+        stream.synchronize().unwrap();
+        assert!(callbacks.is_empty());
+    }
 }
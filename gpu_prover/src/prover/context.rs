@@ -1,19 +1,30 @@
+use super::BF;
 use crate::allocator::device::{
     NonConcurrentStaticDeviceAllocation, NonConcurrentStaticDeviceAllocator,
     StaticDeviceAllocationBackend,
 };
 use crate::allocator::host::{ConcurrentStaticHostAllocator, NonConcurrentStaticHostAllocator};
 use crate::allocator::tracker::AllocationPlacement;
+use crate::allocator::AllocatorStats;
+use crate::circuit_type::{CircuitType, MainCircuitType};
 use crate::device_context::DeviceContext;
-use era_cudart::device::{device_get_attribute, get_device, set_device};
+use era_cudart::device::{
+    device_get_attribute, device_get_stream_priority_range, get_device, get_device_count,
+    set_device,
+};
 use era_cudart::memory::{memory_get_info, CudaHostAllocFlags};
 use era_cudart::result::CudaResult;
 use era_cudart::slice::{CudaSlice, CudaSliceMut};
-use era_cudart::stream::CudaStream;
+use era_cudart::stream::{CudaStream, CudaStreamCreateFlags};
 use era_cudart_sys::{CudaDeviceAttr, CudaError};
 use log::error;
 use rayon::iter::IntoParallelIterator;
 use rayon::iter::ParallelIterator;
+#[cfg(feature = "log_gpu_mem_usage")]
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
 
 pub struct DeviceProperties {
     pub l2_cache_size_bytes: usize,
@@ -40,6 +51,24 @@ pub struct ProverContextConfig {
     pub allocation_block_log_size: u32,
     pub device_slack_blocks_count: usize,
     pub host_allocator_blocks_count: usize,
+    /// Upper bound in bytes for the device memory grabbed by the context, so several
+    /// contexts can share one GPU. `None` takes all free device memory except the slack.
+    pub device_memory_limit: Option<usize>,
+    /// Stream priorities, lower numbers mean higher priority as in CUDA. Values outside of
+    /// the range supported by the device are clamped to it.
+    pub exec_stream_priority: i32,
+    pub aux_stream_priority: i32,
+    pub h2d_stream_priority: i32,
+    /// Proof of work searches nonces in `pow_start_nonce..pow_max_nonce`, so several
+    /// provers can search disjoint nonce bands.
+    pub pow_start_nonce: u64,
+    pub pow_max_nonce: u64,
+    /// Grind the proof of work on the host instead of the device, e.g. to keep the GPU busy with
+    /// other proofs or to cross-check the kernel. The host always searches from zero.
+    pub pow_on_cpu: bool,
+    /// Main circuit traces are uploaded in this many row ranges, so witness generation
+    /// for the first ranges can start while the rest is still being transferred.
+    pub tracing_data_transfer_ranges_count: usize,
 }
 
 impl Default for ProverContextConfig {
@@ -49,14 +78,58 @@ impl Default for ProverContextConfig {
             allocation_block_log_size: 22,    // 4 MB blocks
             device_slack_blocks_count: 64,    // 256 MB slack
             host_allocator_blocks_count: 128, // 512 MB host allocator pool
+            device_memory_limit: None,
+            exec_stream_priority: 0,
+            aux_stream_priority: 0,
+            h2d_stream_priority: 0,
+            pow_start_nonce: 0,
+            pow_max_nonce: u64::MAX,
+            pow_on_cpu: false,
+            tracing_data_transfer_ranges_count: 4,
         }
     }
 }
 
+impl ProverContextConfig {
+    /// Default config with host pool and device slack scaled by the trace size of the given
+    /// circuit type relative to the largest main circuit trace, which the default block counts
+    /// are tuned for. The largest main circuit keeps the default sizes, smaller circuits get
+    /// proportionally less, but never fewer than `MIN_BLOCKS_COUNT` blocks.
+    pub fn for_circuit_type(circuit_type: CircuitType) -> Self {
+        const MIN_BLOCKS_COUNT: usize = 8;
+        let mut config = Self::default();
+        let trace_bytes = |circuit_type: CircuitType| {
+            let widths = circuit_type.layout_widths();
+            let columns_count = widths.witness + widths.memory + widths.setup;
+            circuit_type.get_domain_size() * columns_count * size_of::<BF>()
+        };
+        let largest_trace_bytes = MainCircuitType::all()
+            .into_iter()
+            .map(|main_type| trace_bytes(main_type.into()))
+            .max()
+            .unwrap();
+        let circuit_trace_bytes = trace_bytes(circuit_type);
+        let scale = |default_count: usize| {
+            (default_count * circuit_trace_bytes)
+                .div_ceil(largest_trace_bytes)
+                .max(MIN_BLOCKS_COUNT)
+        };
+        config.host_allocator_blocks_count = scale(config.host_allocator_blocks_count);
+        config.device_slack_blocks_count = scale(config.device_slack_blocks_count);
+        config
+    }
+}
+
 pub type DeviceAllocator = NonConcurrentStaticDeviceAllocator;
 pub type DeviceAllocation<T> = NonConcurrentStaticDeviceAllocation<T>;
 pub type HostAllocator = NonConcurrentStaticHostAllocator;
 
+struct MemWatermark {
+    threshold: usize,
+    callback: Arc<dyn Fn(usize) + Send + Sync>,
+    is_exceeded: AtomicBool,
+}
+
 pub struct ProverContext {
     _device_context: DeviceContext,
     device_allocator: DeviceAllocator,
@@ -68,6 +141,10 @@ pub struct ProverContext {
     device_id: i32,
     device_properties: DeviceProperties,
     reversed_allocation_placement: bool,
+    pow_nonce_range: (u64, u64),
+    pow_on_cpu: bool,
+    tracing_data_transfer_ranges_count: usize,
+    mem_watermark: Option<MemWatermark>,
 }
 
 impl ProverContext {
@@ -82,7 +159,7 @@ impl ProverContext {
     ) -> CudaResult<()> {
         assert!(
             !Self::is_global_host_allocator_initialized(),
-            "Global host allocator can only be initialized once"
+            "Global host allocator is already initialized"
         );
         let host_allocation_size = blocks_per_allocation_count << block_log_size;
         let allocations: Vec<CudaResult<era_cudart::memory::HostAllocation<u8>>> = (0
@@ -106,16 +183,32 @@ impl ProverContext {
         Ok(())
     }
 
+    pub fn shutdown_global_host_allocator() {
+        ConcurrentStaticHostAllocator::shutdown_global();
+    }
+
     pub fn new(config: &ProverContextConfig) -> CudaResult<Self> {
+        assert!(config.pow_start_nonce < config.pow_max_nonce);
+        assert_ne!(config.tracing_data_transfer_ranges_count, 0);
         let slack_size = config.device_slack_blocks_count << config.allocation_block_log_size;
         let slack = era_cudart::memory::DeviceAllocation::<u8>::alloc(slack_size)?;
         let device_id = get_device()?;
         let device_context = DeviceContext::create(config.powers_of_w_coarse_log_count)?;
-        let exec_stream = CudaStream::create()?;
-        let aux_stream = CudaStream::create()?;
-        let h2d_stream = CudaStream::create()?;
+        let (least_priority, greatest_priority) = device_get_stream_priority_range()?;
+        let create_stream = |priority: i32| {
+            let priority = priority.clamp(greatest_priority, least_priority);
+            CudaStream::create_with_priority(CudaStreamCreateFlags::DEFAULT, priority)
+        };
+        let exec_stream = create_stream(config.exec_stream_priority)?;
+        let aux_stream = create_stream(config.aux_stream_priority)?;
+        let h2d_stream = create_stream(config.h2d_stream_priority)?;
         let (free, _) = memory_get_info()?;
         let mut device_blocks_count = free >> config.allocation_block_log_size;
+        if let Some(limit) = config.device_memory_limit {
+            device_blocks_count =
+                device_blocks_count.min(limit >> config.allocation_block_log_size);
+        }
+        assert_ne!(device_blocks_count, 0);
         let device_allocation = loop {
             let result = era_cudart::memory::DeviceAllocation::<u8>::alloc(
                 device_blocks_count << config.allocation_block_log_size,
@@ -163,6 +256,10 @@ impl ProverContext {
             device_id,
             device_properties,
             reversed_allocation_placement: false,
+            pow_nonce_range: (config.pow_start_nonce, config.pow_max_nonce),
+            pow_on_cpu: config.pow_on_cpu,
+            tracing_data_transfer_ranges_count: config.tracing_data_transfer_ranges_count,
+            mem_watermark: None,
         };
         Ok(context)
     }
@@ -171,6 +268,18 @@ impl ProverContext {
         self.host_allocator.clone()
     }
 
+    pub fn get_pow_nonce_range(&self) -> (u64, u64) {
+        self.pow_nonce_range
+    }
+
+    pub fn is_pow_on_cpu(&self) -> bool {
+        self.pow_on_cpu
+    }
+
+    pub fn get_tracing_data_transfer_ranges_count(&self) -> usize {
+        self.tracing_data_transfer_ranges_count
+    }
+
     pub fn get_device_id(&self) -> i32 {
         self.device_id
     }
@@ -195,9 +304,20 @@ impl ProverContext {
         &self,
         size: usize,
         placement: AllocationPlacement,
+    ) -> CudaResult<DeviceAllocation<T>> {
+        self.alloc_raw(size, placement, true)
+    }
+
+    /// Same as `alloc`, but if `respect_reversal` is false the placement is used as is,
+    /// regardless of `set_reversed_allocation_placement`.
+    pub fn alloc_raw<T>(
+        &self,
+        size: usize,
+        placement: AllocationPlacement,
+        respect_reversal: bool,
     ) -> CudaResult<DeviceAllocation<T>> {
         assert_ne!(size, 0);
-        let placement = if self.reversed_allocation_placement {
+        let placement = if respect_reversal && self.reversed_allocation_placement {
             match placement {
                 AllocationPlacement::BestFit => AllocationPlacement::BestFit,
                 AllocationPlacement::Bottom => AllocationPlacement::Top,
@@ -214,10 +334,28 @@ impl ProverContext {
                 self.device_id,
                 self.get_used_mem_current()
             );
+        } else {
+            self.check_mem_watermark();
         }
         result
     }
 
+    /// Same as `alloc`, but when the `log_gpu_mem_usage` feature is enabled the allocation is
+    /// attributed to `label` in `used_mem_by_label` until it is freed.
+    pub fn alloc_labeled<T>(
+        &self,
+        size: usize,
+        placement: AllocationPlacement,
+        label: &'static str,
+    ) -> CudaResult<DeviceAllocation<T>> {
+        let allocation = self.alloc(size, placement)?;
+        #[cfg(feature = "log_gpu_mem_usage")]
+        self.device_allocator.set_label(&allocation, label);
+        #[cfg(not(feature = "log_gpu_mem_usage"))]
+        let _ = label;
+        Ok(allocation)
+    }
+
     pub(crate) unsafe fn alloc_host_uninit<T: Sized>(&self) -> HostAllocation<T> {
         HostAllocation::new_uninit(self)
     }
@@ -245,6 +383,52 @@ impl ProverContext {
         self.device_allocator.reset_used_mem_peak();
     }
 
+    /// Calls `callback` with the current device memory usage when an allocation makes the usage
+    /// exceed `threshold_bytes`. It fires once per crossing and is armed again by the first
+    /// allocation that finds the usage back at or below the threshold. The callback runs after
+    /// the allocator is released, so it may allocate or free memory itself.
+    pub fn set_mem_watermark(
+        &mut self,
+        threshold_bytes: usize,
+        callback: Arc<dyn Fn(usize) + Send + Sync>,
+    ) {
+        self.mem_watermark = Some(MemWatermark {
+            threshold: threshold_bytes,
+            callback,
+            is_exceeded: AtomicBool::new(false),
+        });
+    }
+
+    pub fn clear_mem_watermark(&mut self) {
+        self.mem_watermark = None;
+    }
+
+    fn check_mem_watermark(&self) {
+        let Some(watermark) = &self.mem_watermark else {
+            return;
+        };
+        let used_mem = self.get_used_mem_current();
+        if used_mem > watermark.threshold {
+            if !watermark.is_exceeded.swap(true, Ordering::Relaxed) {
+                (watermark.callback)(used_mem);
+            }
+        } else {
+            watermark.is_exceeded.store(false, Ordering::Relaxed);
+        }
+    }
+
+    pub fn get_used_host_mem_current(&self) -> usize {
+        self.host_allocator.get_used_mem_current()
+    }
+
+    pub fn get_used_host_mem_peak(&self) -> usize {
+        self.host_allocator.get_used_mem_peak()
+    }
+
+    pub fn allocator_stats(&self) -> AllocatorStats {
+        self.device_allocator.get_stats()
+    }
+
     #[cfg(feature = "log_gpu_mem_usage")]
     pub fn log_gpu_mem_usage(&self, location: &str) {
         let used_mem_current = self.get_used_mem_current();
@@ -254,6 +438,17 @@ impl ProverContext {
             used_mem_current as f64 / ((1 << 30) as f64),
             used_mem_peak as f64 / ((1 << 30) as f64),
         );
+        for (label, used_mem) in self.used_mem_by_label() {
+            log::debug!(
+                "GPU memory usage {location} by {label}: {} GB",
+                used_mem as f64 / ((1 << 30) as f64),
+            );
+        }
+    }
+
+    #[cfg(feature = "log_gpu_mem_usage")]
+    pub fn used_mem_by_label(&self) -> HashMap<&'static str, usize> {
+        self.device_allocator.get_used_mem_by_label()
     }
 
     pub fn get_device_properties(&self) -> &DeviceProperties {
@@ -269,6 +464,62 @@ impl ProverContext {
     }
 }
 
+/// Owns one `ProverContext` per visible device and hands them out so that work submitted
+/// through the pool is spread across all GPUs.
+pub struct ProverContextPool {
+    contexts: Vec<ProverContext>,
+    next_index: AtomicUsize,
+}
+
+impl ProverContextPool {
+    pub fn new(config: &ProverContextConfig) -> CudaResult<Self> {
+        let device_count = get_device_count()?;
+        let mut contexts = Vec::with_capacity(device_count as usize);
+        for device_id in 0..device_count {
+            set_device(device_id)?;
+            contexts.push(ProverContext::new(config)?);
+        }
+        let pool = Self {
+            contexts,
+            next_index: AtomicUsize::new(0),
+        };
+        Ok(pool)
+    }
+
+    pub fn size(&self) -> usize {
+        self.contexts.len()
+    }
+
+    /// Picks the context with the least device memory currently in use, ties are resolved in
+    /// round-robin order. The current device is switched to the device of the returned context.
+    pub fn acquire(&self) -> CudaResult<PooledContext> {
+        assert!(!self.contexts.is_empty());
+        let count = self.contexts.len();
+        let start = self.next_index.load(Ordering::Relaxed);
+        let index = (start..start + count)
+            .map(|i| i % count)
+            .min_by_key(|&i| self.contexts[i].get_used_mem_current())
+            .unwrap();
+        self.next_index
+            .store((index + 1) % count, Ordering::Relaxed);
+        let context = &self.contexts[index];
+        context.switch_to_device()?;
+        Ok(PooledContext { context })
+    }
+}
+
+pub struct PooledContext<'a> {
+    context: &'a ProverContext,
+}
+
+impl Deref for PooledContext<'_> {
+    type Target = ProverContext;
+
+    fn deref(&self) -> &Self::Target {
+        self.context
+    }
+}
+
 #[repr(transparent)]
 pub(crate) struct UnsafeAccessor<T: ?Sized>(*const T);
 
@@ -364,3 +615,171 @@ impl<T> CudaSliceMut<T> for HostAllocation<[T]> {
         self.0.as_mut_slice()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    #[test]
+    fn test_prover_context_pool() -> CudaResult<()> {
+        let pool = ProverContextPool::new(&ProverContextConfig::default())?;
+        assert_eq!(pool.size(), get_device_count()? as usize);
+        for _ in 0..pool.size() * 2 {
+            let context = pool.acquire()?;
+            assert_eq!(context.get_device_id(), get_device()?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_prover_context_pool_round_robin() -> CudaResult<()> {
+        let pool = ProverContextPool::new(&ProverContextConfig::default())?;
+        let count = pool.size();
+        // fresh contexts use the same amount of memory, so acquiring cycles through all devices
+        let device_ids = (0..count * 2)
+            .map(|_| pool.acquire().map(|context| context.get_device_id()))
+            .collect::<CudaResult<Vec<_>>>()?;
+        let expected = (0..count as i32)
+            .cycle()
+            .take(count * 2)
+            .collect::<Vec<_>>();
+        assert_eq!(device_ids, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_for_circuit_type() {
+        use crate::circuit_type::DelegationCircuitType;
+
+        let default = ProverContextConfig::default();
+        let main_configs = MainCircuitType::all()
+            .into_iter()
+            .map(|main_type| ProverContextConfig::for_circuit_type(main_type.into()))
+            .collect::<Vec<_>>();
+        assert!(main_configs.iter().all(|config| {
+            config.host_allocator_blocks_count <= default.host_allocator_blocks_count
+                && config.device_slack_blocks_count <= default.device_slack_blocks_count
+        }));
+        assert!(main_configs.iter().any(|config| {
+            config.host_allocator_blocks_count == default.host_allocator_blocks_count
+                && config.device_slack_blocks_count == default.device_slack_blocks_count
+        }));
+        let host_size = |config: &ProverContextConfig| {
+            config.host_allocator_blocks_count << config.allocation_block_log_size
+        };
+        for &delegation_type in DelegationCircuitType::all() {
+            let config = ProverContextConfig::for_circuit_type(delegation_type.into());
+            assert!(host_size(&config) < host_size(&default));
+            assert!(config.device_slack_blocks_count <= default.device_slack_blocks_count);
+        }
+    }
+
+    #[test]
+    fn test_device_memory_limit() -> CudaResult<()> {
+        let limit = 1 << 30;
+        let config = ProverContextConfig {
+            device_memory_limit: Some(limit),
+            ..Default::default()
+        };
+        let context = ProverContext::new(&config)?;
+        assert!(context.get_mem_size() <= limit);
+        Ok(())
+    }
+
+    #[test]
+    fn test_used_host_mem() -> CudaResult<()> {
+        let config = ProverContextConfig::default();
+        let context = ProverContext::new(&config)?;
+        let block_size = 1 << config.allocation_block_log_size;
+        assert_eq!(context.get_used_host_mem_current(), 0);
+        let allocations = (0..4)
+            .map(|_| unsafe { context.alloc_host_uninit_slice::<u8>(block_size) })
+            .collect::<Vec<_>>();
+        assert_eq!(context.get_used_host_mem_current(), 4 * block_size);
+        drop(allocations);
+        assert_eq!(context.get_used_host_mem_current(), 0);
+        assert_eq!(context.get_used_host_mem_peak(), 4 * block_size);
+        Ok(())
+    }
+
+    #[test]
+    #[serial(global_host_allocator)]
+    fn test_reinitialize_global_host_allocator() -> CudaResult<()> {
+        ProverContext::initialize_global_host_allocator(2, 1 << 4, 20)?;
+        assert!(ProverContext::is_global_host_allocator_initialized());
+        let mut values = Vec::with_capacity_in(1 << 10, ConcurrentStaticHostAllocator::default());
+        values.extend(0..1u32 << 10);
+        drop(values);
+        ProverContext::shutdown_global_host_allocator();
+        assert!(!ProverContext::is_global_host_allocator_initialized());
+        ProverContext::initialize_global_host_allocator(1, 1 << 8, 22)?;
+        assert!(ProverContext::is_global_host_allocator_initialized());
+        ProverContext::shutdown_global_host_allocator();
+        Ok(())
+    }
+
+    #[test]
+    fn test_high_priority_h2d_stream() -> CudaResult<()> {
+        let config = ProverContextConfig {
+            h2d_stream_priority: i32::MIN,
+            ..Default::default()
+        };
+        let context = ProverContext::new(&config)?;
+        context.get_h2d_stream().synchronize()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_mem_watermark() -> CudaResult<()> {
+        use std::sync::atomic::AtomicUsize;
+
+        let config = ProverContextConfig::default();
+        let mut context = ProverContext::new(&config)?;
+        let block_size = 1 << config.allocation_block_log_size;
+        let calls = Arc::new(AtomicUsize::new(0));
+        let reported_mem = Arc::new(AtomicUsize::new(0));
+        let callback = {
+            let calls = calls.clone();
+            let reported_mem = reported_mem.clone();
+            Arc::new(move |used_mem| {
+                calls.fetch_add(1, Ordering::Relaxed);
+                reported_mem.store(used_mem, Ordering::Relaxed);
+            })
+        };
+        let base = context.get_used_mem_current();
+        context.set_mem_watermark(base + 2 * block_size, callback);
+        let mut allocations = vec![];
+        for _ in 0..2 {
+            allocations.push(context.alloc::<u8>(block_size, AllocationPlacement::BestFit)?);
+        }
+        assert_eq!(calls.load(Ordering::Relaxed), 0);
+        // crossing the threshold fires once, staying above it does not fire again
+        for _ in 0..3 {
+            allocations.push(context.alloc::<u8>(block_size, AllocationPlacement::BestFit)?);
+        }
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+        assert_eq!(reported_mem.load(Ordering::Relaxed), base + 3 * block_size);
+        // going back below the threshold arms it again
+        allocations.clear();
+        allocations.push(context.alloc::<u8>(block_size, AllocationPlacement::BestFit)?);
+        for _ in 0..2 {
+            allocations.push(context.alloc::<u8>(block_size, AllocationPlacement::BestFit)?);
+        }
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_alloc_raw_ignoring_reversal() -> CudaResult<()> {
+        let mut context = ProverContext::new(&ProverContextConfig::default())?;
+        context.set_reversed_allocation_placement(true);
+        let reversed = context.alloc_raw::<u32>(1024, AllocationPlacement::Top, true)?;
+        let not_reversed = context.alloc_raw::<u32>(1024, AllocationPlacement::Top, false)?;
+        let bottom = context.alloc_raw::<u32>(1024, AllocationPlacement::Bottom, false)?;
+        // reversed Top allocation goes to the bottom of the heap, right before the explicit Bottom one
+        assert!(reversed.as_ptr() < bottom.as_ptr());
+        assert!(bottom.as_ptr() < not_reversed.as_ptr());
+        Ok(())
+    }
+}
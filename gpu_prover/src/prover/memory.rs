@@ -2,6 +2,7 @@ use super::context::{ProverContext, UnsafeMutAccessor};
 use super::trace_holder::{get_tree_caps, TraceHolder, TreesCacheMode};
 use super::tracing_data::{TracingDataDevice, TracingDataTransfer};
 use super::{device_tracing, BF};
+use crate::circuit_type::CircuitType;
 use crate::device_structures::DeviceMatrixMut;
 use crate::prover::callbacks::Callbacks;
 use crate::witness::memory_delegation::generate_memory_values_delegation;
@@ -32,6 +33,8 @@ impl<'a> MemoryCommitmentJob<'a> {
             range,
         } = self;
         is_finished_event.synchronize()?;
+        // every callback runs before the event that closes the job is reached
+        assert!(callbacks.is_empty());
         drop(callbacks);
         let tree_caps = tree_caps.unwrap();
         let commitment_time_ms = range.elapsed()?;
@@ -67,9 +70,9 @@ pub fn commit_memory<'a>(
         circuit_type: _,
         data_host: _,
         data_device,
+        ranges,
         transfer,
     } = tracing_data_transfer;
-    transfer.ensure_transferred(context)?;
     let range = device_tracing::Range::new("commit_memory")?;
     let stream = context.get_exec_stream();
     range.start(stream)?;
@@ -80,15 +83,20 @@ pub fn commit_memory<'a>(
             setup_and_teardown,
             trace,
         } => {
-            generate_memory_values_main(
-                memory_subtree,
-                &setup_and_teardown,
-                &trace,
-                memory,
-                stream,
-            )?;
+            for (index, rows) in ranges.into_iter().enumerate() {
+                transfer.ensure_range_transferred(index, context)?;
+                generate_memory_values_main(
+                    memory_subtree,
+                    &setup_and_teardown,
+                    &trace,
+                    rows,
+                    memory,
+                    stream,
+                )?;
+            }
         }
         TracingDataDevice::Delegation(trace) => {
+            transfer.ensure_transferred(context)?;
             generate_memory_values_delegation(memory_subtree, &trace, memory, stream)?;
         }
     };
@@ -116,3 +124,56 @@ pub fn commit_memory<'a>(
     };
     Ok(job)
 }
+
+pub(crate) fn get_log_lde_factor_and_log_tree_cap_size(circuit_type: CircuitType) -> (u32, u32) {
+    let lde_factor = circuit_type.get_lde_factor();
+    assert!(lde_factor.is_power_of_two());
+    let tree_cap_size = circuit_type.get_tree_cap_size();
+    assert!(tree_cap_size.is_power_of_two());
+    (lde_factor.trailing_zeros(), tree_cap_size.trailing_zeros())
+}
+
+pub fn commit_memory_for_circuit_type<'a>(
+    tracing_data_transfer: TracingDataTransfer<'a, impl GoodAllocator>,
+    circuit: &CompiledCircuitArtifact<BF>,
+    circuit_type: CircuitType,
+    context: &ProverContext,
+) -> CudaResult<MemoryCommitmentJob<'a>> {
+    assert_eq!(tracing_data_transfer.circuit_type, circuit_type);
+    let (log_lde_factor, log_tree_cap_size) =
+        get_log_lde_factor_and_log_tree_cap_size(circuit_type);
+    commit_memory(
+        tracing_data_transfer,
+        circuit,
+        log_lde_factor,
+        log_tree_cap_size,
+        context,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit_type::MainCircuitType;
+    use trace_and_split::setups::risc_v_cycles;
+
+    #[test]
+    fn test_log_lde_factor_and_log_tree_cap_size() {
+        let circuit_type = CircuitType::Main(MainCircuitType::RiscVCycles);
+        let (log_lde_factor, log_tree_cap_size) =
+            get_log_lde_factor_and_log_tree_cap_size(circuit_type);
+        assert_eq!(
+            1 << log_lde_factor,
+            MainCircuitType::RiscVCycles.get_lde_factor()
+        );
+        assert_eq!(
+            1 << log_tree_cap_size,
+            MainCircuitType::RiscVCycles.get_tree_cap_size()
+        );
+        assert_eq!(log_lde_factor, risc_v_cycles::LDE_FACTOR.trailing_zeros());
+        assert_eq!(
+            log_tree_cap_size,
+            risc_v_cycles::TREE_CAP_SIZE.trailing_zeros()
+        );
+    }
+}
@@ -1,14 +1,17 @@
 use super::callbacks::Callbacks;
 use super::context::{HostAllocation, ProverContext};
 use crate::allocator::tracker::AllocationPlacement;
-use crate::blake2s::{blake2s_pow, STATE_SIZE};
+use crate::blake2s::{blake2s_pow, blake2s_pow_searched_count_estimate, STATE_SIZE};
 use era_cudart::memory::memory_copy_async;
 use era_cudart::result::CudaResult;
 use prover::transcript::{Blake2sTranscript, Seed};
 use std::slice;
+use worker::Worker;
 
 pub(crate) struct PowOutput {
     pub nonce: HostAllocation<u64>,
+    /// Approximate number of hashes tried before the nonce was found, zero for external nonces.
+    pub searched_count_estimate: HostAllocation<u64>,
 }
 
 impl PowOutput {
@@ -16,33 +19,120 @@ impl PowOutput {
         seed: &mut HostAllocation<Seed>,
         pow_bits: u32,
         external_nonce: Option<u64>,
+        start_nonce: Option<u64>,
         callbacks: &mut Callbacks<'a>,
         context: &ProverContext,
     ) -> CudaResult<Self> {
         let seed_accessor = seed.get_mut_accessor();
         let mut nonce = unsafe { context.alloc_host_uninit::<u64>() };
         let nonce_accessor = nonce.get_mut_accessor();
+        let mut searched_count_estimate = unsafe { context.alloc_host_uninit::<u64>() };
+        let estimate_accessor = searched_count_estimate.get_mut_accessor();
         let stream = context.get_exec_stream();
         if let Some(external_nonce) = external_nonce {
             let set_nonce_fn = move || unsafe {
                 nonce_accessor.set(external_nonce);
+                estimate_accessor.set(0);
             };
             callbacks.schedule(set_nonce_fn, stream)?;
+        } else if context.is_pow_on_cpu() {
+            // the host searches from zero, so the nonce band of the context does not apply
+            let search_fn = move || unsafe {
+                let nonce = search_pow_cpu(seed_accessor.get(), pow_bits);
+                nonce_accessor.set(nonce);
+                estimate_accessor.set(blake2s_pow_searched_count_estimate(0, nonce));
+            };
+            callbacks.schedule(search_fn, stream)?;
         } else {
             let mut d_seed = context.alloc(STATE_SIZE, AllocationPlacement::BestFit)?;
             let mut d_nonce = context.alloc(1, AllocationPlacement::BestFit)?;
             memory_copy_async(&mut d_seed, unsafe { &seed_accessor.get().0 }, &stream)?;
-            blake2s_pow(&d_seed, pow_bits, u64::MAX, &mut d_nonce[0], stream)?;
+            // fixed start nonce overrides the nonce band of the context, so the found nonce
+            // does not depend on the device that produced the proof
+            let (start_nonce, max_nonce) = match start_nonce {
+                Some(start_nonce) => (start_nonce, u64::MAX),
+                None => context.get_pow_nonce_range(),
+            };
+            blake2s_pow(
+                &d_seed,
+                pow_bits,
+                start_nonce,
+                max_nonce,
+                &mut d_nonce[0],
+                stream,
+            )?;
             memory_copy_async(
                 slice::from_mut::<u64>(unsafe { nonce_accessor.get_mut() }),
                 &d_nonce,
                 &stream,
             )?;
+            let set_estimate_fn = move || unsafe {
+                let estimate =
+                    blake2s_pow_searched_count_estimate(start_nonce, *nonce_accessor.get());
+                estimate_accessor.set(estimate);
+            };
+            callbacks.schedule(set_estimate_fn, stream)?;
         };
         let verify_fn = move || unsafe {
             Blake2sTranscript::verify_pow(seed_accessor.get_mut(), *nonce_accessor.get(), pow_bits);
         };
         callbacks.schedule(verify_fn, stream)?;
-        Ok(Self { nonce })
+        Ok(Self {
+            nonce,
+            searched_count_estimate,
+        })
+    }
+}
+
+/// Grinds the nonce on the host, the seed is updated afterwards by the verification callback
+fn search_pow_cpu(seed: &Seed, pow_bits: u32) -> u64 {
+    let worker = Worker::new();
+    let (_, nonce) = Blake2sTranscript::search_pow(seed, pow_bits, &worker);
+    nonce
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prover::context::ProverContextConfig;
+
+    fn prove_pow(seed: Seed, pow_bits: u32, pow_on_cpu: bool) -> CudaResult<(Seed, u64)> {
+        let config = ProverContextConfig {
+            pow_on_cpu,
+            ..Default::default()
+        };
+        let context = ProverContext::new(&config)?;
+        let mut seed_allocation = unsafe { context.alloc_host_uninit::<Seed>() };
+        unsafe { seed_allocation.get_mut_accessor().set(seed) };
+        let mut callbacks = Callbacks::new();
+        let output = PowOutput::new(
+            &mut seed_allocation,
+            pow_bits,
+            None,
+            None,
+            &mut callbacks,
+            &context,
+        )?;
+        context.get_exec_stream().synchronize()?;
+        assert!(callbacks.is_empty());
+        let seed = unsafe { *seed_allocation.get_accessor().get() };
+        let nonce = unsafe { *output.nonce.get_accessor().get() };
+        Ok((seed, nonce))
+    }
+
+    #[test]
+    fn test_pow_on_cpu_matches_gpu() -> CudaResult<()> {
+        const POW_BITS: u32 = 12;
+        let initial_seed = Seed([42; STATE_SIZE]);
+
+        let (cpu_seed, cpu_nonce) = prove_pow(initial_seed, POW_BITS, true)?;
+        let mut verified_seed = initial_seed;
+        Blake2sTranscript::verify_pow(&mut verified_seed, cpu_nonce, POW_BITS);
+        assert_eq!(cpu_seed, verified_seed);
+
+        let (gpu_seed, gpu_nonce) = prove_pow(initial_seed, POW_BITS, false)?;
+        assert_eq!(gpu_nonce, cpu_nonce);
+        assert_eq!(gpu_seed, cpu_seed);
+        Ok(())
     }
 }
@@ -1,19 +1,27 @@
+use super::context::DeviceProperties;
 use super::E2;
 use fft::{bitreverse_enumeration_inplace, domain_generator_for_size};
 use field::{Field, TwoAdicField};
 use std::sync::LazyLock;
 
-const INVERSE_TWIDDLES_LOG_SIZE: usize = 8;
+const TWIDDLES_LOG_SIZE: usize = 8;
 pub(crate) static PRECOMPUTATIONS: LazyLock<Precomputations> = LazyLock::new(Precomputations::new);
 
-pub(crate) struct Precomputations {
+pub struct Precomputations {
     pub omegas: [E2; E2::TWO_ADICITY + 1],
     pub omegas_inv: [E2; E2::TWO_ADICITY + 1],
-    pub inverse_twiddles: [E2; 1 << INVERSE_TWIDDLES_LOG_SIZE],
+    pub inverse_twiddles: Vec<E2>,
+    pub forward_twiddles: Vec<E2>,
 }
 
 impl Precomputations {
-    pub(crate) fn new() -> Self {
+    pub fn new() -> Self {
+        Self::new_with_log_size(TWIDDLES_LOG_SIZE)
+    }
+
+    /// Same as `new`, but with `1 << log_size` elements in twiddle tables.
+    pub fn new_with_log_size(log_size: usize) -> Self {
+        assert!(log_size < E2::TWO_ADICITY);
         let mut omegas = [E2::ZERO; E2::TWO_ADICITY + 1];
         let mut omega = E2::two_adic_generator();
         omegas.iter_mut().rev().for_each(|el| {
@@ -31,24 +39,101 @@ impl Precomputations {
             omega_inv.square();
         });
         assert_eq!(omegas_inv[0], E2::ONE);
-        let mut inverse_twiddles = [E2::ZERO; 1 << INVERSE_TWIDDLES_LOG_SIZE];
-        let base = omegas_inv[INVERSE_TWIDDLES_LOG_SIZE + 1];
+        let mut inverse_twiddles = vec![E2::ZERO; 1 << log_size];
+        let base = omegas_inv[log_size + 1];
         let mut value = E2::ONE;
         inverse_twiddles.iter_mut().for_each(|el| {
             *el = value;
             value.mul_assign(&base);
         });
         bitreverse_enumeration_inplace(&mut inverse_twiddles);
+        let mut forward_twiddles = vec![E2::ZERO; 1 << log_size];
+        let base = omegas[log_size + 1];
+        let mut value = E2::ONE;
+        forward_twiddles.iter_mut().for_each(|el| {
+            *el = value;
+            value.mul_assign(&base);
+        });
+        bitreverse_enumeration_inplace(&mut forward_twiddles);
         Self {
             omegas,
             omegas_inv,
             inverse_twiddles,
+            forward_twiddles,
         }
     }
 
+    /// Picks twiddle tables size so that both of them take at most 1/1024 of the L2 cache,
+    /// but never less than the default size.
+    pub fn log_size_for_device(device_properties: &DeviceProperties) -> usize {
+        let elems_count =
+            (device_properties.l2_cache_size_bytes >> 10) / (2 * std::mem::size_of::<E2>());
+        let log_size = elems_count.max(1).ilog2() as usize;
+        log_size.clamp(TWIDDLES_LOG_SIZE, E2::TWO_ADICITY - 1)
+    }
+
     pub(crate) fn ensure_initialized() {
         // This function is called to ensure that the static PRECOMPUTATIONS is initialized.
         // The LazyLock will initialize it on the first call.
         let _ = &*PRECOMPUTATIONS;
     }
 }
+
+impl Default for Precomputations {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_twiddles(precomputations: &Precomputations, log_size: usize) {
+        assert_eq!(precomputations.forward_twiddles.len(), 1 << log_size);
+        assert_eq!(precomputations.inverse_twiddles.len(), 1 << log_size);
+        for (forward, inverse) in precomputations
+            .forward_twiddles
+            .iter()
+            .zip(precomputations.inverse_twiddles.iter())
+        {
+            let mut product = *forward;
+            product.mul_assign(inverse);
+            assert_eq!(product, E2::ONE);
+        }
+        assert_eq!(precomputations.forward_twiddles[0], E2::ONE);
+        assert_eq!(
+            precomputations.forward_twiddles[1],
+            precomputations.omegas[2]
+        );
+    }
+
+    #[test]
+    fn test_forward_twiddles() {
+        check_twiddles(&Precomputations::new(), TWIDDLES_LOG_SIZE);
+    }
+
+    #[test]
+    fn test_new_with_log_size() {
+        for log_size in [6, 10] {
+            check_twiddles(&Precomputations::new_with_log_size(log_size), log_size);
+        }
+    }
+
+    #[test]
+    fn test_log_size_for_device() {
+        let device_properties = DeviceProperties {
+            l2_cache_size_bytes: 1 << 25,
+            sm_count: 1,
+        };
+        assert_eq!(Precomputations::log_size_for_device(&device_properties), 11);
+        let device_properties = DeviceProperties {
+            l2_cache_size_bytes: 0,
+            sm_count: 1,
+        };
+        assert_eq!(
+            Precomputations::log_size_for_device(&device_properties),
+            TWIDDLES_LOG_SIZE
+        );
+    }
+}
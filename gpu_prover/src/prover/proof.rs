@@ -3,7 +3,7 @@ use super::context::{HostAllocation, ProverContext, UnsafeMutAccessor};
 use super::pow::PowOutput;
 use super::queries::QueriesOutput;
 use super::setup::SetupPrecomputations;
-use super::stage_1::StageOneOutput;
+use super::stage_1::{StageOneOutput, WitnessTimingRanges};
 use super::stage_2::StageTwoOutput;
 use super::stage_3::StageThreeOutput;
 use super::stage_4::StageFourOutput;
@@ -28,7 +28,9 @@ pub struct ProofJob<'a> {
     is_finished_event: CudaEvent,
     callbacks: Callbacks<'a>,
     proof: Box<Option<Proof>>,
+    pow_output: PowOutput,
     ranges: Vec<device_tracing::Range<'a>>,
+    witness_timing_ranges: WitnessTimingRanges,
 }
 
 impl<'a> ProofJob<'a> {
@@ -41,14 +43,29 @@ impl<'a> ProofJob<'a> {
             is_finished_event,
             callbacks,
             mut proof,
+            pow_output,
             ranges,
+            witness_timing_ranges,
         } = self;
         is_finished_event.synchronize()?;
+        // every callback runs before the event that closes the job is reached
+        assert!(callbacks.is_empty());
         drop(callbacks);
         let proof = proof.take().unwrap();
+        let pow_searched_count_estimate =
+            unsafe { *pow_output.searched_count_estimate.get_accessor().get() };
+        log::debug!("GPU pow searched approximately {pow_searched_count_estimate} nonces");
         #[cfg(feature = "log_gpu_stages_timings")]
         {
             log::debug!("GPU setup time: {:.3} ms", ranges[0].elapsed()?);
+            let witness_timings = witness_timing_ranges.elapsed()?;
+            log::debug!(
+                "GPU witness generation time: memory {:.3} ms, witness {:.3} ms, generic multiplicities {:.3} ms, range check multiplicities {:.3} ms",
+                witness_timings.memory_ms,
+                witness_timings.witness_ms,
+                witness_timings.generic_mult_ms,
+                witness_timings.range_check_mult_ms,
+            );
             log::debug!("GPU stage 1 time: {:.3} ms", ranges[1].elapsed()?);
             log::debug!("GPU stage 2 time: {:.3} ms", ranges[2].elapsed()?);
             log::debug!("GPU stage 3 time: {:.3} ms", ranges[3].elapsed()?);
@@ -57,6 +74,7 @@ impl<'a> ProofJob<'a> {
             log::debug!("GPU pow time: {:.3} ms", ranges[6].elapsed()?);
             log::debug!("GPU queries time: {:.3} ms", ranges[7].elapsed()?);
         }
+        drop(witness_timing_ranges);
         let proof_time_ms = ranges[8].elapsed()?;
 
         Ok((proof, proof_time_ms))
@@ -75,6 +93,7 @@ pub fn prove<'a>(
     num_queries: usize,
     pow_bits: u32,
     external_pow_nonce: Option<u64>,
+    pow_start_nonce: Option<u64>,
     recompute_cosets: bool,
     trees_cache_mode: TreesCacheMode,
     context: &ProverContext,
@@ -135,7 +154,7 @@ pub fn prove<'a>(
     // witness_generation
     let witness_generation_range = device_tracing::Range::new("witness_generation")?;
     witness_generation_range.start(stream)?;
-    stage_1_output.generate_witness(
+    let witness_timing_ranges = stage_1_output.generate_witness(
         &circuit,
         setup,
         tracing_data_transfer,
@@ -251,6 +270,7 @@ pub fn prove<'a>(
         &mut seed,
         pow_bits,
         external_pow_nonce,
+        pow_start_nonce,
         &mut callbacks,
         context,
     )?;
@@ -290,7 +310,7 @@ pub fn prove<'a>(
         stage_3_output,
         stage_4_output,
         stage_5_output,
-        pow_output,
+        &pow_output,
         queries_output,
         &mut callbacks,
         context,
@@ -326,6 +346,8 @@ pub fn prove<'a>(
         callbacks,
         ranges,
         proof,
+        pow_output,
+        witness_timing_ranges,
     };
     Ok(proof_job)
 }
@@ -409,7 +431,7 @@ fn create_proof(
     stage_3_output: StageThreeOutput,
     stage_4_output: StageFourOutput,
     stage_5_output: StageFiveOutput,
-    pow_output: PowOutput,
+    pow_output: &PowOutput,
     queries_output: QueriesOutput,
     callbacks: &mut Callbacks,
     context: &ProverContext,
@@ -1,5 +1,7 @@
 use super::callbacks::Callbacks;
 use super::context::{DeviceAllocation, HostAllocation, ProverContext};
+#[cfg(feature = "log_gpu_stages_timings")]
+use super::device_tracing::Range;
 use super::setup::SetupPrecomputations;
 use super::trace_holder::{TraceHolder, TreesCacheMode};
 use super::tracing_data::{TracingDataDevice, TracingDataTransfer};
@@ -20,15 +22,64 @@ use cs::definitions::{
 };
 use cs::one_row_compiler::{read_value, CompiledCircuitArtifact};
 use era_cudart::memory::memory_copy_async;
-use era_cudart::result::CudaResult;
+use era_cudart::result::{CudaError, CudaResult};
 use fft::GoodAllocator;
-use itertools::Itertools;
 use std::sync::Arc;
 
+#[cfg(feature = "log_gpu_stages_timings")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WitnessTimings {
+    pub memory_ms: f32,
+    pub witness_ms: f32,
+    pub generic_mult_ms: f32,
+    pub range_check_mult_ms: f32,
+}
+
+/// Per-phase ranges recorded by `generate_witness`, empty unless `log_gpu_stages_timings` is enabled.
+pub struct WitnessTimingRanges {
+    #[cfg(feature = "log_gpu_stages_timings")]
+    memory: Range<'static>,
+    #[cfg(feature = "log_gpu_stages_timings")]
+    witness: Range<'static>,
+    #[cfg(feature = "log_gpu_stages_timings")]
+    generic_multiplicities: Range<'static>,
+    #[cfg(feature = "log_gpu_stages_timings")]
+    range_check_multiplicities: Range<'static>,
+}
+
+impl WitnessTimingRanges {
+    fn new() -> CudaResult<Self> {
+        Ok(Self {
+            #[cfg(feature = "log_gpu_stages_timings")]
+            memory: Range::new("generate_memory_values")?,
+            #[cfg(feature = "log_gpu_stages_timings")]
+            witness: Range::new("generate_witness_values")?,
+            #[cfg(feature = "log_gpu_stages_timings")]
+            generic_multiplicities: Range::new("generate_generic_lookup_multiplicities")?,
+            #[cfg(feature = "log_gpu_stages_timings")]
+            range_check_multiplicities: Range::new("generate_range_check_multiplicities")?,
+        })
+    }
+
+    // can only be called after the work recorded in the ranges has finished
+    #[cfg(feature = "log_gpu_stages_timings")]
+    pub fn elapsed(&self) -> CudaResult<WitnessTimings> {
+        Ok(WitnessTimings {
+            memory_ms: self.memory.elapsed()?,
+            witness_ms: self.witness.elapsed()?,
+            generic_mult_ms: self.generic_multiplicities.elapsed()?,
+            range_check_mult_ms: self.range_check_multiplicities.elapsed()?,
+        })
+    }
+}
+
 pub(crate) struct StageOneOutput {
     pub witness_holder: TraceHolder<BF>,
     pub memory_holder: TraceHolder<BF>,
     pub generic_lookup_mapping: Option<DeviceAllocation<u32>>,
+    /// Placement of `generic_lookup_mapping`, swapped by the context if its allocation placement
+    /// is reversed. Defaults to `Top`, away from the trace holders.
+    pub generic_lookup_mapping_placement: AllocationPlacement,
     pub public_inputs: Option<HostAllocation<[BF]>>,
 }
 
@@ -74,10 +125,25 @@ impl StageOneOutput {
             witness_holder,
             memory_holder,
             generic_lookup_mapping: None,
+            generic_lookup_mapping_placement: AllocationPlacement::Top,
             public_inputs: None,
         })
     }
 
+    fn allocate_generic_lookup_mapping(
+        &self,
+        circuit: &CompiledCircuitArtifact<BF>,
+        context: &ProverContext,
+    ) -> CudaResult<DeviceAllocation<u32>> {
+        let log_domain_size = circuit.trace_len.trailing_zeros();
+        let size = circuit.witness_layout.width_3_lookups.len() << log_domain_size;
+        context.alloc_labeled(
+            size,
+            self.generic_lookup_mapping_placement,
+            "generic_lookup_mapping",
+        )
+    }
+
     pub fn generate_witness<'a>(
         &mut self,
         circuit: &CompiledCircuitArtifact<BF>,
@@ -86,23 +152,20 @@ impl StageOneOutput {
         circuit_sequence: usize,
         callbacks: &mut Callbacks<'a>,
         context: &ProverContext,
-    ) -> CudaResult<()> {
+    ) -> CudaResult<WitnessTimingRanges> {
         let trace_len = circuit.trace_len;
         assert!(trace_len.is_power_of_two());
         let log_domain_size = trace_len.trailing_zeros();
         let witness_subtree = &circuit.witness_layout;
         let memory_subtree = &circuit.memory_layout;
-        let generic_lookup_mapping_size = witness_subtree.width_3_lookups.len() << log_domain_size;
-        let mut generic_lookup_mapping =
-            context.alloc(generic_lookup_mapping_size, AllocationPlacement::Top)?;
+        let mut generic_lookup_mapping = self.allocate_generic_lookup_mapping(circuit, context)?;
         let TracingDataTransfer {
             circuit_type,
             data_host: _,
             data_device,
+            ranges: rows_ranges,
             transfer,
         } = tracing_data_transfer;
-        transfer.ensure_transferred(context)?;
-        callbacks.extend(transfer.callbacks);
         let stream = context.get_exec_stream();
         assert_eq!(COMMON_TABLE_WIDTH, 3);
         assert_eq!(NUM_COLUMNS_FOR_COMMON_TABLE_WIDTH_SETUP, 4);
@@ -130,34 +193,52 @@ impl StageOneOutput {
         );
         let mut memory_evaluations = self.memory_holder.get_uninit_evaluations_mut();
         let mut witness_evaluations = self.witness_holder.get_uninit_evaluations_mut();
+        let ranges = WitnessTimingRanges::new()?;
         match data_device {
             TracingDataDevice::Main {
                 setup_and_teardown,
                 trace,
             } => {
                 set_to_zero(&mut witness_evaluations, stream)?;
-                generate_memory_and_witness_values_main(
-                    memory_subtree,
-                    &circuit.memory_queries_timestamp_comparison_aux_vars,
-                    &setup_and_teardown,
-                    circuit.lazy_init_address_aux_vars.as_ref().unwrap(),
-                    &trace,
-                    timestamp_high_from_circuit_sequence,
-                    &mut DeviceMatrixMut::new(&mut memory_evaluations, trace_len),
-                    &mut DeviceMatrixMut::new(&mut witness_evaluations, trace_len),
-                    stream,
-                )?;
-                generate_witness_values_main(
-                    circuit_type.as_main().unwrap(),
-                    &trace,
-                    &DeviceMatrix::new(&generic_lookup_tables, trace_len),
-                    &DeviceMatrix::new(&memory_evaluations, trace_len),
-                    &mut DeviceMatrixMut::new(&mut witness_evaluations, trace_len),
-                    &mut DeviceMatrixMut::new(&mut generic_lookup_mapping, trace_len),
-                    stream,
-                )?;
+                #[cfg(feature = "log_gpu_stages_timings")]
+                ranges.memory.start(stream)?;
+                // memory values for each range of rows are generated as soon as it is transferred
+                for (index, rows) in rows_ranges.iter().enumerate() {
+                    transfer.ensure_range_transferred(index, context)?;
+                    generate_memory_and_witness_values_main(
+                        memory_subtree,
+                        &circuit.memory_queries_timestamp_comparison_aux_vars,
+                        &setup_and_teardown,
+                        circuit.lazy_init_address_aux_vars.as_ref().unwrap(),
+                        &trace,
+                        rows.clone(),
+                        timestamp_high_from_circuit_sequence,
+                        &mut DeviceMatrixMut::new(&mut memory_evaluations, trace_len),
+                        &mut DeviceMatrixMut::new(&mut witness_evaluations, trace_len),
+                        stream,
+                    )?;
+                }
+                #[cfg(feature = "log_gpu_stages_timings")]
+                ranges.memory.end(stream)?;
+                #[cfg(feature = "log_gpu_stages_timings")]
+                ranges.witness.start(stream)?;
+                for rows in rows_ranges.iter() {
+                    generate_witness_values_main(
+                        circuit_type.as_main().unwrap(),
+                        &trace,
+                        rows.clone(),
+                        &DeviceMatrix::new(&generic_lookup_tables, trace_len),
+                        &DeviceMatrix::new(&memory_evaluations, trace_len),
+                        &mut DeviceMatrixMut::new(&mut witness_evaluations, trace_len),
+                        &mut DeviceMatrixMut::new(&mut generic_lookup_mapping, trace_len),
+                        stream,
+                    )?;
+                }
+                #[cfg(feature = "log_gpu_stages_timings")]
+                ranges.witness.end(stream)?;
             }
             TracingDataDevice::Delegation(trace) => {
+                transfer.ensure_transferred(context)?;
                 let all_multiplicities_columns_count = range_check_16_multiplicities_columns
                     .num_elements
                     + timestamp_range_check_multiplicities_columns.num_elements
@@ -166,6 +247,8 @@ impl StageOneOutput {
                     [range_check_16_multiplicities_columns.start * trace_len..]
                     [..all_multiplicities_columns_count * trace_len];
                 set_to_zero(all_multiplicities, stream)?;
+                #[cfg(feature = "log_gpu_stages_timings")]
+                ranges.memory.start(stream)?;
                 generate_memory_and_witness_values_delegation(
                     memory_subtree,
                     &circuit.register_and_indirect_access_timestamp_comparison_aux_vars,
@@ -174,6 +257,10 @@ impl StageOneOutput {
                     &mut DeviceMatrixMut::new(&mut witness_evaluations, trace_len),
                     stream,
                 )?;
+                #[cfg(feature = "log_gpu_stages_timings")]
+                ranges.memory.end(stream)?;
+                #[cfg(feature = "log_gpu_stages_timings")]
+                ranges.witness.start(stream)?;
                 generate_witness_values_delegation(
                     circuit_type.as_delegation().unwrap(),
                     &trace,
@@ -183,16 +270,28 @@ impl StageOneOutput {
                     &mut DeviceMatrixMut::new(&mut generic_lookup_mapping, trace_len),
                     stream,
                 )?;
+                #[cfg(feature = "log_gpu_stages_timings")]
+                ranges.witness.end(stream)?;
             }
         };
         let generic_lookup_multiplicities = &mut witness_evaluations
             [generic_multiplicities_columns.start * trace_len..]
             [..generic_multiplicities_columns.num_elements * trace_len];
+        #[cfg(feature = "log_gpu_stages_timings")]
+        ranges.generic_multiplicities.start(stream)?;
         generate_generic_lookup_multiplicities(
             &mut DeviceMatrixMut::new(&mut generic_lookup_mapping, trace_len),
             &mut DeviceMatrixMut::new(generic_lookup_multiplicities, trace_len),
             context,
         )?;
+        #[cfg(feature = "log_gpu_stages_timings")]
+        ranges.generic_multiplicities.end(stream)?;
+        // range check multiplicities are computed over the whole memory evaluations,
+        // so all the ranges must be transferred at this point
+        transfer.ensure_transferred(context)?;
+        callbacks.extend(transfer.callbacks);
+        #[cfg(feature = "log_gpu_stages_timings")]
+        ranges.range_check_multiplicities.start(stream)?;
         generate_range_check_multiplicities(
             circuit,
             &DeviceMatrix::new(&setup.trace_holder.get_evaluations(context)?, trace_len),
@@ -202,8 +301,10 @@ impl StageOneOutput {
             trace_len,
             context,
         )?;
+        #[cfg(feature = "log_gpu_stages_timings")]
+        ranges.range_check_multiplicities.end(stream)?;
         self.generic_lookup_mapping = Some(generic_lookup_mapping);
-        Ok(())
+        Ok(ranges)
     }
 
     pub fn commit_witness(
@@ -233,13 +334,25 @@ impl StageOneOutput {
             self.public_inputs = Some(unsafe { context.alloc_host_uninit_slice(0) });
             return Ok(());
         }
+        // validate locations before scheduling any work, panicking inside the callback is not recoverable
+        if let Some((_, column_address)) = circuit
+            .public_inputs
+            .iter()
+            .find(|(location, _)| *location == BoundaryConstraintLocation::LastRow)
+        {
+            log::error!(
+                "public input at {column_address:?} is on the last row, which is not supported"
+            );
+            return Err(CudaError::ErrorInvalidValue);
+        }
         let holder = &mut self.witness_holder;
         let columns_count = holder.columns_count;
         let trace_len = 1 << holder.log_domain_size;
         let stream = context.get_exec_stream();
-        let mut d_witness_first_row = context.alloc(columns_count, AllocationPlacement::BestFit)?;
+        let mut d_witness_first_row =
+            context.alloc_labeled(columns_count, AllocationPlacement::BestFit, "public_inputs")?;
         let mut d_witness_one_before_last_row =
-            context.alloc(columns_count, AllocationPlacement::BestFit)?;
+            context.alloc_labeled(columns_count, AllocationPlacement::BestFit, "public_inputs")?;
         let mut h_witness_first_row = unsafe { context.alloc_host_uninit_slice(columns_count) };
         let h_witness_first_row_accessor = h_witness_first_row.get_mut_accessor();
         let mut h_witness_one_before_last_row =
@@ -274,32 +387,173 @@ impl StageOneOutput {
         let unsafe_public_inputs = public_inputs.get_mut_accessor();
         let circuit_clone = circuit.clone();
         let function = move || unsafe {
-            let mut first_row_public_inputs = vec![];
-            let mut one_before_last_row_public_inputs = vec![];
-            let witness_first_row = h_witness_first_row_accessor.get();
-            let witness_one_before_last_row = h_witness_one_before_last_row_accessor.get();
-            for (location, column_address) in circuit_clone.public_inputs.iter() {
-                match location {
-                    BoundaryConstraintLocation::FirstRow => {
-                        let value = read_value(*column_address, witness_first_row, &[]);
-                        first_row_public_inputs.push(value);
-                    }
-                    BoundaryConstraintLocation::OneBeforeLastRow => {
-                        let value = read_value(*column_address, witness_one_before_last_row, &[]);
-                        one_before_last_row_public_inputs.push(value);
-                    }
-                    BoundaryConstraintLocation::LastRow => {
-                        panic!("public inputs on the last row are not supported");
-                    }
-                }
-            }
-            let public_inputs = unsafe_public_inputs.get_mut();
-            let mut iter = public_inputs.iter_mut();
-            iter.set_from(first_row_public_inputs);
-            iter.set_from(one_before_last_row_public_inputs);
+            let values = compute_public_inputs_cpu(
+                &circuit_clone,
+                h_witness_first_row_accessor.get(),
+                h_witness_one_before_last_row_accessor.get(),
+            );
+            unsafe_public_inputs.get_mut().copy_from_slice(&values);
         };
         callbacks.schedule(function, stream)?;
         self.public_inputs = Some(public_inputs);
         Ok(())
     }
 }
+
+/// Extracts public inputs from the first and one before last witness rows, first row values go first.
+/// This is what `StageOneOutput::produce_public_inputs` does on the host once the rows are transferred.
+pub(crate) fn compute_public_inputs_cpu(
+    circuit: &CompiledCircuitArtifact<BF>,
+    witness_first_row: &[BF],
+    witness_one_before_last_row: &[BF],
+) -> Vec<BF> {
+    let mut first_row_public_inputs = vec![];
+    let mut one_before_last_row_public_inputs = vec![];
+    for (location, column_address) in circuit.public_inputs.iter() {
+        match location {
+            BoundaryConstraintLocation::FirstRow => {
+                let value = read_value(*column_address, witness_first_row, &[]);
+                first_row_public_inputs.push(value);
+            }
+            BoundaryConstraintLocation::OneBeforeLastRow => {
+                let value = read_value(*column_address, witness_one_before_last_row, &[]);
+                one_before_last_row_public_inputs.push(value);
+            }
+            BoundaryConstraintLocation::LastRow => {
+                panic!("public inputs on the last row are not supported");
+            }
+        }
+    }
+    first_row_public_inputs.extend(one_before_last_row_public_inputs);
+    first_row_public_inputs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::context::ProverContextConfig;
+    use super::*;
+    use cs::definitions::ColumnAddress;
+    use era_cudart::memory::memory_copy;
+    use execution_utils::get_padded_binary;
+    use itertools::Itertools;
+    use prover::risc_v_simulator::cycle::{IMStandardIsaConfig, MachineConfig};
+    use std::io::Read;
+    use trace_and_split::setups::risc_v_cycles;
+
+    fn get_circuit() -> CompiledCircuitArtifact<BF> {
+        let mut binary = vec![];
+        std::fs::File::open("../examples/hashed_fibonacci/app.bin")
+            .unwrap()
+            .read_to_end(&mut binary)
+            .unwrap();
+        let binary = get_padded_binary(&binary);
+        risc_v_cycles::get_machine(&binary, IMStandardIsaConfig::ALLOWED_DELEGATION_CSRS)
+    }
+
+    fn allocate_trace_holders(
+        circuit: &CompiledCircuitArtifact<BF>,
+        context: &ProverContext,
+    ) -> CudaResult<StageOneOutput> {
+        let log_lde_factor = risc_v_cycles::LDE_FACTOR.trailing_zeros();
+        let log_tree_cap_size = risc_v_cycles::TREE_CAP_SIZE.trailing_zeros();
+        StageOneOutput::allocate_trace_holders(
+            circuit,
+            log_lde_factor,
+            log_tree_cap_size,
+            false,
+            TreesCacheMode::CacheFull,
+            context,
+        )
+    }
+
+    #[test]
+    fn test_generic_lookup_mapping_placement() -> CudaResult<()> {
+        let mut context = ProverContext::new(&ProverContextConfig::default())?;
+        context.set_reversed_allocation_placement(true);
+        let circuit = get_circuit();
+        let mut output = allocate_trace_holders(&circuit, &context)?;
+        // default Top placement goes to the bottom of the heap in a reversed context
+        let mapping = output.allocate_generic_lookup_mapping(&circuit, &context)?;
+        let bottom = context.alloc_raw::<u32>(1024, AllocationPlacement::Bottom, false)?;
+        assert!(mapping.as_ptr() < bottom.as_ptr());
+        drop(mapping);
+        output.generic_lookup_mapping_placement = AllocationPlacement::Bottom;
+        let mapping = output.allocate_generic_lookup_mapping(&circuit, &context)?;
+        let top = context.alloc_raw::<u32>(1024, AllocationPlacement::Top, false)?;
+        assert!(top.as_ptr() < mapping.as_ptr());
+        Ok(())
+    }
+
+    #[test]
+    fn test_public_inputs_on_last_row() -> CudaResult<()> {
+        let context = ProverContext::new(&ProverContextConfig::default())?;
+        let mut circuit = get_circuit();
+        circuit.public_inputs.push((
+            BoundaryConstraintLocation::LastRow,
+            ColumnAddress::WitnessSubtree(0),
+        ));
+        let mut output = allocate_trace_holders(&circuit, &context)?;
+        let mut callbacks = Callbacks::new();
+        let result = output.produce_public_inputs(&Arc::new(circuit), &mut callbacks, &context);
+        assert_eq!(result.err(), Some(CudaError::ErrorInvalidValue));
+        assert!(output.public_inputs.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_compute_public_inputs_cpu() {
+        let mut circuit = get_circuit();
+        circuit.public_inputs = vec![
+            (
+                BoundaryConstraintLocation::OneBeforeLastRow,
+                ColumnAddress::WitnessSubtree(2),
+            ),
+            (
+                BoundaryConstraintLocation::FirstRow,
+                ColumnAddress::WitnessSubtree(1),
+            ),
+            (
+                BoundaryConstraintLocation::OneBeforeLastRow,
+                ColumnAddress::WitnessSubtree(0),
+            ),
+            (
+                BoundaryConstraintLocation::FirstRow,
+                ColumnAddress::WitnessSubtree(3),
+            ),
+        ];
+        let first_row = [10, 11, 12, 13].map(BF::new);
+        let one_before_last_row = [20, 21, 22, 23].map(BF::new);
+        let public_inputs = compute_public_inputs_cpu(&circuit, &first_row, &one_before_last_row);
+        // first row values go first, both groups keep the circuit order
+        assert_eq!(public_inputs, [11, 13, 22, 20].map(BF::new));
+    }
+
+    #[test]
+    fn test_public_inputs_match_cpu() -> CudaResult<()> {
+        let context = ProverContext::new(&ProverContextConfig::default())?;
+        let circuit = Arc::new(get_circuit());
+        assert!(!circuit.public_inputs.is_empty());
+        let mut output = allocate_trace_holders(&circuit, &context)?;
+        let trace_len = circuit.trace_len;
+        let columns_count = output.witness_holder.columns_count;
+        let h_evaluations = (0..columns_count * trace_len)
+            .map(|i| BF::new(i as u32))
+            .collect_vec();
+        memory_copy(
+            output.witness_holder.get_uninit_evaluations_mut(),
+            &h_evaluations,
+        )?;
+        let mut callbacks = Callbacks::new();
+        output.produce_public_inputs(&circuit, &mut callbacks, &context)?;
+        context.get_exec_stream().synchronize()?;
+        let get_row = |row: usize| {
+            (0..columns_count)
+                .map(|column| h_evaluations[column * trace_len + row])
+                .collect_vec()
+        };
+        let expected = compute_public_inputs_cpu(&circuit, &get_row(0), &get_row(trace_len - 2));
+        let public_inputs = output.public_inputs.as_ref().unwrap();
+        assert_eq!(unsafe { public_inputs.get_accessor().get() }, &expected[..]);
+        Ok(())
+    }
+}
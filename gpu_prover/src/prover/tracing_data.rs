@@ -10,6 +10,7 @@ use crate::witness::trace_main::{
 };
 use era_cudart::result::CudaResult;
 use fft::GoodAllocator;
+use std::ops::Range;
 
 pub enum TracingDataDevice {
     Main {
@@ -32,16 +33,28 @@ pub struct TracingDataTransfer<'a, A: GoodAllocator> {
     pub circuit_type: CircuitType,
     pub data_host: TracingDataHost<A>,
     pub data_device: TracingDataDevice,
+    /// Trace rows transferred in each of the ranges recorded in `transfer`.
+    /// Main traces are split into several ranges, delegation traces are transferred as a single one.
+    pub ranges: Vec<Range<usize>>,
     pub transfer: Transfer<'a>,
 }
 
+fn split_into_ranges(len: usize, ranges_count: usize) -> Vec<Range<usize>> {
+    assert_ne!(ranges_count, 0);
+    let range_len = len.div_ceil(ranges_count).max(1);
+    (0..len)
+        .step_by(range_len)
+        .map(|start| start..(start + range_len).min(len))
+        .collect()
+}
+
 impl<'a, A: GoodAllocator + 'a> TracingDataTransfer<'a, A> {
     pub fn new(
         circuit_type: CircuitType,
         data_host: TracingDataHost<A>,
         context: &ProverContext,
     ) -> CudaResult<Self> {
-        let data_device = match &data_host {
+        let (data_device, ranges) = match &data_host {
             TracingDataHost::Main {
                 setup_and_teardown,
                 trace,
@@ -54,10 +67,13 @@ impl<'a, A: GoodAllocator + 'a> TracingDataTransfer<'a, A> {
                 let setup_and_teardown = ShuffleRamSetupAndTeardownDevice { lazy_init_data };
                 let cycle_data = context.alloc(len, AllocationPlacement::Top)?;
                 let trace = MainTraceDevice { cycle_data };
-                TracingDataDevice::Main {
+                let data_device = TracingDataDevice::Main {
                     setup_and_teardown,
                     trace,
-                }
+                };
+                let ranges =
+                    split_into_ranges(len, context.get_tracing_data_transfer_ranges_count());
+                (data_device, ranges)
             }
             TracingDataHost::Delegation(trace) => {
                 let d_write_timestamp =
@@ -82,7 +98,8 @@ impl<'a, A: GoodAllocator + 'a> TracingDataTransfer<'a, A> {
                     indirect_reads: d_indirect_reads,
                     indirect_writes: d_indirect_writes,
                 };
-                TracingDataDevice::Delegation(trace)
+                let ranges = vec![0..trace.num_requests];
+                (TracingDataDevice::Delegation(trace), ranges)
             }
         };
         let transfer = Transfer::new()?;
@@ -91,6 +108,7 @@ impl<'a, A: GoodAllocator + 'a> TracingDataTransfer<'a, A> {
             circuit_type,
             data_host,
             data_device,
+            ranges,
             transfer,
         })
     }
@@ -105,6 +123,8 @@ impl<'a, A: GoodAllocator + 'a> TracingDataTransfer<'a, A> {
                     setup_and_teardown: d_setup_and_teardown,
                     trace: d_trace,
                 } => {
+                    // setup and teardown data is transferred as a whole before the first range,
+                    // because the last row of a range also reads the first row of the next one
                     if let Some(h_setup_and_teardown) = h_setup_and_teardown {
                         self.transfer.schedule(
                             h_setup_and_teardown.lazy_init_data.clone(),
@@ -118,11 +138,15 @@ impl<'a, A: GoodAllocator + 'a> TracingDataTransfer<'a, A> {
                             context.get_h2d_stream(),
                         )?;
                     }
-                    self.transfer.schedule(
-                        h_trace.cycle_data.clone(),
-                        &mut d_trace.cycle_data,
-                        context,
-                    )?;
+                    for range in self.ranges.iter() {
+                        self.transfer.schedule_range(
+                            h_trace.cycle_data.clone(),
+                            range.clone(),
+                            &mut d_trace.cycle_data,
+                            context,
+                        )?;
+                        self.transfer.record_range_transferred(context)?;
+                    }
                 }
                 TracingDataDevice::Delegation(_) => panic!("expected main trace"),
             },
@@ -149,9 +173,25 @@ impl<'a, A: GoodAllocator + 'a> TracingDataTransfer<'a, A> {
                         &mut d_trace.indirect_writes,
                         context,
                     )?;
+                    self.transfer.record_range_transferred(context)?;
                 }
             },
         }
         self.transfer.record_transferred(context)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_into_ranges() {
+        assert_eq!(split_into_ranges(10, 1), vec![0..10]);
+        assert_eq!(split_into_ranges(10, 3), vec![0..4, 4..8, 8..10]);
+        assert_eq!(split_into_ranges(3, 4), vec![0..1, 1..2, 2..3]);
+        let ranges = split_into_ranges((1 << 22) - 1, 4);
+        assert_eq!(ranges.len(), 4);
+        assert_eq!(ranges.last().unwrap().end, (1 << 22) - 1);
+    }
+}
@@ -3,13 +3,15 @@ use crate::prover::callbacks::Callbacks;
 use era_cudart::event::{CudaEvent, CudaEventCreateFlags};
 use era_cudart::memory::memory_copy_async;
 use era_cudart::result::CudaResult;
-use era_cudart::slice::{CudaSlice, CudaSliceMut};
+use era_cudart::slice::{CudaSlice, CudaSliceMut, DeviceSlice};
 use era_cudart::stream::CudaStreamWaitEventFlags;
+use std::ops::{Deref, Range};
 use std::sync::Arc;
 
 pub struct Transfer<'a> {
     pub(crate) allocated: CudaEvent,
     pub(crate) transferred: CudaEvent,
+    pub(crate) ranges_transferred: Vec<CudaEvent>,
     pub(crate) callbacks: Callbacks<'a>,
 }
 
@@ -18,6 +20,7 @@ impl<'a> Transfer<'a> {
         Ok(Self {
             allocated: CudaEvent::create_with_flags(CudaEventCreateFlags::DISABLE_TIMING)?,
             transferred: CudaEvent::create_with_flags(CudaEventCreateFlags::DISABLE_TIMING)?,
+            ranges_transferred: vec![],
             callbacks: Callbacks::new(),
         })
     }
@@ -48,6 +51,24 @@ impl<'a> Transfer<'a> {
         self.callbacks.schedule(f, stream)
     }
 
+    /// Same as `schedule`, but only copies `range` of `src` into the same range of `dst`.
+    pub fn schedule_range<T>(
+        &mut self,
+        src: Arc<impl Deref<Target = [T]> + Send + Sync + 'a>,
+        range: Range<usize>,
+        dst: &mut DeviceSlice<T>,
+        context: &ProverContext,
+    ) -> CudaResult<()> {
+        assert_eq!(src.len(), dst.len());
+        self.ensure_allocated(context)?;
+        let stream = context.get_h2d_stream();
+        memory_copy_async(&mut dst[range.clone()], &src[range], stream)?;
+        let f = move || {
+            let _ = src.clone();
+        };
+        self.callbacks.schedule(f, stream)
+    }
+
     pub(crate) fn record_transferred(&self, context: &ProverContext) -> CudaResult<()> {
         self.transferred.record(context.get_h2d_stream())
     }
@@ -57,6 +78,32 @@ impl<'a> Transfer<'a> {
             .get_exec_stream()
             .wait_event(&self.transferred, CudaStreamWaitEventFlags::DEFAULT)
     }
+
+    /// Marks the end of the range of transfers scheduled since the previous range,
+    /// so it can be awaited separately with `ensure_range_transferred`.
+    pub(crate) fn record_range_transferred(&mut self, context: &ProverContext) -> CudaResult<()> {
+        let event = CudaEvent::create_with_flags(CudaEventCreateFlags::DISABLE_TIMING)?;
+        event.record(context.get_h2d_stream())?;
+        self.ranges_transferred.push(event);
+        Ok(())
+    }
+
+    pub fn ranges_count(&self) -> usize {
+        self.ranges_transferred.len()
+    }
+
+    /// Transfers are executed in order on the h2d stream,
+    /// so waiting for a range also waits for all ranges before it.
+    pub fn ensure_range_transferred(
+        &self,
+        index: usize,
+        context: &ProverContext,
+    ) -> CudaResult<()> {
+        context.get_exec_stream().wait_event(
+            &self.ranges_transferred[index],
+            CudaStreamWaitEventFlags::DEFAULT,
+        )
+    }
 }
 
 #[cfg(test)]
@@ -76,4 +123,28 @@ mod tests {
         transfer.record_transferred(&context)?;
         Ok(())
     }
+
+    #[test]
+    fn test_transfer_ranges() -> CudaResult<()> {
+        let context = ProverContext::new(&ProverContextConfig::default())?;
+        let src = Arc::new((0..1024u32).collect::<Vec<_>>());
+        let mut transfer = Transfer::new()?;
+        let mut dst = context.alloc(1024, AllocationPlacement::BestFit)?;
+        transfer.record_allocated(&context)?;
+        for range in [0..256, 256..1000, 1000..1024] {
+            transfer.schedule_range(src.clone(), range, &mut dst, &context)?;
+            transfer.record_range_transferred(&context)?;
+        }
+        transfer.record_transferred(&context)?;
+        assert_eq!(transfer.ranges_count(), 3);
+        let mut result = vec![0u32; 1024];
+        for index in 0..transfer.ranges_count() {
+            transfer.ensure_range_transferred(index, &context)?;
+        }
+        transfer.ensure_transferred(&context)?;
+        memory_copy_async(&mut result, &dst, context.get_exec_stream())?;
+        context.get_exec_stream().synchronize()?;
+        assert_eq!(&result, src.as_ref());
+        Ok(())
+    }
 }
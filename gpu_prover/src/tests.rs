@@ -1,6 +1,10 @@
 use crate::allocator::host::ConcurrentStaticHostAllocator;
 use crate::circuit_type::CircuitType;
 use crate::circuit_type::MainCircuitType;
+use crate::execution::prover::{
+    BatchResumeState, ExecutableBinary, ExecutionProver, ReproducibilityConfig, SplitStrategy,
+    WorkerError,
+};
 use crate::prover::context::{ProverContext, ProverContextConfig};
 use crate::prover::memory::commit_memory;
 use crate::prover::setup::SetupPrecomputations;
@@ -42,13 +46,14 @@ use prover::{
     DelegationMemoryOnlyWitnessEvaluationData, MemoryOnlyWitnessEvaluationData,
     ShuffleRamSetupAndTeardown, VectorMemoryImplWithRom, WitnessEvaluationAuxData,
 };
+use serial_test::serial;
 use std::alloc::Global;
 use std::collections::HashMap;
 use std::ffi::CStr;
 use std::io::Read;
 use std::mem;
 use std::mem::MaybeUninit;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use trace_and_split::setups::{
     risc_v_cycles, DelegationCircuitPrecomputations, MainCircuitPrecomputations,
 };
@@ -75,11 +80,38 @@ fn init_logger() {
         .init();
 }
 
+// shuts the global host allocator down when dropped, so that tests initializing it can run one after another
+struct GlobalHostAllocatorGuard;
+
+impl GlobalHostAllocatorGuard {
+    fn new(
+        host_allocations_count: usize,
+        blocks_per_allocation_count: usize,
+        block_log_size: u32,
+    ) -> CudaResult<Self> {
+        ProverContext::initialize_global_host_allocator(
+            host_allocations_count,
+            blocks_per_allocation_count,
+            block_log_size,
+        )?;
+        Ok(Self)
+    }
+}
+
+impl Drop for GlobalHostAllocatorGuard {
+    fn drop(&mut self) {
+        if !std::thread::panicking() {
+            ProverContext::shutdown_global_host_allocator();
+        }
+    }
+}
+
 #[test]
+#[serial(global_host_allocator)]
 fn test_prove_hashed_fibonacci() -> CudaResult<()> {
     init_logger();
     let instant = std::time::Instant::now();
-    ProverContext::initialize_global_host_allocator(4, 1 << 8, 22)?;
+    let _global_host_allocator = GlobalHostAllocatorGuard::new(4, 1 << 8, 22)?;
     let mut prover_context_config = ProverContextConfig::default();
     prover_context_config.allocation_block_log_size = 22;
     let prover_context = ProverContext::new(&prover_context_config)?;
@@ -95,7 +127,7 @@ fn test_prove_hashed_fibonacci() -> CudaResult<()> {
         .read_to_end(&mut binary)
         .unwrap();
 
-    let expected_final_pc = find_binary_exit_point(&binary);
+    let expected_final_pc = find_binary_exit_point(&binary).unwrap();
     println!(
         "Expected final PC for base program is 0x{:08x}",
         expected_final_pc
@@ -130,11 +162,215 @@ fn test_prove_hashed_fibonacci() -> CudaResult<()> {
     Ok(())
 }
 
+fn hashed_fibonacci_prover() -> ExecutionProver<usize> {
+    let mut binary = vec![];
+    std::fs::File::open("../examples/hashed_fibonacci/app.bin")
+        .unwrap()
+        .read_to_end(&mut binary)
+        .unwrap();
+    let main_binary = ExecutableBinary {
+        key: 0,
+        circuit_type: MainCircuitType::RiscVCycles,
+        bytecode: get_padded_binary(&binary),
+    };
+    ExecutionProver::new(1, vec![main_binary])
+}
+
+fn hashed_fibonacci_non_determinism() -> QuasiUARTSource {
+    QuasiUARTSource::new_with_reads(vec![1 << 16, 1 << 14])
+}
+
+fn compare_batch_proofs(
+    left_main_proofs: &[Proof],
+    left_delegation_proofs: &[(u32, Vec<Proof>)],
+    right_main_proofs: &[Proof],
+    right_delegation_proofs: &[(u32, Vec<Proof>)],
+) {
+    for (left, right) in left_main_proofs.iter().zip_eq(right_main_proofs.iter()) {
+        compare_proofs(left, right);
+    }
+    for ((left_type, left), (right_type, right)) in left_delegation_proofs
+        .iter()
+        .zip_eq(right_delegation_proofs.iter())
+    {
+        assert_eq!(left_type, right_type);
+        for (left, right) in left.iter().zip_eq(right.iter()) {
+            compare_proofs(left, right);
+        }
+    }
+}
+
+#[test]
+fn test_reproducible_batch() {
+    let mut prover = hashed_fibonacci_prover();
+    prover.set_reproducibility_config(ReproducibilityConfig {
+        pow_start_nonce: Some(0),
+    });
+    let external_challenges = ExternalChallenges::draw_from_transcript_seed(Seed([0; 8]), true);
+    let prove = |batch_id| {
+        prover
+            .prove(
+                batch_id,
+                &0,
+                10,
+                hashed_fibonacci_non_determinism(),
+                external_challenges,
+            )
+            .unwrap()
+    };
+    let (left_registers, left_main_proofs, left_delegation_proofs) = prove(0);
+    let (right_registers, right_main_proofs, right_delegation_proofs) = prove(1);
+    assert!(left_registers.is_some());
+    assert_eq!(left_registers, right_registers);
+    compare_batch_proofs(
+        &left_main_proofs,
+        &left_delegation_proofs,
+        &right_main_proofs,
+        &right_delegation_proofs,
+    );
+    // skipping final register values must not change the proofs
+    prover.set_want_final_registers(false);
+    let (registers, main_proofs, _) = prover
+        .prove(
+            2,
+            &0,
+            10,
+            hashed_fibonacci_non_determinism(),
+            external_challenges,
+        )
+        .unwrap();
+    assert!(registers.is_none());
+    for (left, right) in left_main_proofs.iter().zip_eq(main_proofs.iter()) {
+        compare_proofs(left, right);
+    }
+}
+
 #[test]
+fn test_contiguous_split_batch() {
+    let mut prover = hashed_fibonacci_prover();
+    prover.set_reproducibility_config(ReproducibilityConfig {
+        pow_start_nonce: Some(0),
+    });
+    let external_challenges = ExternalChallenges::draw_from_transcript_seed(Seed([0; 8]), true);
+    let (round_robin_registers, round_robin_main_proofs, round_robin_delegation_proofs) = prover
+        .prove(
+            0,
+            &0,
+            10,
+            hashed_fibonacci_non_determinism(),
+            external_challenges,
+        )
+        .unwrap();
+
+    // chained contiguous workers must trace the same chunks as the round-robin ones
+    prover.set_cycles_split_strategy(SplitStrategy::Contiguous);
+    let (registers, main_proofs, delegation_proofs) = prover
+        .prove(
+            1,
+            &0,
+            10,
+            hashed_fibonacci_non_determinism(),
+            external_challenges,
+        )
+        .unwrap();
+    assert_eq!(registers, round_robin_registers);
+    compare_batch_proofs(
+        &round_robin_main_proofs,
+        &round_robin_delegation_proofs,
+        &main_proofs,
+        &delegation_proofs,
+    );
+}
+
+#[test]
+fn test_resume_batch() {
+    let mut prover = hashed_fibonacci_prover();
+    prover.set_reproducibility_config(ReproducibilityConfig {
+        pow_start_nonce: Some(0),
+    });
+    let external_challenges = ExternalChallenges::draw_from_transcript_seed(Seed([0; 8]), true);
+    let (_, full_main_proofs, full_delegation_proofs) = prover
+        .prove(
+            0,
+            &0,
+            10,
+            hashed_fibonacci_non_determinism(),
+            external_challenges,
+        )
+        .unwrap();
+
+    // the resumed batch only produces proofs of the chunks that are not completed yet
+    let mut resume_state = BatchResumeState::new();
+    resume_state.mark_completed(CircuitType::Main(MainCircuitType::RiscVCycles), 0);
+    prover.set_resume_state(Some(resume_state));
+    let (_, main_proofs, delegation_proofs) = prover
+        .prove(
+            1,
+            &0,
+            10,
+            hashed_fibonacci_non_determinism(),
+            external_challenges,
+        )
+        .unwrap();
+    compare_batch_proofs(
+        &full_main_proofs[1..],
+        &full_delegation_proofs,
+        &main_proofs,
+        &delegation_proofs,
+    );
+}
+
+#[test]
+fn test_touched_ram_stats_callback() {
+    let mut prover = hashed_fibonacci_prover();
+    let stats = Arc::new(Mutex::new(vec![]));
+    let stats_clone = stats.clone();
+    prover.set_touched_ram_stats_callback(Some(Arc::new(move |chunk_stats| {
+        stats_clone.lock().unwrap().push(chunk_stats)
+    })));
+    let (_, main_memory_commitments, _) = prover
+        .commit_memory(0, &0, 10, hashed_fibonacci_non_determinism())
+        .unwrap();
+    let stats = stats.lock().unwrap();
+    assert_eq!(stats.len(), main_memory_commitments.len());
+    assert!(stats
+        .iter()
+        .enumerate()
+        .all(|(index, chunk_stats)| chunk_stats.chunk_index == index));
+    let total = stats
+        .iter()
+        .map(|chunk_stats| chunk_stats.delta)
+        .sum::<usize>();
+    assert_eq!(total, stats.last().unwrap().touched_cells);
+}
+
+#[test]
+fn test_prove_execution_not_finished() {
+    let prover = hashed_fibonacci_prover();
+    let external_challenges = ExternalChallenges::draw_from_transcript_seed(Seed([0; 8]), true);
+    // with no chunks allowed every CPU worker fails, the error must reach the caller
+    let result = prover.prove(
+        0,
+        &0,
+        0,
+        hashed_fibonacci_non_determinism(),
+        external_challenges,
+    );
+    assert!(matches!(
+        result,
+        Err(WorkerError::ExecutionNotFinished {
+            chunks_traced: 0,
+            ..
+        })
+    ));
+}
+
+#[test]
+#[serial(global_host_allocator)]
 fn bench_prove_hashed_fibonacci() -> CudaResult<()> {
     init_logger();
     let instant = std::time::Instant::now();
-    ProverContext::initialize_global_host_allocator(4, 1 << 8, 22)?;
+    let _global_host_allocator = GlobalHostAllocatorGuard::new(4, 1 << 8, 22)?;
     println!("host allocator initialized in {:?}", instant.elapsed());
     let instant = std::time::Instant::now();
     let device_count = get_device_count()?;
@@ -168,7 +404,7 @@ fn bench_prove_hashed_fibonacci() -> CudaResult<()> {
         .read_to_end(&mut binary)
         .unwrap();
 
-    let expected_final_pc = find_binary_exit_point(&binary);
+    let expected_final_pc = find_binary_exit_point(&binary).unwrap();
     println!(
         "Expected final PC for base program is 0x{:08x}",
         expected_final_pc
@@ -528,6 +764,7 @@ fn prove_image_execution_for_machine_with_gpu_tracers<
                 NUM_QUERIES,
                 POW_BITS,
                 Some(cpu_proof.pow_nonce),
+                None,
                 RECOMPUTE_COSETS_FOR_CORRECTNESS,
                 TREES_CACHE_MODE_FOR_CORRECTNESS,
                 prover_context,
@@ -674,6 +911,7 @@ fn prove_image_execution_for_machine_with_gpu_tracers<
                     NUM_QUERIES,
                     POW_BITS,
                     Some(cpu_proof.pow_nonce),
+                    None,
                     RECOMPUTE_COSETS_FOR_CORRECTNESS,
                     TREES_CACHE_MODE_FOR_CORRECTNESS,
                     prover_context,
@@ -823,6 +1061,7 @@ fn bench_proof_main<ND: NonDeterminismCSRSource<VectorMemoryImplWithRom>>(
                 NUM_QUERIES,
                 POW_BITS,
                 None,
+                None,
                 RECOMPUTE_COSETS_FOR_BENCHMARKS,
                 TREES_CACHE_MODE_FOR_BENCHMARKS,
                 context,
@@ -884,6 +1123,7 @@ fn bench_proof_main<ND: NonDeterminismCSRSource<VectorMemoryImplWithRom>>(
                 NUM_QUERIES,
                 POW_BITS,
                 None,
+                None,
                 RECOMPUTE_COSETS_FOR_BENCHMARKS,
                 TREES_CACHE_MODE_FOR_BENCHMARKS,
                 context,
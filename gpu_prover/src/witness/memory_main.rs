@@ -15,6 +15,7 @@ use era_cudart::execution::{CudaLaunchConfig, KernelFunction};
 use era_cudart::result::CudaResult;
 use era_cudart::slice::CudaSlice;
 use era_cudart::stream::CudaStream;
+use std::ops::Range;
 
 const MAX_SHUFFLE_RAM_ACCESS_SETS_COUNT: usize = 4;
 
@@ -78,6 +79,8 @@ cuda_kernel!(GenerateMemoryValuesMain,
         trace: MainTraceRaw,
         memory: MutPtrAndStride<BF>,
         count: u32,
+        offset: u32,
+        rows_count: u32,
     )
 );
 
@@ -92,6 +95,8 @@ cuda_kernel!(GenerateMemoryAndWitnessValuesMain,
         memory: MutPtrAndStride<BF>,
         witness: MutPtrAndStride<BF>,
         count: u32,
+        offset: u32,
+        rows_count: u32,
     )
 );
 
@@ -99,23 +104,34 @@ pub(crate) fn generate_memory_values_main(
     subtree: &MemorySubtree,
     setup_and_teardown: &ShuffleRamSetupAndTeardownDevice,
     trace: &MainTraceDevice,
+    rows: Range<usize>,
     memory: &mut DeviceMatrixMut<BF>,
     stream: &CudaStream,
 ) -> CudaResult<()> {
     let count = trace.cycle_data.len();
+    assert!(rows.end <= count);
     assert_eq!(setup_and_teardown.lazy_init_data.len(), count);
     assert_eq!(memory.stride(), count + 1);
     assert_eq!(memory.cols(), subtree.total_width);
     assert!(count <= u32::MAX as usize);
     let count = count as u32;
+    let offset = rows.start as u32;
+    let rows_count = rows.len() as u32;
     let subtree = subtree.into();
     let setup_and_teardown = setup_and_teardown.into();
     let trace = trace.into();
     let memory = memory.as_mut_ptr_and_stride();
-    let (grid_dim, block_dim) = get_grid_block_dims_for_threads_count(WARP_SIZE * 4, count);
+    let (grid_dim, block_dim) = get_grid_block_dims_for_threads_count(WARP_SIZE * 4, rows_count);
     let config = CudaLaunchConfig::basic(grid_dim, block_dim, stream);
-    let args =
-        GenerateMemoryValuesMainArguments::new(subtree, setup_and_teardown, trace, memory, count);
+    let args = GenerateMemoryValuesMainArguments::new(
+        subtree,
+        setup_and_teardown,
+        trace,
+        memory,
+        count,
+        offset,
+        rows_count,
+    );
     GenerateMemoryValuesMainFunction::default().launch(&config, &args)
 }
 
@@ -125,18 +141,22 @@ pub(crate) fn generate_memory_and_witness_values_main(
     setup_and_teardown: &ShuffleRamSetupAndTeardownDevice,
     lazy_init_address_aux_vars: &cs::definitions::ShuffleRamAuxComparisonSet,
     trace: &MainTraceDevice,
+    rows: Range<usize>,
     timestamp_high_from_circuit_sequence: TimestampScalar,
     memory: &mut DeviceMatrixMut<BF>,
     witness: &mut DeviceMatrixMut<BF>,
     stream: &CudaStream,
 ) -> CudaResult<()> {
     let count = trace.cycle_data.len();
+    assert!(rows.end <= count);
     assert_eq!(setup_and_teardown.lazy_init_data.len(), count);
     assert_eq!(memory.stride(), count + 1);
     assert_eq!(memory.cols(), subtree.total_width);
     assert_eq!(witness.stride(), count + 1);
     assert!(count <= u32::MAX as usize);
     let count = count as u32;
+    let offset = rows.start as u32;
+    let rows_count = rows.len() as u32;
     let subtree = subtree.into();
     let memory_queries_timestamp_comparison_aux_vars =
         memory_queries_timestamp_comparison_aux_vars.into();
@@ -145,7 +165,7 @@ pub(crate) fn generate_memory_and_witness_values_main(
     let trace = trace.into();
     let memory = memory.as_mut_ptr_and_stride();
     let witness = witness.as_mut_ptr_and_stride();
-    let (grid_dim, block_dim) = get_grid_block_dims_for_threads_count(WARP_SIZE * 4, count);
+    let (grid_dim, block_dim) = get_grid_block_dims_for_threads_count(WARP_SIZE * 4, rows_count);
     let config = CudaLaunchConfig::basic(grid_dim, block_dim, stream);
     let args = GenerateMemoryAndWitnessValuesMainArguments::new(
         subtree,
@@ -157,6 +177,8 @@ pub(crate) fn generate_memory_and_witness_values_main(
         memory,
         witness,
         count,
+        offset,
+        rows_count,
     );
     GenerateMemoryAndWitnessValuesMainFunction::default().launch(&config, &args)
 }
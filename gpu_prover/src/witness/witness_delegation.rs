@@ -18,6 +18,7 @@ cuda_kernel!(GenerateWitnessDelegationKernel,
     witness: *mut BF,
     lookup_mapping: *mut u32,
     stride: u32,
+    offset: u32,
     count: u32,
 );
 
@@ -56,6 +57,7 @@ pub fn generate_witness_values_delegation(
         witness,
         lookup_mapping,
         stride,
+        0,
         count,
     );
     let kernel = match circuit_type {
@@ -10,6 +10,7 @@ use era_cudart::execution::{CudaLaunchConfig, KernelFunction};
 use era_cudart::result::CudaResult;
 use era_cudart::slice::CudaSlice;
 use era_cudart::stream::CudaStream;
+use std::ops::Range;
 
 cuda_kernel!(GenerateWitnessMainKernel,
     generate_witness_main_kernel,
@@ -19,6 +20,7 @@ cuda_kernel!(GenerateWitnessMainKernel,
     witness: *mut BF,
     lookup_mapping: *mut u32,
     stride: u32,
+    offset: u32,
     count: u32,
 );
 
@@ -28,24 +30,27 @@ generate_witness_main_kernel!(ab_generate_reduced_risc_v_machine_witness_kernel)
 generate_witness_main_kernel!(ab_generate_reduced_risc_v_log_23_machine_witness_kernel);
 generate_witness_main_kernel!(ab_generate_risc_v_cycles_witness_kernel);
 
+/// Generates witness values for `rows` of the trace only.
 pub fn generate_witness_values_main(
     circuit_type: MainCircuitType,
     trace: &MainTraceDevice,
+    rows: Range<usize>,
     generic_lookup_tables: &DeviceMatrix<BF>,
     memory: &DeviceMatrix<BF>,
     witness: &mut DeviceMatrixMut<BF>,
     lookup_mapping: &mut DeviceMatrixMut<u32>,
     stream: &CudaStream,
 ) -> CudaResult<()> {
-    let count = trace.cycle_data.len();
+    assert!(rows.end <= trace.cycle_data.len());
     let stride = generic_lookup_tables.stride();
     assert_eq!(memory.stride(), stride);
     assert_eq!(witness.stride(), stride);
     assert_eq!(lookup_mapping.stride(), stride);
     assert!(stride < u32::MAX as usize);
     let stride = stride as u32;
-    assert!(count < u32::MAX as usize);
-    let count = count as u32;
+    assert!(rows.end < u32::MAX as usize);
+    let offset = rows.start as u32;
+    let count = rows.len() as u32;
     let trace = trace.into();
     let generic_lookup_tables = generic_lookup_tables.as_ptr();
     let memory = memory.as_ptr();
@@ -60,6 +65,7 @@ pub fn generate_witness_values_main(
         witness,
         lookup_mapping,
         stride,
+        offset,
         count,
     );
     let kernel = match circuit_type {
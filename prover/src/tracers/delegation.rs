@@ -38,22 +38,53 @@ impl<A: GoodAllocator> DelegationWitness<A> {
     pub fn assert_consistency(&self) {
         #[cfg(debug_assertions)]
         {
-            assert!((self.num_requests + 1).is_power_of_two());
-            let baseline = self.write_timestamp.len();
+            if let Err(error) = self.check_consistency() {
+                panic!("{error}");
+            }
+        }
+    }
 
-            assert_eq!(
+    /// Same checks as `assert_consistency`, but done in release builds too and reported as an error
+    pub fn check_consistency(&self) -> Result<(), String> {
+        if !(self.num_requests + 1).is_power_of_two() {
+            return Err(format!(
+                "witness capacity is {}, but it must be one less than a power of two",
+                self.num_requests
+            ));
+        }
+        let baseline = self.write_timestamp.len();
+        if baseline > self.num_requests {
+            return Err(format!(
+                "witness has {baseline} delegations, but capacity is {}",
+                self.num_requests
+            ));
+        }
+        let parts = [
+            (
+                "register accesses",
                 self.register_accesses.len(),
-                baseline * self.num_register_accesses_per_delegation
-            );
-            assert_eq!(
+                self.num_register_accesses_per_delegation,
+            ),
+            (
+                "indirect reads",
                 self.indirect_reads.len(),
-                baseline * self.num_indirect_reads_per_delegation
-            );
-            assert_eq!(
+                self.num_indirect_reads_per_delegation,
+            ),
+            (
+                "indirect writes",
                 self.indirect_writes.len(),
-                baseline * self.num_indirect_writes_per_delegation
-            );
+                self.num_indirect_writes_per_delegation,
+            ),
+        ];
+        for (name, len, per_delegation) in parts {
+            if len != baseline * per_delegation {
+                return Err(format!(
+                    "witness has {len} {name} for {baseline} delegations, expected {}",
+                    baseline * per_delegation
+                ));
+            }
         }
+        Ok(())
     }
 
     pub fn at_capacity(&self) -> bool {
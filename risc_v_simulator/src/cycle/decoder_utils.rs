@@ -11,6 +11,7 @@ pub const OPCODE_JALR: u8 = 0b1100111;
 pub const OPCODE_BRANCH: u8 = 0b1100011;
 pub const OPCODE_LOAD: u8 = 0b0000011;
 pub const OPCODE_STORE: u8 = 0b0100011;
+pub const OPCODE_MISC_MEM: u8 = 0b0001111;
 pub const OPCODE_SYSTEM: u8 = 0b1110011;
 
 pub(crate) const SUB_FUNCT7: u8 = 0b0100000;
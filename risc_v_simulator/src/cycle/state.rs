@@ -1142,10 +1142,20 @@ impl<Config: MachineConfig> RiscV32State<Config> {
                         };
                     }
                 },
-                // 0b0001111 => {
-                //     // nothing to do in fence, our memory is linear
-                //     rd = 0;
-                // },
+                0b0001111 => {
+                    // FENCE and FENCE.I, nothing to do, our memory is linear
+                    report_opcode("FENCE");
+                    match ITypeOpcode::funct3(instr) {
+                        0 | 1 => {
+                            // RD field is ignored
+                            rd = 0;
+                        },
+                        _ => {
+                            trap = TrapReason::IllegalInstruction;
+                            break 'cycle_block;
+                        },
+                    }
+                },
                 0b1110011 => {
                     // various control instructions, we implement only a subset
                     const ZICSR_MASK: u32 = 0x3;
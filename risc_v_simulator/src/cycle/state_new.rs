@@ -842,6 +842,22 @@ impl<Config: MachineConfig> RiscV32StateForUnrolledProver<Config> {
                     }
                 }
             }
+            OPCODE_MISC_MEM => {
+                // FENCE and FENCE.I, nothing to do, our memory is linear
+                match funct3 {
+                    0 | 1 => {
+                        report_opcode("FENCE");
+                    }
+                    _ => {
+                        panic!("Unknown opcode 0x{:08x}", opcode);
+                    }
+                }
+
+                // RD field is ignored, and must be masked as-is it did access x0
+                let rd = 0;
+                let rd_old_value = self.get_register(rd);
+                tracer.trace_rd_write(rd, rd_old_value, 0);
+            }
             OPCODE_SYSTEM => {
                 // various control instructions, we implement only a subset
                 const ZICSR_MASK: u8 = 0x3;
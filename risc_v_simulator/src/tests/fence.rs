@@ -0,0 +1,34 @@
+use super::*;
+
+// FENCE is a no-op, and registers must stay intact even if the ignored RD field is set
+fn test_fence_encoding(encoding: u32) {
+    {
+        let mut state = RiscV32State::<IMStandardIsaConfig>::initial(INITIAL_PC);
+        state.observable.registers[3] = 0xdead;
+        let mut memory = VectorMemoryImpl::new_for_byte_size(1 << 12);
+        memory.populate(INITIAL_PC, encoding);
+        let mut mmu = NoMMU::default();
+        state.cycle(&mut memory, &mut (), &mut mmu, &mut ZeroedSource);
+        assert_eq!(state.observable.pc, INITIAL_PC + 4);
+        assert_eq!(state.observable.registers[3], 0xdead);
+    }
+    {
+        let mut state = RiscV32StateForUnrolledProver::<IMStandardIsaConfig>::initial(INITIAL_PC);
+        state.observable.registers[3] = 0xdead;
+        let mut memory = VectorMemoryImpl::new_for_byte_size(1 << 12);
+        memory.populate(INITIAL_PC, encoding);
+        let _ = state.run_cycles(&mut memory, &mut (), &mut ZeroedSource, &mut NoExtraCSRs, 1);
+        assert_eq!(state.observable.pc, INITIAL_PC + 4);
+        assert_eq!(state.observable.registers[3], 0xdead);
+    }
+}
+
+#[test]
+fn test_fence() {
+    // fence iorw, iorw
+    test_fence_encoding(0x0ff0000f);
+    // same with x3 in the ignored RD field
+    test_fence_encoding(0x0ff0018f);
+    // fence.i
+    test_fence_encoding(0x0000100f);
+}
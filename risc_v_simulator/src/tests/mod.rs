@@ -11,6 +11,7 @@ use std::collections::HashMap;
 mod add;
 mod addi;
 mod beq;
+mod fence;
 mod mul;
 mod mulh;
 mod mulhu;
@@ -394,6 +394,9 @@ fn main() {
             recompute,
             mode,
         } => {
+            if let Err(error) = mode.validate_for(*universal_verifier) {
+                panic!("{error}");
+            }
             let base_layer_bin = std::fs::read(bin).expect("Failed to read base layer binary file");
 
             let (end_params, aux_values) = generate_constants_for_binary(
@@ -285,13 +285,14 @@ pub fn create_proofs_internal(
                     {
                         println!("**** proving using GPU ****");
                         let timer = std::time::Instant::now();
+                        let result = gpu_shared_state.prover.commit_memory_and_prove(
+                            0,
+                            &GpuSharedState::MAIN_BINARY_KEY,
+                            num_instances,
+                            non_determinism_source,
+                        );
                         let (final_register_values, basic_proofs, delegation_proofs) =
-                            gpu_shared_state.prover.commit_memory_and_prove(
-                                0,
-                                &GpuSharedState::MAIN_BINARY_KEY,
-                                num_instances,
-                                non_determinism_source,
-                            );
+                            result.expect("GPU proving failed");
                         let elapsed = timer.elapsed().as_secs_f64();
                         *total_proof_time.as_mut().unwrap() += elapsed;
                         println!("**** proofs generated in {:.3}s ****", elapsed,);
@@ -340,13 +341,14 @@ pub fn create_proofs_internal(
                     {
                         println!("**** proving using GPU ****");
                         let timer = std::time::Instant::now();
+                        let result = gpu_shared_state.prover.commit_memory_and_prove(
+                            0,
+                            &GpuSharedState::RECURSION_BINARY_KEY,
+                            num_instances,
+                            non_determinism_source,
+                        );
                         let (final_register_values, basic_proofs, delegation_proofs) =
-                            gpu_shared_state.prover.commit_memory_and_prove(
-                                0,
-                                &GpuSharedState::RECURSION_BINARY_KEY,
-                                num_instances,
-                                non_determinism_source,
-                            );
+                            result.expect("GPU proving failed");
                         let elapsed = timer.elapsed().as_secs_f64();
                         *total_proof_time.as_mut().unwrap() += elapsed;
                         println!("**** proofs generated in {:.3}s ****", elapsed);
@@ -395,13 +397,14 @@ pub fn create_proofs_internal(
                     {
                         println!("**** proving using GPU ****");
                         let timer = std::time::Instant::now();
+                        let result = gpu_shared_state.prover.commit_memory_and_prove(
+                            0,
+                            &GpuSharedState::RECURSION_BINARY_KEY,
+                            num_instances,
+                            non_determinism_source,
+                        );
                         let (final_register_values, basic_proofs, delegation_proofs) =
-                            gpu_shared_state.prover.commit_memory_and_prove(
-                                0,
-                                &GpuSharedState::RECURSION_BINARY_KEY,
-                                num_instances,
-                                non_determinism_source,
-                            );
+                            result.expect("GPU proving failed");
                         let elapsed = timer.elapsed().as_secs_f64();
                         *total_proof_time.as_mut().unwrap() += elapsed;
                         println!("**** proofs generated in {:.3}s ****", elapsed);
@@ -487,6 +490,7 @@ pub fn create_proofs_internal(
         end_params,
         prev_end_params_output_hash,
         prev_end_params_output,
+        recursion_depth: 0,
     };
 
     (proof_list, proof_metadata)
@@ -522,6 +526,7 @@ pub fn create_recursion_proofs(
             &current_proof_list,
         );
 
+        let recursion_depth = current_proof_metadata.recursion_depth;
         (current_proof_list, current_proof_metadata) = create_proofs_internal(
             &binary,
             non_determinism_data,
@@ -531,6 +536,7 @@ pub fn create_recursion_proofs(
             gpu_shared_state,
             total_proof_time,
         );
+        current_proof_metadata.recursion_depth = recursion_depth + 1;
 
         if let Some(tmp_dir) = tmp_dir {
             let base_tmp_dir = Path::new(tmp_dir).join(format!("recursion_{}", recursion_level));
@@ -611,6 +617,7 @@ pub fn create_final_proofs(
             &current_proof_metadata,
             &current_proof_list,
         );
+        let recursion_depth = current_proof_metadata.recursion_depth;
         (current_proof_list, current_proof_metadata) = create_proofs_internal(
             &binary,
             non_determinism_data,
@@ -620,6 +627,7 @@ pub fn create_final_proofs(
             gpu_shared_state,
             total_proof_time,
         );
+        current_proof_metadata.recursion_depth = recursion_depth + 1;
         if let Some(tmp_dir) = tmp_dir {
             let base_tmp_dir = Path::new(tmp_dir).join(format!("final_{}", final_proof_level));
             if !base_tmp_dir.exists() {
@@ -629,8 +637,10 @@ pub fn create_final_proofs(
             serialize_to_file(&current_proof_metadata, &base_tmp_dir.join("metadata.json"))
         }
 
-        if recursion_mode.finish_second_recursion_layer(&current_proof_metadata, final_proof_level)
-        {
+        let finished = recursion_mode
+            .finish_second_recursion_layer(&current_proof_metadata, final_proof_level)
+            .unwrap_or_else(|error| panic!("{error}"));
+        if finished {
             println!("Stopping 2nd recursion layer.");
             break;
         }